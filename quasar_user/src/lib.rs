@@ -0,0 +1,291 @@
+#![no_std]
+
+use core::ffi::CStr;
+
+mod raw;
+
+/// Números das chamadas, os mesmos do Linux x86_64 (tabela completa em
+/// `rust_kernel/src/syscall.rs`)
+pub mod nr {
+    pub const READ: u64 = 0;
+    pub const WRITE: u64 = 1;
+    pub const OPEN: u64 = 2;
+    pub const CLOSE: u64 = 3;
+    pub const STAT: u64 = 4;
+    pub const FSTAT: u64 = 5;
+    pub const LSEEK: u64 = 8;
+    pub const MMAP: u64 = 9;
+    pub const MUNMAP: u64 = 11;
+    pub const BRK: u64 = 12;
+    pub const NANOSLEEP: u64 = 35;
+    pub const GETPID: u64 = 39;
+    pub const EXIT: u64 = 60;
+    pub const GETDENTS64: u64 = 217;
+    pub const CLOCK_GETTIME: u64 = 228;
+}
+
+/// Código de erro devolvido pelo kernel (o valor de `-rax`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Errno(pub i64);
+
+impl Errno {
+    pub const ENOENT: Errno = Errno(2);
+    pub const ESRCH: Errno = Errno(3);
+    pub const EIO: Errno = Errno(5);
+    pub const EBADF: Errno = Errno(9);
+    pub const EAGAIN: Errno = Errno(11);
+    pub const ENOMEM: Errno = Errno(12);
+    pub const EFAULT: Errno = Errno(14);
+    pub const EBUSY: Errno = Errno(16);
+    pub const EEXIST: Errno = Errno(17);
+    pub const EXDEV: Errno = Errno(18);
+    pub const ENODEV: Errno = Errno(19);
+    pub const ENOTDIR: Errno = Errno(20);
+    pub const EISDIR: Errno = Errno(21);
+    pub const EINVAL: Errno = Errno(22);
+    pub const EMFILE: Errno = Errno(24);
+    pub const EFBIG: Errno = Errno(27);
+    pub const ENOSPC: Errno = Errno(28);
+    pub const EROFS: Errno = Errno(30);
+    pub const EPIPE: Errno = Errno(32);
+    pub const ENAMETOOLONG: Errno = Errno(36);
+    pub const ENOSYS: Errno = Errno(38);
+    pub const ENOTEMPTY: Errno = Errno(39);
+    pub const ELOOP: Errno = Errno(40);
+    pub const EOPNOTSUPP: Errno = Errno(95);
+}
+
+pub type Result<T> = core::result::Result<T, Errno>;
+
+/// Retornos entre -4095 e -1 são erros; o resto é o resultado
+fn check(ret: i64) -> Result<u64> {
+    if (-4095..0).contains(&ret) {
+        Err(Errno(-ret))
+    } else {
+        Ok(ret as u64)
+    }
+}
+
+pub type Fd = u32;
+
+pub const STDIN: Fd = 0;
+pub const STDOUT: Fd = 1;
+pub const STDERR: Fd = 2;
+
+pub const O_RDONLY: u64 = 0o0;
+pub const O_WRONLY: u64 = 0o1;
+pub const O_RDWR: u64 = 0o2;
+pub const O_CREAT: u64 = 0o100;
+pub const O_EXCL: u64 = 0o200;
+pub const O_TRUNC: u64 = 0o1000;
+pub const O_APPEND: u64 = 0o2000;
+pub const O_DIRECTORY: u64 = 0o200000;
+
+pub const SEEK_SET: u32 = 0;
+pub const SEEK_CUR: u32 = 1;
+pub const SEEK_END: u32 = 2;
+
+pub const PROT_NONE: u64 = 0x0;
+pub const PROT_READ: u64 = 0x1;
+pub const PROT_WRITE: u64 = 0x2;
+pub const PROT_EXEC: u64 = 0x4;
+pub const MAP_PRIVATE: u64 = 0x02;
+pub const MAP_FIXED: u64 = 0x10;
+pub const MAP_ANONYMOUS: u64 = 0x20;
+
+pub const CLOCK_REALTIME: u32 = 0;
+pub const CLOCK_MONOTONIC: u32 = 1;
+pub const CLOCK_BOOTTIME: u32 = 7;
+
+pub const S_IFMT: u32 = 0o170000;
+pub const S_IFREG: u32 = 0o100000;
+pub const S_IFDIR: u32 = 0o040000;
+pub const S_IFCHR: u32 = 0o020000;
+pub const S_IFBLK: u32 = 0o060000;
+pub const S_IFLNK: u32 = 0o120000;
+
+pub const DT_CHR: u8 = 2;
+pub const DT_DIR: u8 = 4;
+pub const DT_BLK: u8 = 6;
+pub const DT_REG: u8 = 8;
+pub const DT_LNK: u8 = 10;
+
+/// `struct stat` do Linux x86_64
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stat {
+    pub dev: u64,
+    pub ino: u64,
+    pub nlink: u64,
+    /// Tipo (`S_IF*`) e permissões
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pad: u32,
+    pub rdev: u64,
+    pub size: i64,
+    pub blksize: i64,
+    pub blocks: i64,
+    pub atime: i64,
+    pub atime_nsec: i64,
+    pub mtime: i64,
+    pub mtime_nsec: i64,
+    pub ctime: i64,
+    pub ctime_nsec: i64,
+    unused: [i64; 3],
+}
+
+impl Stat {
+    pub fn is_dir(&self) -> bool {
+        self.mode & S_IFMT == S_IFDIR
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timespec {
+    pub sec: i64,
+    pub nsec: i64,
+}
+
+pub fn read(fd: Fd, buf: &mut [u8]) -> Result<usize> {
+    check(unsafe { raw::syscall3(nr::READ, fd as u64, buf.as_mut_ptr() as u64, buf.len() as u64) }).map(|n| n as usize)
+}
+
+pub fn write(fd: Fd, buf: &[u8]) -> Result<usize> {
+    check(unsafe { raw::syscall3(nr::WRITE, fd as u64, buf.as_ptr() as u64, buf.len() as u64) }).map(|n| n as usize)
+}
+
+/// Escreve `buf` inteiro, repetindo enquanto o kernel aceitar só parte
+pub fn write_all(fd: Fd, mut buf: &[u8]) -> Result<()> {
+    while !buf.is_empty() {
+        let n = write(fd, buf)?;
+        if n == 0 {
+            return Err(Errno::EIO);
+        }
+        buf = &buf[n..];
+    }
+    Ok(())
+}
+
+/// Abre um arquivo ou, com `O_RDONLY`, um diretório (para `getdents`).
+/// Caminhos relativos partem do diretório de onde o programa foi executado.
+pub fn open(path: &CStr, flags: u64, mode: u32) -> Result<Fd> {
+    check(unsafe { raw::syscall3(nr::OPEN, path.as_ptr() as u64, flags, mode as u64) }).map(|fd| fd as Fd)
+}
+
+pub fn close(fd: Fd) -> Result<()> {
+    check(unsafe { raw::syscall1(nr::CLOSE, fd as u64) }).map(|_| ())
+}
+
+pub fn stat(path: &CStr) -> Result<Stat> {
+    let mut stat = Stat::default();
+    check(unsafe { raw::syscall2(nr::STAT, path.as_ptr() as u64, &mut stat as *mut Stat as u64) })?;
+    Ok(stat)
+}
+
+pub fn fstat(fd: Fd) -> Result<Stat> {
+    let mut stat = Stat::default();
+    check(unsafe { raw::syscall2(nr::FSTAT, fd as u64, &mut stat as *mut Stat as u64) })?;
+    Ok(stat)
+}
+
+pub fn lseek(fd: Fd, offset: i64, whence: u32) -> Result<u64> {
+    check(unsafe { raw::syscall3(nr::LSEEK, fd as u64, offset as u64, whence as u64) })
+}
+
+/// Só mapeações anônimas (`MAP_ANONYMOUS`); a memória vem zerada
+pub fn mmap(addr: usize, len: usize, prot: u64, flags: u64) -> Result<*mut u8> {
+    let ret = unsafe { raw::syscall6(nr::MMAP, addr as u64, len as u64, prot, flags, u64::MAX, 0) };
+    check(ret).map(|addr| addr as *mut u8)
+}
+
+/// # Safety
+/// Nada pode continuar usando a memória de `[addr, addr + len)`.
+pub unsafe fn munmap(addr: *mut u8, len: usize) -> Result<()> {
+    check(raw::syscall2(nr::MUNMAP, addr as u64, len as u64)).map(|_| ())
+}
+
+/// Move o fim do heap; retorna o novo fim, ou o atual se não foi possível
+/// (`brk(0)` só consulta)
+pub fn brk(addr: usize) -> usize {
+    unsafe { raw::syscall1(nr::BRK, addr as u64) as usize }
+}
+
+/// Cresce (ou encolhe) o heap em `increment` bytes e retorna o fim antigo
+pub fn sbrk(increment: isize) -> Result<*mut u8> {
+    let current = brk(0);
+    if increment == 0 {
+        return Ok(current as *mut u8);
+    }
+    let wanted = current.checked_add_signed(increment).ok_or(Errno::ENOMEM)?;
+    if brk(wanted) != wanted {
+        return Err(Errno::ENOMEM);
+    }
+    Ok(current as *mut u8)
+}
+
+pub fn nanosleep(duration: &Timespec) -> Result<()> {
+    check(unsafe { raw::syscall2(nr::NANOSLEEP, duration as *const Timespec as u64, 0) }).map(|_| ())
+}
+
+pub fn sleep_ms(ms: u64) -> Result<()> {
+    nanosleep(&Timespec { sec: (ms / 1000) as i64, nsec: (ms % 1000 * 1_000_000) as i64 })
+}
+
+pub fn getpid() -> u64 {
+    unsafe { raw::syscall0(nr::GETPID) as u64 }
+}
+
+pub fn exit(status: i32) -> ! {
+    unsafe {
+        raw::syscall1(nr::EXIT, status as u64);
+    }
+    unreachable!("exit retornou")
+}
+
+pub fn clock_gettime(clock: u32) -> Result<Timespec> {
+    let mut time = Timespec::default();
+    check(unsafe { raw::syscall2(nr::CLOCK_GETTIME, clock as u64, &mut time as *mut Timespec as u64) })?;
+    Ok(time)
+}
+
+/// Lê entradas de um diretório aberto em `buf`; 0 no fim. Percorra o
+/// resultado com `dirents(&buf[..n])`.
+pub fn getdents(fd: Fd, buf: &mut [u8]) -> Result<usize> {
+    let ret = unsafe { raw::syscall3(nr::GETDENTS64, fd as u64, buf.as_mut_ptr() as u64, buf.len() as u64) };
+    check(ret).map(|n| n as usize)
+}
+
+/// Entrada de diretório (`struct linux_dirent64`)
+#[derive(Debug, Clone, Copy)]
+pub struct Dirent<'a> {
+    pub ino: u64,
+    /// Tipo (`DT_*`)
+    pub kind: u8,
+    pub name: &'a str,
+}
+
+/// Entradas contidas num buffer preenchido por `getdents`
+pub fn dirents(buf: &[u8]) -> impl Iterator<Item = Dirent<'_>> {
+    let mut rest = buf;
+    core::iter::from_fn(move || {
+        if rest.len() < 19 {
+            return None;
+        }
+        let reclen = u16::from_le_bytes([rest[16], rest[17]]) as usize;
+        if reclen < 19 {
+            return None;
+        }
+        let record = rest.get(..reclen)?;
+        rest = &rest[reclen..];
+
+        let name = &record[19..];
+        let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+        Some(Dirent {
+            ino: u64::from_le_bytes(record[0..8].try_into().ok()?),
+            kind: record[18],
+            name: core::str::from_utf8(name).ok()?,
+        })
+    })
+}
@@ -0,0 +1,70 @@
+use core::arch::asm;
+
+// `syscall` usa rcx (RIP de retorno) e r11 (RFLAGS); os argumentos vão em
+// rdi, rsi, rdx, r10, r8 e r9 e o resultado volta em rax
+
+pub unsafe fn syscall0(number: u64) -> i64 {
+    let ret: i64;
+    asm!("syscall", inlateout("rax") number as i64 => ret, out("rcx") _, out("r11") _, options(nostack));
+    ret
+}
+
+pub unsafe fn syscall1(number: u64, a0: u64) -> i64 {
+    let ret: i64;
+    asm!(
+        "syscall",
+        inlateout("rax") number as i64 => ret,
+        in("rdi") a0,
+        out("rcx") _,
+        out("r11") _,
+        options(nostack),
+    );
+    ret
+}
+
+pub unsafe fn syscall2(number: u64, a0: u64, a1: u64) -> i64 {
+    let ret: i64;
+    asm!(
+        "syscall",
+        inlateout("rax") number as i64 => ret,
+        in("rdi") a0,
+        in("rsi") a1,
+        out("rcx") _,
+        out("r11") _,
+        options(nostack),
+    );
+    ret
+}
+
+pub unsafe fn syscall3(number: u64, a0: u64, a1: u64, a2: u64) -> i64 {
+    let ret: i64;
+    asm!(
+        "syscall",
+        inlateout("rax") number as i64 => ret,
+        in("rdi") a0,
+        in("rsi") a1,
+        in("rdx") a2,
+        out("rcx") _,
+        out("r11") _,
+        options(nostack),
+    );
+    ret
+}
+
+pub unsafe fn syscall6(number: u64, a0: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64) -> i64 {
+    let ret: i64;
+    asm!(
+        "syscall",
+        inlateout("rax") number as i64 => ret,
+        in("rdi") a0,
+        in("rsi") a1,
+        in("rdx") a2,
+        in("r10") a3,
+        in("r8") a4,
+        in("r9") a5,
+        out("rcx") _,
+        out("r11") _,
+        options(nostack),
+    );
+    ret
+}
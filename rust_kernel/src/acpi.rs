@@ -0,0 +1,239 @@
+use spin::Mutex;
+use x86_64::instructions::port::Port;
+use x86_64::PhysAddr;
+
+use crate::memory;
+use crate::vga_println;
+
+/// Tamanho do cabeçalho comum das tabelas ACPI (SDT)
+const SDT_HEADER_LEN: usize = 36;
+
+/// Registrador de reset da FADT (Generic Address Structure)
+#[derive(Debug, Clone, Copy)]
+pub struct ResetRegister {
+    /// 0 = memória, 1 = porta de E/S
+    pub space: u8,
+    pub address: u64,
+    pub value: u8,
+}
+
+/// Campos da FADT usados para desligar e reiniciar
+#[derive(Debug, Clone, Copy)]
+pub struct Fadt {
+    smi_command: u32,
+    acpi_enable: u8,
+    pub pm1a_control: u16,
+    pub pm1b_control: u16,
+    pub reset: Option<ResetRegister>,
+    /// SLP_TYPa e SLP_TYPb do objeto `\_S5_` da DSDT
+    pub s5: Option<(u16, u16)>,
+}
+
+static FADT: Mutex<Option<Fadt>> = Mutex::new(None);
+
+/// Bytes de uma região física, pelo mapeamento linear do bootloader
+fn phys_bytes(addr: u64, len: usize) -> &'static [u8] {
+    let virt = memory::phys_to_virt(PhysAddr::new(addr));
+    unsafe { core::slice::from_raw_parts(virt.as_ptr(), len) }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn checksum_ok(data: &[u8]) -> bool {
+    data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) == 0
+}
+
+/// Procura a assinatura "RSD PTR " (alinhada em 16 bytes) numa região
+fn scan_rsdp(start: u64, len: usize) -> Option<u64> {
+    let area = phys_bytes(start, len);
+    (0..len.saturating_sub(20))
+        .step_by(16)
+        .find(|&i| &area[i..i + 8] == b"RSD PTR " && checksum_ok(&area[i..i + 20]))
+        .map(|i| start + i as u64)
+}
+
+/// RSDP no primeiro KiB da EBDA ou na área da BIOS (0xE0000..0x100000)
+fn find_rsdp() -> Option<u64> {
+    let ebda = (read_u16(phys_bytes(0x40E, 2), 0) as u64) << 4;
+    if ebda != 0 {
+        if let Some(rsdp) = scan_rsdp(ebda, 1024) {
+            return Some(rsdp);
+        }
+    }
+    scan_rsdp(0xE0000, 0x20000)
+}
+
+/// Tabela inteira a partir do endereço do cabeçalho, se o checksum bater
+fn sdt(addr: u64) -> Option<&'static [u8]> {
+    let len = read_u32(phys_bytes(addr, SDT_HEADER_LEN), 4) as usize;
+    let table = phys_bytes(addr, len);
+    checksum_ok(table).then_some(table)
+}
+
+/// Percorre a RSDT (ponteiros de 32 bits) ou XSDT (64 bits) atrás de `signature`
+fn find_table(signature: &[u8; 4]) -> Option<&'static [u8]> {
+    let rsdp = phys_bytes(find_rsdp()?, 36);
+    let (root, entry_size) = if rsdp[15] >= 2 && read_u64(rsdp, 24) != 0 {
+        (read_u64(rsdp, 24), 8)
+    } else {
+        (read_u32(rsdp, 16) as u64, 4)
+    };
+
+    let root = sdt(root)?;
+    root[SDT_HEADER_LEN..]
+        .chunks_exact(entry_size)
+        .map(|entry| if entry_size == 8 { read_u64(entry, 0) } else { read_u32(entry, 0) as u64 })
+        .filter_map(sdt)
+        .find(|table| &table[..4] == signature)
+}
+
+/// Lê um inteiro AML (BytePrefix/WordPrefix ou as constantes Zero/One)
+fn aml_integer(aml: &[u8], pos: &mut usize) -> Option<u16> {
+    let op = *aml.get(*pos)?;
+    *pos += 1;
+    match op {
+        0x00 => Some(0),
+        0x01 => Some(1),
+        0x0A => {
+            *pos += 1;
+            aml.get(*pos - 1).map(|&b| b as u16)
+        }
+        0x0B => {
+            *pos += 2;
+            aml.get(*pos - 2..*pos).map(|w| read_u16(w, 0))
+        }
+        _ => None,
+    }
+}
+
+/// Extrai SLP_TYPa/b do pacote `Name(\_S5_, Package() {a, b, ...})` da DSDT
+fn parse_s5(dsdt: &[u8]) -> Option<(u16, u16)> {
+    let aml = &dsdt[SDT_HEADER_LEN..];
+    let at = aml.windows(4).position(|w| w == b"_S5_")?;
+
+    // Precisa ser um NameOp (0x08), possivelmente com '\' antes do nome
+    let name_op = match at {
+        0 => return None,
+        1 => aml[0],
+        _ if aml[at - 1] == b'\\' => aml[at - 2],
+        _ => aml[at - 1],
+    };
+    if name_op != 0x08 || aml.get(at + 4) != Some(&0x12) {
+        return None;
+    }
+
+    // PackageOp, PkgLength (os 2 bits altos dizem quantos bytes extras), NumElements
+    let mut pos = at + 5;
+    let extra = (*aml.get(pos)? >> 6) as usize;
+    pos += 1 + extra + 1;
+
+    let a = aml_integer(aml, &mut pos)?;
+    let b = aml_integer(aml, &mut pos)?;
+    Some((a, b))
+}
+
+fn parse_fadt(fadt: &'static [u8]) -> Fadt {
+    const RESET_REG_SUPPORTED: u32 = 1 << 10;
+
+    let flags = if fadt.len() >= 116 { read_u32(fadt, 112) } else { 0 };
+    let reset = (fadt.len() >= 129 && flags & RESET_REG_SUPPORTED != 0).then(|| ResetRegister {
+        space: fadt[116],
+        address: read_u64(fadt, 120),
+        value: fadt[128],
+    });
+
+    // X_DSDT (64 bits) tem precedência sobre o DSDT de 32 bits
+    let x_dsdt = if fadt.len() >= 148 { read_u64(fadt, 140) } else { 0 };
+    let dsdt = if x_dsdt != 0 { x_dsdt } else { read_u32(fadt, 40) as u64 };
+
+    Fadt {
+        smi_command: read_u32(fadt, 48),
+        acpi_enable: fadt[52],
+        pm1a_control: read_u32(fadt, 64) as u16,
+        pm1b_control: read_u32(fadt, 68) as u16,
+        reset,
+        s5: sdt(dsdt).and_then(parse_s5),
+    }
+}
+
+/// Localiza a FADT; sem ela, desligar e reiniciar usam só os métodos legados
+pub fn init() {
+    match find_table(b"FACP") {
+        Some(table) => {
+            let fadt = parse_fadt(table);
+            vga_println!(
+                "ACPI: PM1a em {:#x}, S5 {}, reset {}",
+                fadt.pm1a_control,
+                if fadt.s5.is_some() { "disponível" } else { "ausente" },
+                if fadt.reset.is_some() { "disponível" } else { "ausente" }
+            );
+            *FADT.lock() = Some(fadt);
+        }
+        None => vga_println!("ACPI: FADT não encontrada"),
+    }
+}
+
+pub fn fadt() -> Option<Fadt> {
+    *FADT.lock()
+}
+
+/// Passa o chipset para o modo ACPI (SCI_EN), se o firmware ainda não passou
+fn enable(fadt: &Fadt) {
+    const SCI_EN: u16 = 1;
+
+    let mut pm1a: Port<u16> = Port::new(fadt.pm1a_control);
+    unsafe {
+        if pm1a.read() & SCI_EN != 0 || fadt.smi_command == 0 || fadt.acpi_enable == 0 {
+            return;
+        }
+        Port::<u8>::new(fadt.smi_command as u16).write(fadt.acpi_enable);
+        for _ in 0..1_000_000 {
+            if pm1a.read() & SCI_EN != 0 {
+                break;
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// Entra no estado S5 (desligado); só retorna se o ACPI não estiver disponível
+pub fn power_off() {
+    const SLP_EN: u16 = 1 << 13;
+
+    let Some(fadt) = fadt() else { return };
+    let Some((typ_a, typ_b)) = fadt.s5 else { return };
+    enable(&fadt);
+
+    unsafe {
+        Port::<u16>::new(fadt.pm1a_control).write((typ_a << 10) | SLP_EN);
+        if fadt.pm1b_control != 0 {
+            Port::<u16>::new(fadt.pm1b_control).write((typ_b << 10) | SLP_EN);
+        }
+    }
+}
+
+/// Escreve no registrador de reset da FADT; só retorna se não funcionar
+pub fn reset() {
+    let Some(reset) = fadt().and_then(|f| f.reset) else { return };
+
+    match reset.space {
+        // Espaço de memória
+        0 => unsafe {
+            let virt = memory::phys_to_virt(PhysAddr::new(reset.address));
+            core::ptr::write_volatile(virt.as_mut_ptr::<u8>(), reset.value);
+        },
+        // Espaço de E/S
+        1 => unsafe { Port::<u8>::new(reset.address as u16).write(reset.value) },
+        _ => {}
+    }
+}
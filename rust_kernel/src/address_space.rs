@@ -0,0 +1,405 @@
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::fmt;
+use core::sync::atomic::{AtomicU64, Ordering};
+use x86_64::registers::control::{Cr3, Cr3Flags};
+use x86_64::structures::paging::page_table::PageTableEntry;
+use x86_64::structures::paging::{Page, PageTable, PageTableFlags, PhysFrame};
+use x86_64::{PhysAddr, VirtAddr};
+
+use crate::{memory, task};
+
+const PAGE_SIZE: u64 = 4096;
+
+/// Fim da metade inferior; mapeamentos de usuário ficam abaixo disso
+pub const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
+/// Início das janelas do kernel na metade inferior (heap, MMIO e pilhas das
+/// tarefas). Cada janela fica numa entrada da PML4 que processos não usam.
+const KERNEL_WINDOWS: [u64; 3] = [memory::HEAP_START, memory::MMIO_START, task::STACKS_START];
+
+/// Bytes cobertos por uma entrada da PML4
+const PML4_ENTRY_SIZE: u64 = 1 << 39;
+
+/// Se `[start, end)` toca uma entrada da PML4 usada pelas janelas do kernel
+pub fn overlaps_kernel(start: u64, end: u64) -> bool {
+    if start >= end {
+        return false;
+    }
+    let (first, last) = (start / PML4_ENTRY_SIZE, (end - 1) / PML4_ENTRY_SIZE);
+    KERNEL_WINDOWS.iter().any(|&window| (first..=last).contains(&(window / PML4_ENTRY_SIZE)))
+}
+
+/// PML4 do kernel (a ativa no boot), base de todos os espaços novos
+static KERNEL_PML4: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressSpaceError {
+    OutOfMemory,
+    /// Região fora da metade inferior ou desalinhada
+    BadRange,
+    /// Já existe uma VMA cobrindo parte da região
+    Overlap,
+    /// A página não pertence a nenhuma VMA
+    NotInVma,
+    /// A página já está mapeada (por este espaço ou pelo kernel)
+    AlreadyMapped,
+}
+
+impl fmt::Display for AddressSpaceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddressSpaceError::OutOfMemory => write!(f, "memória insuficiente"),
+            AddressSpaceError::BadRange => write!(f, "região inválida"),
+            AddressSpaceError::Overlap => write!(f, "região sobreposta"),
+            AddressSpaceError::NotInVma => write!(f, "endereço fora das regiões do processo"),
+            AddressSpaceError::AlreadyMapped => write!(f, "página já mapeada"),
+        }
+    }
+}
+
+/// Região virtual contígua do processo, com as mesmas permissões
+#[derive(Debug, Clone)]
+pub struct Vma {
+    pub start: VirtAddr,
+    pub end: VirtAddr,
+    pub flags: PageTableFlags,
+    pub name: &'static str,
+}
+
+impl Vma {
+    pub fn contains(&self, addr: VirtAddr) -> bool {
+        self.start <= addr && addr < self.end
+    }
+
+    pub fn pages(&self) -> impl Iterator<Item = Page> {
+        Page::range(Page::containing_address(self.start), Page::containing_address(self.end))
+    }
+
+    /// `next` começa onde esta termina, com as mesmas permissões e nome
+    fn joins(&self, next: &Vma) -> bool {
+        self.end == next.start && self.flags == next.flags && self.name == next.name
+    }
+}
+
+/// Guarda a PML4 ativa no boot; chamado depois de `memory::init_globals`
+pub fn init() {
+    let (frame, _) = Cr3::read();
+    KERNEL_PML4.store(frame.start_address().as_u64(), Ordering::Relaxed);
+}
+
+fn kernel_pml4() -> PhysFrame {
+    PhysFrame::containing_address(PhysAddr::new(KERNEL_PML4.load(Ordering::Relaxed)))
+}
+
+/// Volta para as tabelas do kernel (por exemplo, antes de destruir um espaço)
+pub fn activate_kernel() {
+    let kernel = kernel_pml4();
+    if Cr3::read().0 != kernel {
+        unsafe { Cr3::write(kernel, Cr3Flags::empty()) };
+    }
+}
+
+fn table(frame: PhysFrame) -> &'static mut PageTable {
+    unsafe { &mut *memory::phys_to_virt(frame.start_address()).as_mut_ptr::<PageTable>() }
+}
+
+/// Índices de uma página nos quatro níveis, da PML4 à tabela final
+fn indices(page: Page) -> [usize; 4] {
+    [
+        usize::from(page.p4_index()),
+        usize::from(page.p3_index()),
+        usize::from(page.p2_index()),
+        usize::from(page.p1_index()),
+    ]
+}
+
+/// Espaço de endereçamento de um processo: uma PML4 própria que compartilha
+/// as tabelas do kernel e as mapeações de usuário descritas pelas VMAs.
+///
+/// As tabelas intermediárias do kernel são compartilhadas até que uma
+/// mapeação de usuário precise alterá-las; aí a tabela é copiada e passa a
+/// ser do processo. Na destruição, só os frames das VMAs e as tabelas
+/// próprias voltam ao alocador.
+pub struct AddressSpace {
+    pml4: PhysFrame,
+    /// Tabelas intermediárias criadas ou copiadas por este espaço
+    tables: BTreeSet<PhysFrame>,
+    vmas: Vec<Vma>,
+}
+
+impl AddressSpace {
+    /// Nova PML4 com todas as entradas do kernel
+    pub fn new() -> Result<Self, AddressSpaceError> {
+        let pml4 = memory::alloc_zeroed_frame().ok_or(AddressSpaceError::OutOfMemory)?;
+        table(pml4).clone_from(table(kernel_pml4()));
+        Ok(Self { pml4, tables: BTreeSet::new(), vmas: Vec::new() })
+    }
+
+    pub fn pml4(&self) -> PhysFrame {
+        self.pml4
+    }
+
+    pub fn vmas(&self) -> &[Vma] {
+        &self.vmas
+    }
+
+    /// Carrega a PML4 em CR3, se ainda não for a ativa
+    pub fn activate(&self) {
+        if Cr3::read().0 != self.pml4 {
+            unsafe { Cr3::write(self.pml4, Cr3Flags::empty()) };
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        Cr3::read().0 == self.pml4
+    }
+
+    pub fn find_vma(&self, addr: VirtAddr) -> Option<&Vma> {
+        self.vmas.iter().find(|vma| vma.contains(addr))
+    }
+
+    /// Registra uma região (alinhada em páginas) sem mapear nada ainda
+    pub fn insert_vma(&mut self, vma: Vma) -> Result<(), AddressSpaceError> {
+        let (start, end) = (vma.start.as_u64(), vma.end.as_u64());
+        if start >= end || end > USER_SPACE_END || start % PAGE_SIZE != 0 || end % PAGE_SIZE != 0 {
+            return Err(AddressSpaceError::BadRange);
+        }
+        if overlaps_kernel(start, end) {
+            return Err(AddressSpaceError::BadRange);
+        }
+        if self.vmas.iter().any(|other| vma.start < other.end && other.start < vma.end) {
+            return Err(AddressSpaceError::Overlap);
+        }
+        let at = self.vmas.partition_point(|other| other.start < vma.start);
+        self.vmas.insert(at, vma);
+
+        // Regiões vizinhas iguais (o heap crescendo aos poucos) viram uma só
+        if at + 1 < self.vmas.len() && self.vmas[at].joins(&self.vmas[at + 1]) {
+            let next = self.vmas.remove(at + 1);
+            self.vmas[at].end = next.end;
+        }
+        if at > 0 && self.vmas[at - 1].joins(&self.vmas[at]) {
+            let vma = self.vmas.remove(at);
+            self.vmas[at - 1].end = vma.end;
+        }
+        Ok(())
+    }
+
+    /// Cria uma VMA e a preenche com frames novos e zerados. Se faltar
+    /// memória no meio, desfaz tudo e a VMA não fica registrada.
+    pub fn allocate(&mut self, vma: Vma) -> Result<(), AddressSpaceError> {
+        let (start, end, flags) = (vma.start, vma.end, vma.flags);
+        let pages: Vec<Page> = vma.pages().collect();
+        self.insert_vma(vma)?;
+        for (i, &page) in pages.iter().enumerate() {
+            let result = memory::alloc_zeroed_frame()
+                .ok_or(AddressSpaceError::OutOfMemory)
+                .and_then(|frame| {
+                    self.map(page, frame, flags)
+                        .inspect_err(|_| unsafe { memory::free_frame(frame) })
+                });
+            if let Err(e) = result {
+                self.remove_vma(start, end, &pages[..i]);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Desfaz uma VMA cujo preenchimento falhou: tira `[start, end)` das
+    /// regiões e libera só as páginas em `mapped`. As outras entradas da
+    /// região podem ser do kernel (a que fez `map` falhar, por exemplo) e
+    /// não podem chegar ao `Drop`.
+    pub fn remove_vma(&mut self, start: VirtAddr, end: VirtAddr, mapped: &[Page]) {
+        self.split_out(start, end);
+        for &page in mapped {
+            self.release(page);
+        }
+    }
+
+    /// Primeiro intervalo livre de `len` bytes (múltiplo de página) em
+    /// `from` ou acima, sem cruzar nenhuma VMA
+    pub fn find_free(&self, from: VirtAddr, len: u64) -> Option<VirtAddr> {
+        let mut start = from.align_up(PAGE_SIZE).as_u64();
+        for vma in &self.vmas {
+            if vma.end.as_u64() <= start {
+                continue;
+            }
+            if start.checked_add(len)? <= vma.start.as_u64() {
+                break;
+            }
+            start = vma.end.as_u64();
+        }
+        (start.checked_add(len)? < USER_SPACE_END).then(|| VirtAddr::new(start))
+    }
+
+    /// Entrada de uma tabela filha, criando a tabela ou copiando a do kernel
+    /// se ela ainda for compartilhada
+    fn child_table(&mut self, parent: PhysFrame, index: usize) -> Result<PhysFrame, AddressSpaceError> {
+        let entry = &mut table(parent)[index];
+        let path_flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE;
+
+        if entry.is_unused() {
+            let frame = memory::alloc_zeroed_frame().ok_or(AddressSpaceError::OutOfMemory)?;
+            self.tables.insert(frame);
+            entry.set_frame(frame, path_flags);
+            return Ok(frame);
+        }
+        if entry.flags().contains(PageTableFlags::HUGE_PAGE) {
+            return Err(AddressSpaceError::AlreadyMapped);
+        }
+
+        // O caminho até uma página de usuário não pode restringir acesso nem execução
+        let flags = (entry.flags() | path_flags) - PageTableFlags::NO_EXECUTE;
+        let current = entry.frame().map_err(|_| AddressSpaceError::AlreadyMapped)?;
+        if self.tables.contains(&current) {
+            entry.set_flags(flags);
+            return Ok(current);
+        }
+
+        let copy = memory::alloc_zeroed_frame().ok_or(AddressSpaceError::OutOfMemory)?;
+        table(copy).clone_from(table(current));
+        self.tables.insert(copy);
+        entry.set_frame(copy, flags);
+        Ok(copy)
+    }
+
+    /// Mapeia `frame` em `page`, que precisa estar numa VMA. O frame passa a
+    /// pertencer ao espaço e é liberado na destruição.
+    pub fn map(&mut self, page: Page, frame: PhysFrame, flags: PageTableFlags) -> Result<(), AddressSpaceError> {
+        if self.find_vma(page.start_address()).is_none() {
+            return Err(AddressSpaceError::NotInVma);
+        }
+
+        let [i4, i3, i2, i1] = indices(page);
+        let p3 = self.child_table(self.pml4, i4)?;
+        let p2 = self.child_table(p3, i3)?;
+        let p1 = self.child_table(p2, i2)?;
+
+        let entry = &mut table(p1)[i1];
+        if !entry.is_unused() {
+            return Err(AddressSpaceError::AlreadyMapped);
+        }
+        entry.set_frame(frame, flags | PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE);
+        if self.is_active() {
+            x86_64::instructions::tlb::flush(page.start_address());
+        }
+        Ok(())
+    }
+
+    /// Entrada final de uma página de usuário, se as tabelas até ela existirem
+    fn leaf(&self, page: Page) -> Option<&'static mut PageTableEntry> {
+        let [i4, i3, i2, i1] = indices(page);
+        let mut frame = self.pml4;
+        for index in [i4, i3, i2] {
+            let entry = &table(frame)[index];
+            if entry.is_unused() || entry.flags().contains(PageTableFlags::HUGE_PAGE) {
+                return None;
+            }
+            frame = entry.frame().ok()?;
+        }
+        let entry = &mut table(frame)[i1];
+        (!entry.is_unused()).then_some(entry)
+    }
+
+    /// Endereço físico correspondente, se a página estiver mapeada
+    pub fn translate(&self, addr: VirtAddr) -> Option<PhysAddr> {
+        let entry = self.leaf(Page::containing_address(addr))?;
+        Some(entry.addr() + (addr.as_u64() % PAGE_SIZE))
+    }
+
+    /// Desmapeia e libera os frames de `[start, end)`, encolhendo ou
+    /// dividindo as VMAs afetadas
+    pub fn unmap(&mut self, start: VirtAddr, end: VirtAddr) -> Result<(), AddressSpaceError> {
+        if start >= end || end.as_u64() > USER_SPACE_END || start.as_u64() % PAGE_SIZE != 0 {
+            return Err(AddressSpaceError::BadRange);
+        }
+        let end = end.align_up(PAGE_SIZE);
+
+        // Só as partes das VMAs dentro da região são liberadas: o resto da
+        // metade inferior pode ter mapeações do kernel
+        let removed = self.split_out(start, end);
+        for page in removed.iter().flat_map(Vma::pages) {
+            self.release(page);
+        }
+        Ok(())
+    }
+
+    /// Tira `[start, end)` das VMAs, dividindo as que cruzam os limites, e
+    /// retorna as partes removidas
+    fn split_out(&mut self, start: VirtAddr, end: VirtAddr) -> Vec<Vma> {
+        let mut kept = Vec::new();
+        let mut removed = Vec::new();
+        for vma in self.vmas.drain(..) {
+            if vma.end <= start || end <= vma.start {
+                kept.push(vma);
+                continue;
+            }
+            if vma.start < start {
+                kept.push(Vma { end: start, ..vma.clone() });
+            }
+            if end < vma.end {
+                kept.push(Vma { start: end, ..vma.clone() });
+            }
+            removed.push(Vma { start: vma.start.max(start), end: vma.end.min(end), ..vma });
+        }
+        self.vmas = kept;
+        removed
+    }
+
+    /// Desmapeia uma página de usuário e devolve seu frame
+    fn release(&mut self, page: Page) {
+        if let Some(entry) = self.leaf(page) {
+            let frame = entry.frame().ok();
+            entry.set_unused();
+            if self.is_active() {
+                x86_64::instructions::tlb::flush(page.start_address());
+            }
+            if let Some(frame) = frame {
+                unsafe { memory::free_frame(frame) };
+            }
+        }
+    }
+
+    /// Verifica se `[addr, addr + len)` está inteiro em VMAs do processo (e
+    /// graváveis, se `write`), para validar ponteiros vindos do usuário
+    pub fn check_range(&self, addr: u64, len: u64, write: bool) -> bool {
+        let Some(end) = addr.checked_add(len) else { return false };
+        if end > USER_SPACE_END {
+            return false;
+        }
+        let mut at = addr;
+        while at < end {
+            let Some(vma) = self.find_vma(VirtAddr::new(at)) else { return false };
+            if write && !vma.flags.contains(PageTableFlags::WRITABLE) {
+                return false;
+            }
+            at = vma.end.as_u64();
+        }
+        true
+    }
+}
+
+impl Drop for AddressSpace {
+    fn drop(&mut self) {
+        if self.is_active() {
+            activate_kernel();
+        }
+
+        let vmas = core::mem::take(&mut self.vmas);
+        for vma in &vmas {
+            for page in vma.pages() {
+                if let Some(entry) = self.leaf(page) {
+                    if let Ok(frame) = entry.frame() {
+                        unsafe { memory::free_frame(frame) };
+                    }
+                    entry.set_unused();
+                }
+            }
+        }
+        for &frame in &self.tables {
+            unsafe { memory::free_frame(frame) };
+        }
+        unsafe { memory::free_frame(self.pml4) };
+    }
+}
@@ -1,11 +1,36 @@
+use core::alloc::{GlobalAlloc, Layout};
 use linked_list_allocator::LockedHeap;
+use x86_64::instructions::interrupts;
+
+/// Heap com as interrupções desligadas enquanto o lock está preso: uma tarefa
+/// preemptada no meio de uma alocação não pode travar quem aloca numa IRQ ou
+/// numa seção sem interrupções
+struct IrqSafeHeap(LockedHeap);
+
+unsafe impl GlobalAlloc for IrqSafeHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        interrupts::without_interrupts(|| self.0.alloc(layout))
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        interrupts::without_interrupts(|| self.0.dealloc(ptr, layout))
+    }
+}
 
 #[global_allocator]
-static ALLOCATOR: LockedHeap = LockedHeap::empty();
+static ALLOCATOR: IrqSafeHeap = IrqSafeHeap(LockedHeap::empty());
 
 /// Inicializa o heap no endereço e tamanho definidos
 pub fn init_heap_allocator(heap_start: usize, heap_size: usize) {
     unsafe {
-        ALLOCATOR.lock().init(heap_start, heap_size);
+        ALLOCATOR.0.lock().init(heap_start, heap_size);
     }
 }
+
+/// Retorna (tamanho total, bytes em uso) do heap do kernel
+pub fn heap_stats() -> (usize, usize) {
+    interrupts::without_interrupts(|| {
+        let heap = ALLOCATOR.0.lock();
+        (heap.size(), heap.used())
+    })
+}
@@ -0,0 +1,365 @@
+use alloc::{boxed::Box, string::String};
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts as x86_interrupts;
+use x86_64::instructions::port::Port;
+
+use crate::block::{self, BlockDevice, SECTOR_SIZE};
+use crate::interrupts;
+use crate::vga_println;
+
+// Registradores relativos à porta base de I/O
+const REG_DATA: u16 = 0;
+const REG_SECTOR_COUNT: u16 = 2;
+const REG_LBA_LOW: u16 = 3;
+const REG_LBA_MID: u16 = 4;
+const REG_LBA_HIGH: u16 = 5;
+const REG_DRIVE: u16 = 6;
+const REG_STATUS: u16 = 7;
+const REG_COMMAND: u16 = 7;
+
+// Bits do registrador de status
+const STATUS_ERR: u8 = 0x01;
+const STATUS_DRQ: u8 = 0x08;
+const STATUS_DF: u8 = 0x20;
+const STATUS_BSY: u8 = 0x80;
+
+// Comandos ATA
+const CMD_READ_PIO: u8 = 0x20;
+const CMD_READ_PIO_EXT: u8 = 0x24;
+const CMD_WRITE_PIO: u8 = 0x30;
+const CMD_WRITE_PIO_EXT: u8 = 0x34;
+const CMD_CACHE_FLUSH: u8 = 0xE7;
+const CMD_CACHE_FLUSH_EXT: u8 = 0xEA;
+const CMD_IDENTIFY: u8 = 0xEC;
+
+/// Limite de iterações ao esperar o disco (evita travar se não houver IRQ)
+const TIMEOUT: u32 = 1_000_000;
+
+/// Canal IDE (primário ou secundário) com suas portas e flag de IRQ
+pub struct AtaChannel {
+    io_base: u16,
+    control_base: u16,
+    lock: Mutex<()>,
+    irq_fired: AtomicBool,
+}
+
+pub static PRIMARY: AtaChannel = AtaChannel::new(0x1F0, 0x3F6);
+pub static SECONDARY: AtaChannel = AtaChannel::new(0x170, 0x376);
+
+impl AtaChannel {
+    const fn new(io_base: u16, control_base: u16) -> Self {
+        Self {
+            io_base,
+            control_base,
+            lock: Mutex::new(()),
+            irq_fired: AtomicBool::new(false),
+        }
+    }
+
+    fn read_reg(&self, reg: u16) -> u8 {
+        unsafe { Port::<u8>::new(self.io_base + reg).read() }
+    }
+
+    fn write_reg(&self, reg: u16, value: u8) {
+        unsafe { Port::<u8>::new(self.io_base + reg).write(value) }
+    }
+
+    fn read_data(&self) -> u16 {
+        unsafe { Port::<u16>::new(self.io_base + REG_DATA).read() }
+    }
+
+    fn write_data(&self, value: u16) {
+        unsafe { Port::<u16>::new(self.io_base + REG_DATA).write(value) }
+    }
+
+    /// Status alternativo: lê sem reconhecer a interrupção
+    fn alt_status(&self) -> u8 {
+        unsafe { Port::<u8>::new(self.control_base).read() }
+    }
+
+    /// Habilita (nIEN = 0) as interrupções do canal
+    fn enable_irq(&self) {
+        unsafe { Port::<u8>::new(self.control_base).write(0u8) }
+    }
+
+    /// Espera ~400ns lendo o status alternativo 4 vezes
+    fn delay_400ns(&self) {
+        for _ in 0..4 {
+            self.alt_status();
+        }
+    }
+
+    fn wait_not_busy(&self) -> Result<u8, &'static str> {
+        for _ in 0..TIMEOUT {
+            let status = self.alt_status();
+            if status & STATUS_BSY == 0 {
+                return Ok(status);
+            }
+        }
+        Err("timeout esperando BSY")
+    }
+
+    /// Espera o disco ficar pronto para transferir dados (DRQ)
+    fn wait_drq(&self) -> Result<(), &'static str> {
+        let status = self.wait_not_busy()?;
+        if status & (STATUS_ERR | STATUS_DF) != 0 {
+            return Err("erro no disco ATA");
+        }
+        for _ in 0..TIMEOUT {
+            let status = self.alt_status();
+            if status & (STATUS_ERR | STATUS_DF) != 0 {
+                return Err("erro no disco ATA");
+            }
+            if status & STATUS_DRQ != 0 {
+                return Ok(());
+            }
+        }
+        Err("timeout esperando DRQ")
+    }
+
+    /// Espera a IRQ do canal; se ela não vier (ou as interrupções estiverem
+    /// desligadas, como no boot), espera o BSY baixar por polling
+    fn wait_irq(&self) -> Result<(), &'static str> {
+        let mut fired = false;
+        if x86_interrupts::are_enabled() {
+            for _ in 0..TIMEOUT {
+                if self.irq_fired.swap(false, Ordering::AcqRel) {
+                    fired = true;
+                    break;
+                }
+                core::hint::spin_loop();
+            }
+        }
+        if !fired {
+            self.delay_400ns();
+            self.wait_not_busy()?;
+        }
+        // Lê o status normal para reconhecer a interrupção
+        let status = self.read_reg(REG_STATUS);
+        if status & (STATUS_ERR | STATUS_DF) != 0 {
+            return Err("erro no disco ATA");
+        }
+        Ok(())
+    }
+
+    /// Chamado pelo handler da IRQ14/IRQ15
+    pub fn handle_irq(&self) {
+        self.read_reg(REG_STATUS);
+        self.irq_fired.store(true, Ordering::Release);
+    }
+
+    fn select(&self, slave: bool, head: u8) {
+        self.write_reg(REG_DRIVE, head | if slave { 0x10 } else { 0 });
+        self.delay_400ns();
+    }
+
+    /// Programa os registradores de LBA/contagem e envia o comando
+    fn setup_transfer(&self, slave: bool, lba48: bool, lba: u64, count: u16, cmd: u8) {
+        self.irq_fired.store(false, Ordering::Release);
+
+        if lba48 {
+            self.select(slave, 0x40);
+            // Bytes altos primeiro, depois os baixos
+            self.write_reg(REG_SECTOR_COUNT, (count >> 8) as u8);
+            self.write_reg(REG_LBA_LOW, (lba >> 24) as u8);
+            self.write_reg(REG_LBA_MID, (lba >> 32) as u8);
+            self.write_reg(REG_LBA_HIGH, (lba >> 40) as u8);
+        } else {
+            self.select(slave, 0xE0 | ((lba >> 24) as u8 & 0x0F));
+        }
+
+        self.write_reg(REG_SECTOR_COUNT, count as u8);
+        self.write_reg(REG_LBA_LOW, lba as u8);
+        self.write_reg(REG_LBA_MID, (lba >> 8) as u8);
+        self.write_reg(REG_LBA_HIGH, (lba >> 16) as u8);
+        self.write_reg(REG_COMMAND, cmd);
+    }
+}
+
+/// Um disco ATA detectado via IDENTIFY
+pub struct AtaDrive {
+    name: String,
+    channel: &'static AtaChannel,
+    slave: bool,
+    lba48: bool,
+    sectors: u64,
+    pub model: String,
+}
+
+impl AtaDrive {
+    /// Envia IDENTIFY e retorna o disco, se existir um disco ATA nessa posição
+    pub fn identify(name: &str, channel: &'static AtaChannel, slave: bool) -> Option<Self> {
+        let _guard = channel.lock.lock();
+
+        channel.select(slave, 0xA0);
+        channel.write_reg(REG_SECTOR_COUNT, 0);
+        channel.write_reg(REG_LBA_LOW, 0);
+        channel.write_reg(REG_LBA_MID, 0);
+        channel.write_reg(REG_LBA_HIGH, 0);
+        channel.write_reg(REG_COMMAND, CMD_IDENTIFY);
+
+        // Status 0 (ou barramento flutuando em 0xFF) = nenhum disco
+        let status = channel.read_reg(REG_STATUS);
+        if status == 0 || status == 0xFF {
+            return None;
+        }
+        channel.wait_not_busy().ok()?;
+
+        // ATAPI/SATA respondem com assinatura em LBA mid/high
+        if channel.read_reg(REG_LBA_MID) != 0 || channel.read_reg(REG_LBA_HIGH) != 0 {
+            return None;
+        }
+        channel.wait_drq().ok()?;
+
+        let mut id = [0u16; 256];
+        for word in id.iter_mut() {
+            *word = channel.read_data();
+        }
+
+        let lba48 = id[83] & (1 << 10) != 0;
+        let sectors = if lba48 {
+            (id[100] as u64)
+                | (id[101] as u64) << 16
+                | (id[102] as u64) << 32
+                | (id[103] as u64) << 48
+        } else {
+            (id[60] as u64) | (id[61] as u64) << 16
+        };
+
+        // Modelo: palavras 27..47, cada uma com os bytes trocados
+        let mut model = String::new();
+        for word in &id[27..47] {
+            model.push((word >> 8) as u8 as char);
+            model.push((word & 0xFF) as u8 as char);
+        }
+        let model = String::from(model.trim());
+
+        channel.enable_irq();
+
+        Some(Self {
+            name: name.into(),
+            channel,
+            slave,
+            lba48,
+            sectors,
+            model,
+        })
+    }
+
+    fn check_range(&self, lba: u64, len: usize) -> Result<u64, &'static str> {
+        if len % SECTOR_SIZE != 0 {
+            return Err("buffer não é múltiplo do setor");
+        }
+        let count = (len / SECTOR_SIZE) as u64;
+        let end = lba.checked_add(count).ok_or("LBA fora do disco")?;
+        if end > self.sectors {
+            return Err("LBA fora do disco");
+        }
+        if !self.lba48 && end > (1 << 28) {
+            return Err("LBA exige 48 bits");
+        }
+        Ok(count)
+    }
+
+    /// Máximo de setores por comando (contagem 0 significa 256/65536)
+    fn max_per_command(&self) -> u64 {
+        if self.lba48 { 65536 } else { 256 }
+    }
+}
+
+impl BlockDevice for AtaDrive {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sector_count(&self) -> u64 {
+        self.sectors
+    }
+
+    fn read_sectors(&self, lba: u64, buf: &mut [u8]) -> Result<(), &'static str> {
+        let total = self.check_range(lba, buf.len())?;
+        let cmd = if self.lba48 { CMD_READ_PIO_EXT } else { CMD_READ_PIO };
+        let _guard = self.channel.lock.lock();
+
+        let mut done = 0u64;
+        while done < total {
+            let count = core::cmp::min(total - done, self.max_per_command());
+            self.channel.setup_transfer(self.slave, self.lba48, lba + done, count as u16, cmd);
+
+            for i in 0..count {
+                // Uma IRQ por setor pronto para leitura
+                self.channel.wait_irq()?;
+                self.channel.wait_drq()?;
+
+                let offset = ((done + i) as usize) * SECTOR_SIZE;
+                for chunk in buf[offset..offset + SECTOR_SIZE].chunks_mut(2) {
+                    let word = self.channel.read_data();
+                    chunk[0] = word as u8;
+                    chunk[1] = (word >> 8) as u8;
+                }
+            }
+            done += count;
+        }
+
+        Ok(())
+    }
+
+    fn write_sectors(&self, lba: u64, buf: &[u8]) -> Result<(), &'static str> {
+        let total = self.check_range(lba, buf.len())?;
+        let cmd = if self.lba48 { CMD_WRITE_PIO_EXT } else { CMD_WRITE_PIO };
+        let _guard = self.channel.lock.lock();
+
+        let mut done = 0u64;
+        while done < total {
+            let count = core::cmp::min(total - done, self.max_per_command());
+            self.channel.setup_transfer(self.slave, self.lba48, lba + done, count as u16, cmd);
+
+            for i in 0..count {
+                self.channel.wait_drq()?;
+
+                let offset = ((done + i) as usize) * SECTOR_SIZE;
+                for chunk in buf[offset..offset + SECTOR_SIZE].chunks(2) {
+                    self.channel.write_data(u16::from_le_bytes([chunk[0], chunk[1]]));
+                }
+                // A IRQ confirma que o setor foi gravado
+                self.channel.wait_irq()?;
+            }
+            done += count;
+        }
+
+        let flush = if self.lba48 { CMD_CACHE_FLUSH_EXT } else { CMD_CACHE_FLUSH };
+        self.channel.irq_fired.store(false, Ordering::Release);
+        self.channel.write_reg(REG_COMMAND, flush);
+        self.channel.wait_irq()?;
+        self.channel.wait_not_busy()?;
+
+        Ok(())
+    }
+}
+
+/// Detecta os quatro discos IDE possíveis e registra os encontrados
+pub fn init() {
+    let slots: [(&str, &'static AtaChannel, bool); 4] = [
+        ("hda", &PRIMARY, false),
+        ("hdb", &PRIMARY, true),
+        ("hdc", &SECONDARY, false),
+        ("hdd", &SECONDARY, true),
+    ];
+
+    for (name, channel, slave) in slots {
+        if let Some(drive) = AtaDrive::identify(name, channel, slave) {
+            // IRQ14 para o canal primário, IRQ15 para o secundário
+            interrupts::unmask_irq(if core::ptr::eq(channel, &PRIMARY) { 14 } else { 15 });
+            vga_println!(
+                "ATA {}: {} ({} setores, {})",
+                name,
+                drive.model,
+                drive.sectors,
+                if drive.lba48 { "LBA48" } else { "LBA28" }
+            );
+            block::register(Box::leak(Box::new(drive)));
+        }
+    }
+}
+
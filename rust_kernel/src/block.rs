@@ -0,0 +1,100 @@
+use alloc::{string::String, vec::Vec};
+use spin::Mutex;
+
+use crate::sync::IrqSpinLock;
+
+/// Tamanho de um setor lógico em bytes
+pub const SECTOR_SIZE: usize = 512;
+
+/// Dispositivo de blocos endereçado por LBA (setores de 512 bytes)
+pub trait BlockDevice: Send + Sync {
+    /// Nome do dispositivo (ex: "hda", "ram0")
+    fn name(&self) -> &str;
+
+    /// Quantidade total de setores do dispositivo
+    fn sector_count(&self) -> u64;
+
+    /// Lê `buf.len() / SECTOR_SIZE` setores a partir de `lba`
+    fn read_sectors(&self, lba: u64, buf: &mut [u8]) -> Result<(), &'static str>;
+
+    /// Escreve `buf.len() / SECTOR_SIZE` setores a partir de `lba`
+    fn write_sectors(&self, lba: u64, buf: &[u8]) -> Result<(), &'static str>;
+
+    /// O conteúdo se perde ao desligar (ex: `RamDisk`)
+    fn volatile(&self) -> bool {
+        false
+    }
+}
+
+/// Disco em memória (usado para a imagem FAT embutida no kernel)
+pub struct RamDisk {
+    name: String,
+    data: Mutex<&'static mut [u8]>,
+}
+
+impl RamDisk {
+    pub fn new(name: &str, data: &'static mut [u8]) -> Self {
+        Self {
+            name: name.into(),
+            data: Mutex::new(data),
+        }
+    }
+
+    /// Valida que o buffer cabe no disco e retorna o intervalo em bytes
+    fn byte_range(&self, len: usize, lba: u64, buf_len: usize) -> Result<(usize, usize), &'static str> {
+        if buf_len % SECTOR_SIZE != 0 {
+            return Err("buffer não é múltiplo do setor");
+        }
+        let start = lba as usize * SECTOR_SIZE;
+        let end = start + buf_len;
+        if end > len {
+            return Err("LBA fora do disco");
+        }
+        Ok((start, end))
+    }
+}
+
+impl BlockDevice for RamDisk {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sector_count(&self) -> u64 {
+        (self.data.lock().len() / SECTOR_SIZE) as u64
+    }
+
+    fn read_sectors(&self, lba: u64, buf: &mut [u8]) -> Result<(), &'static str> {
+        let data = self.data.lock();
+        let (start, end) = self.byte_range(data.len(), lba, buf.len())?;
+        buf.copy_from_slice(&data[start..end]);
+        Ok(())
+    }
+
+    fn write_sectors(&self, lba: u64, buf: &[u8]) -> Result<(), &'static str> {
+        let mut data = self.data.lock();
+        let (start, end) = self.byte_range(data.len(), lba, buf.len())?;
+        data[start..end].copy_from_slice(buf);
+        Ok(())
+    }
+
+    fn volatile(&self) -> bool {
+        true
+    }
+}
+
+static DEVICES: IrqSpinLock<Vec<&'static dyn BlockDevice>> = IrqSpinLock::named("block_devices", Vec::new());
+
+/// Registra um dispositivo de blocos para que possa ser montado pelo nome
+pub fn register(device: &'static dyn BlockDevice) {
+    DEVICES.lock().push(device);
+}
+
+/// Procura um dispositivo registrado pelo nome
+pub fn find(name: &str) -> Option<&'static dyn BlockDevice> {
+    DEVICES.lock().iter().copied().find(|d| d.name() == name)
+}
+
+/// Retorna todos os dispositivos registrados
+pub fn devices() -> Vec<&'static dyn BlockDevice> {
+    DEVICES.lock().clone()
+}
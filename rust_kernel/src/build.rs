@@ -1,8 +1,23 @@
 use std::fs;
+use std::path::Path;
 
 fn main() {
     println!("cargo:rerun-if-changed=floppy.img");
+    println!("cargo:rerun-if-changed=initramfs.cpio");
+    println!("cargo:rerun-if-changed=initramfs.tar");
 
     let data = fs::read("floppy.img").expect("floppy.img não encontrado");
     println!("cargo:rustc-env=FAT12_BYTES={}", base64::encode(data));
+
+    // initramfs opcional: copiado para OUT_DIR e embutido com include_bytes!
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR não definido");
+    let target = Path::new(&out_dir).join("initramfs.bin");
+
+    let archive = ["initramfs.cpio", "initramfs.tar"]
+        .iter()
+        .find_map(|name| fs::read(name).ok())
+        .unwrap_or_default();
+    fs::write(&target, archive).expect("falha ao gravar initramfs.bin");
+
+    println!("cargo:rustc-env=INITRAMFS_PATH={}", target.display());
 }
@@ -0,0 +1,459 @@
+use alloc::{format, string::{String, ToString}, vec::Vec};
+
+use crate::shell::{self, parse_flags, Command, CommandResult, Context};
+use crate::vfs::{self, FileType, VfsError, VFS_INSTANCE};
+use crate::{sh_print, sh_println};
+
+/// Linhas mostradas por `head` e `tail` sem `-n`
+const DEFAULT_LINES: usize = 10;
+
+/// Bytes por linha do `hexdump`/`xxd`
+const DUMP_WIDTH: usize = 16;
+
+/// Registra os utilitários de arquivo
+pub fn register_commands() {
+    let commands = [
+        Command { name: "pwd", usage: "", help: "mostra o diretório atual", min_args: 0, max_args: Some(0), handler: cmd_pwd },
+        Command { name: "echo", usage: "[-n] [texto]...", help: "imprime os argumentos (-n: sem quebra de linha)", min_args: 0, max_args: None, handler: cmd_echo },
+        Command { name: "mkdir", usage: "[-p] <diretório>...", help: "cria diretórios (-p: com os pais)", min_args: 1, max_args: None, handler: cmd_mkdir },
+        Command { name: "rm", usage: "[-rf] <caminho>...", help: "remove arquivos (-r: diretórios, -f: ignora ausentes)", min_args: 1, max_args: None, handler: cmd_rm },
+        Command { name: "cp", usage: "<origem>... <destino>", help: "copia arquivos", min_args: 2, max_args: None, handler: cmd_cp },
+        Command { name: "mv", usage: "<origem>... <destino>", help: "move ou renomeia", min_args: 2, max_args: None, handler: cmd_mv },
+        Command { name: "touch", usage: "<arquivo>...", help: "cria arquivos ou atualiza a data de modificação", min_args: 1, max_args: None, handler: cmd_touch },
+        Command { name: "head", usage: "[-n linhas] [arquivo]...", help: "mostra as primeiras linhas", min_args: 0, max_args: None, handler: cmd_head },
+        Command { name: "tail", usage: "[-n linhas] [arquivo]...", help: "mostra as últimas linhas", min_args: 0, max_args: None, handler: cmd_tail },
+        Command { name: "wc", usage: "[-lwc] [arquivo]...", help: "conta linhas, palavras e bytes", min_args: 0, max_args: None, handler: cmd_wc },
+        Command { name: "grep", usage: "[-ivnc] <texto> [arquivo]...", help: "mostra linhas que contêm o texto", min_args: 1, max_args: None, handler: cmd_grep },
+        Command { name: "find", usage: "[caminho] [-name padrão] [-type f|d]", help: "procura arquivos recursivamente", min_args: 0, max_args: None, handler: cmd_find },
+        Command { name: "hexdump", usage: "[arquivo]", help: "mostra bytes em hexa e ASCII", min_args: 0, max_args: Some(1), handler: cmd_hexdump },
+        Command { name: "xxd", usage: "[arquivo]", help: "mostra bytes no formato do xxd", min_args: 0, max_args: Some(1), handler: cmd_xxd },
+        Command { name: "df", usage: "", help: "mostra o espaço usado em cada montagem", min_args: 0, max_args: Some(0), handler: cmd_df },
+    ];
+    for command in commands {
+        shell::register(command);
+    }
+}
+
+fn read_file(ctx: &Context, name: &str) -> Result<Vec<u8>, String> {
+    ctx.resolve(name)
+        .and_then(|path| VFS_INSTANCE.lock().open(&path))
+        .and_then(|mut file| file.read_to_end())
+        .map_err(|e| format!("{}: {}", name, e))
+}
+
+/// Conteúdo de cada arquivo, ou da entrada padrão (com nome vazio) se não
+/// houver nenhum
+fn read_inputs(ctx: &mut Context, files: &[&str]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    if files.is_empty() {
+        let data = ctx.read_stdin().map_err(|e| e.to_string())?;
+        return Ok(Vec::from([(String::new(), data)]));
+    }
+    files
+        .iter()
+        .map(|name| Ok((String::from(*name), read_file(ctx, name)?)))
+        .collect()
+}
+
+/// Separa `-n N` dos demais argumentos
+fn take_line_count<'a>(args: &[&'a str]) -> Result<(usize, Vec<&'a str>), String> {
+    match args {
+        ["-n", count, rest @ ..] => {
+            let count = count.parse().map_err(|_| format!("{}: número de linhas inválido", count))?;
+            Ok((count, rest.to_vec()))
+        }
+        _ => Ok((DEFAULT_LINES, args.to_vec())),
+    }
+}
+
+/// Se `path` é um diretório, o destino é `path/<nome de source>`
+fn target_path(destination: &str, source: &str) -> String {
+    let is_dir = VFS_INSTANCE.lock().stat(destination).is_ok_and(|m| m.is_dir());
+    if !is_dir {
+        return destination.into();
+    }
+    let name = source.trim_end_matches('/').rsplit('/').next().unwrap_or(source);
+    format!("{}/{}", destination.trim_end_matches('/'), name)
+}
+
+/// Lê e escreve sem o lock do VFS: o /proc o pega de novo para gerar
+/// conteúdo (ex: /proc/mounts)
+fn copy_file(from: &str, to: &str) -> Result<(), VfsError> {
+    let mut source = VFS_INSTANCE.lock().open(from)?;
+    let data = source.read_to_end()?;
+    let mut target = VFS_INSTANCE.lock().create(to)?;
+    target.write(&data)?;
+    Ok(())
+}
+
+/// Remove `path` e, se for diretório, tudo o que há dentro (sem seguir links)
+fn remove_tree(path: &str) -> Result<(), VfsError> {
+    let metadata = VFS_INSTANCE.lock().lstat(path)?;
+    if metadata.is_dir() {
+        let dir = VFS_INSTANCE.lock().list_dir(path)?;
+        for entry in dir.entries.iter().filter(|e| e.name != "." && e.name != "..") {
+            remove_tree(&vfs::resolve(path, &entry.name)?)?;
+        }
+    }
+    VFS_INSTANCE.lock().remove(path)
+}
+
+/// Casa `name` com um padrão de shell com `*` e `?`
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, None) => true,
+        (Some((b'*', rest)), _) => glob_match(rest, name) || (!name.is_empty() && glob_match(pattern, &name[1..])),
+        (Some((b'?', rest)), Some((_, name_rest))) => glob_match(rest, name_rest),
+        (Some((p, rest)), Some((n, name_rest))) => p == n && glob_match(rest, name_rest),
+        _ => false,
+    }
+}
+
+fn cmd_pwd(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    let cwd = ctx.cwd.clone();
+    sh_println!(ctx, "{}", cwd);
+    Ok(())
+}
+
+fn cmd_echo(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (newline, words) = match args {
+        ["-n", rest @ ..] => (false, rest),
+        _ => (true, args),
+    };
+    sh_print!(ctx, "{}", words.join(" "));
+    if newline {
+        sh_println!(ctx);
+    }
+    Ok(())
+}
+
+fn cmd_mkdir(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (flags, dirs) = parse_flags(args, "p")?;
+    let parents = flags.contains('p');
+
+    for name in dirs {
+        let path = ctx.resolve(name).map_err(|e| format!("{}: {}", name, e))?;
+        if !parents {
+            VFS_INSTANCE.lock().mkdir(&path).map_err(|e| format!("{}: {}", name, e))?;
+            continue;
+        }
+
+        let mut prefix = String::new();
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            prefix.push('/');
+            prefix.push_str(component);
+            match VFS_INSTANCE.lock().mkdir(&prefix) {
+                Ok(()) | Err(VfsError::AlreadyExists) => {}
+                Err(e) => return Err(format!("{}: {}", prefix, e)),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cmd_rm(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (flags, paths) = parse_flags(args, "rf")?;
+    let (recursive, force) = (flags.contains('r'), flags.contains('f'));
+
+    for name in paths {
+        let path = ctx.resolve(name).map_err(|e| format!("{}: {}", name, e))?;
+        let result = match VFS_INSTANCE.lock().lstat(&path) {
+            Ok(m) if m.is_dir() && !recursive => Err(VfsError::IsADirectory),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        };
+        match result.and_then(|()| remove_tree(&path)) {
+            Ok(()) => {}
+            Err(VfsError::NotFound) if force => {}
+            Err(e) => return Err(format!("{}: {}", name, e)),
+        }
+    }
+    Ok(())
+}
+
+fn cmd_cp(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (sources, destination) = args.split_at(args.len() - 1);
+    let destination = ctx.resolve(destination[0]).map_err(|e| e.to_string())?;
+
+    for name in sources {
+        let from = ctx.resolve(name).map_err(|e| format!("{}: {}", name, e))?;
+        let to = target_path(&destination, &from);
+        copy_file(&from, &to).map_err(|e| format!("{}: {}", name, e))?;
+    }
+    Ok(())
+}
+
+fn cmd_mv(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (sources, destination) = args.split_at(args.len() - 1);
+    let destination = ctx.resolve(destination[0]).map_err(|e| e.to_string())?;
+
+    for name in sources {
+        let from = ctx.resolve(name).map_err(|e| format!("{}: {}", name, e))?;
+        let to = target_path(&destination, &from);
+        let renamed = VFS_INSTANCE.lock().rename(&from, &to);
+        let result = match renamed {
+            // Entre montagens diferentes, arquivos são copiados e removidos
+            Err(VfsError::CrossDevice) => copy_file(&from, &to).and_then(|()| VFS_INSTANCE.lock().remove(&from)),
+            other => other,
+        };
+        result.map_err(|e| format!("{}: {}", name, e))?;
+    }
+    Ok(())
+}
+
+fn cmd_touch(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    for name in args {
+        let path = ctx.resolve(name).map_err(|e| format!("{}: {}", name, e))?;
+        let vfs = VFS_INSTANCE.lock();
+        let result = match vfs.open(&path) {
+            // Uma escrita vazia atualiza a data de modificação
+            Ok(mut file) => file.write(&[]).map(|_| ()),
+            Err(VfsError::NotFound) => vfs.create(&path).map(|_| ()),
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| format!("{}: {}", name, e))?;
+    }
+    Ok(())
+}
+
+/// Cabeçalho "==> nome <==" do head/tail com vários arquivos
+fn print_header(ctx: &mut Context, inputs: usize, index: usize, name: &str) {
+    if inputs > 1 {
+        if index > 0 {
+            sh_println!(ctx);
+        }
+        sh_println!(ctx, "==> {} <==", name);
+    }
+}
+
+fn cmd_head(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (count, files) = take_line_count(args)?;
+    let inputs = read_inputs(ctx, &files)?;
+
+    for (i, (name, data)) in inputs.iter().enumerate() {
+        print_header(ctx, inputs.len(), i, name);
+        for line in String::from_utf8_lossy(data).lines().take(count) {
+            sh_println!(ctx, "{}", line);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_tail(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (count, files) = take_line_count(args)?;
+    let inputs = read_inputs(ctx, &files)?;
+
+    for (i, (name, data)) in inputs.iter().enumerate() {
+        print_header(ctx, inputs.len(), i, name);
+        let text = String::from_utf8_lossy(data);
+        let lines: Vec<&str> = text.lines().collect();
+        for line in &lines[lines.len().saturating_sub(count)..] {
+            sh_println!(ctx, "{}", line);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_wc(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (mut flags, files) = parse_flags(args, "lwc")?;
+    if flags.is_empty() {
+        flags = String::from("lwc");
+    }
+    let inputs = read_inputs(ctx, &files)?;
+
+    let print = |ctx: &mut Context, counts: [usize; 3], name: &str| {
+        let mut out = String::new();
+        for (flag, count) in ['l', 'w', 'c'].iter().zip(counts) {
+            if flags.contains(*flag) {
+                out.push_str(&format!("{:>8}", count));
+            }
+        }
+        sh_println!(ctx, "{} {}", out, name);
+    };
+
+    let mut total = [0; 3];
+    for (name, data) in &inputs {
+        let counts = [
+            data.iter().filter(|&&b| b == b'\n').count(),
+            data.split(|b| b.is_ascii_whitespace()).filter(|w| !w.is_empty()).count(),
+            data.len(),
+        ];
+        for (t, c) in total.iter_mut().zip(counts) {
+            *t += c;
+        }
+        print(ctx, counts, name);
+    }
+    if inputs.len() > 1 {
+        print(ctx, total, "total");
+    }
+    Ok(())
+}
+
+/// Sem nenhuma linha encontrada termina com status 1, como o grep
+fn cmd_grep(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (flags, operands) = parse_flags(args, "ivnc")?;
+    let Some((&pattern, files)) = operands.split_first() else {
+        return Err(String::from("Uso: grep [-ivnc] <texto> [arquivo]..."));
+    };
+    let ignore_case = flags.contains('i');
+    let pattern = if ignore_case { pattern.to_lowercase() } else { pattern.into() };
+
+    let inputs = read_inputs(ctx, files)?;
+    let mut found = false;
+    for (name, data) in &inputs {
+        let prefix = if inputs.len() > 1 { format!("{}:", name) } else { String::new() };
+        let mut count = 0;
+
+        for (number, line) in String::from_utf8_lossy(data).lines().enumerate() {
+            let matches = if ignore_case {
+                line.to_lowercase().contains(&pattern)
+            } else {
+                line.contains(&pattern)
+            };
+            if matches == flags.contains('v') {
+                continue;
+            }
+            count += 1;
+            if flags.contains('c') {
+                continue;
+            }
+            if flags.contains('n') {
+                sh_println!(ctx, "{}{}:{}", prefix, number + 1, line);
+            } else {
+                sh_println!(ctx, "{}{}", prefix, line);
+            }
+        }
+
+        if flags.contains('c') {
+            sh_println!(ctx, "{}{}", prefix, count);
+        }
+        found |= count > 0;
+    }
+
+    if found { Ok(()) } else { Err(String::new()) }
+}
+
+/// Critérios do `find`
+struct FindFilter<'a> {
+    name: Option<&'a str>,
+    file_type: Option<FileType>,
+}
+
+/// Visita `path` (mostrado como `shown`) e, se for diretório, seus filhos;
+/// links simbólicos não são seguidos
+fn find_walk(ctx: &mut Context, path: &str, shown: &str, filter: &FindFilter) -> Result<(), VfsError> {
+    let metadata = VFS_INSTANCE.lock().lstat(path)?;
+    let name = shown.trim_end_matches('/').rsplit('/').next().unwrap_or(shown);
+
+    let name_ok = filter.name.is_none_or(|p| glob_match(p.as_bytes(), name.as_bytes()));
+    let type_ok = filter.file_type.is_none_or(|t| t == metadata.file_type);
+    if name_ok && type_ok {
+        sh_println!(ctx, "{}", shown);
+    }
+
+    if metadata.is_dir() {
+        let dir = VFS_INSTANCE.lock().list_dir(path)?;
+        for entry in dir.entries.iter().filter(|e| e.name != "." && e.name != "..") {
+            let child = vfs::resolve(path, &entry.name)?;
+            let child_shown = format!("{}/{}", shown.trim_end_matches('/'), entry.name);
+            find_walk(ctx, &child, &child_shown, filter)?;
+        }
+    }
+    Ok(())
+}
+
+fn cmd_find(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (start, mut rest) = match args.split_first() {
+        Some((first, rest)) if !first.starts_with('-') => (*first, rest),
+        _ => (".", args),
+    };
+
+    let mut filter = FindFilter { name: None, file_type: None };
+    while let Some((&option, tail)) = rest.split_first() {
+        let Some((&value, tail)) = tail.split_first() else {
+            return Err(format!("{}: falta o argumento", option));
+        };
+        match option {
+            "-name" => filter.name = Some(value),
+            "-type" => {
+                filter.file_type = Some(match value {
+                    "f" => FileType::File,
+                    "d" => FileType::Directory,
+                    "l" => FileType::Symlink,
+                    _ => return Err(format!("-type: tipo desconhecido: {}", value)),
+                })
+            }
+            _ => return Err(format!("{}: opção desconhecida", option)),
+        }
+        rest = tail;
+    }
+
+    let path = ctx.resolve(start).map_err(|e| format!("{}: {}", start, e))?;
+    find_walk(ctx, &path, start, &filter).map_err(|e| format!("{}: {}", start, e))
+}
+
+fn printable(byte: u8) -> char {
+    if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' }
+}
+
+/// Formato `hexdump -C`: deslocamento, 16 bytes em hexa e o texto entre `|`
+fn cmd_hexdump(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (_, data) = read_inputs(ctx, args)?.remove(0);
+
+    for (i, chunk) in data.chunks(DUMP_WIDTH).enumerate() {
+        let mut hex = String::new();
+        for j in 0..DUMP_WIDTH {
+            match chunk.get(j) {
+                Some(b) => hex.push_str(&format!("{:02x} ", b)),
+                None => hex.push_str("   "),
+            }
+            if j == DUMP_WIDTH / 2 - 1 {
+                hex.push(' ');
+            }
+        }
+        let text: String = chunk.iter().map(|&b| printable(b)).collect();
+        sh_println!(ctx, "{:08x}  {} |{}|", i * DUMP_WIDTH, hex, text);
+    }
+    sh_println!(ctx, "{:08x}", data.len());
+    Ok(())
+}
+
+/// Formato do `xxd`: bytes agrupados de dois em dois
+fn cmd_xxd(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (_, data) = read_inputs(ctx, args)?.remove(0);
+
+    for (i, chunk) in data.chunks(DUMP_WIDTH).enumerate() {
+        let mut hex = String::new();
+        for j in 0..DUMP_WIDTH {
+            match chunk.get(j) {
+                Some(b) => hex.push_str(&format!("{:02x}", b)),
+                None => hex.push_str("  "),
+            }
+            if j % 2 == 1 {
+                hex.push(' ');
+            }
+        }
+        let text: String = chunk.iter().map(|&b| printable(b)).collect();
+        sh_println!(ctx, "{:08x}: {} {}", i * DUMP_WIDTH, hex, text);
+    }
+    Ok(())
+}
+
+fn cmd_df(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    sh_println!(ctx, "{:<10} {:<6} {:>9} {:>9} {:>9} {:>5} Montado em", "Origem", "Tipo", "KiB", "Usados", "Livres", "Uso%");
+
+    let mounts = VFS_INSTANCE.lock().mounts();
+    for (path, fs, source) in mounts {
+        let stats = VFS_INSTANCE.lock().statfs(&path);
+        match stats {
+            Ok(stats) => {
+                let total = stats.total_blocks * stats.block_size / 1024;
+                let free = stats.free_blocks * stats.block_size / 1024;
+                let used = total - free;
+                let percent = if total == 0 { 0 } else { (used * 100).div_ceil(total) };
+                sh_println!(ctx, "{:<10} {:<6} {:>9} {:>9} {:>9} {:>4}% {}", source, fs, total, used, free, percent, path);
+            }
+            Err(_) => sh_println!(ctx, "{:<10} {:<6} {:>9} {:>9} {:>9} {:>5} {}", source, fs, "-", "-", "-", "-", path),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use alloc::{string::String, vec::Vec};
+use core::arch::x86_64::__cpuid;
+
+use crate::shell::{self, Command, CommandResult, Context};
+use crate::sh_println;
+
+/// Informações do processador obtidas via CPUID
+pub struct CpuInfo {
+    pub vendor: String,
+    pub brand: String,
+    pub family: u32,
+    pub model: u32,
+    pub stepping: u32,
+    pub features: Vec<&'static str>,
+}
+
+/// Bits de EDX (folha 1) e os nomes usados em /proc/cpuinfo
+const EDX_FEATURES: [(u32, &str); 12] = [
+    (0, "fpu"),
+    (4, "tsc"),
+    (5, "msr"),
+    (6, "pae"),
+    (8, "cx8"),
+    (9, "apic"),
+    (11, "sep"),
+    (13, "pge"),
+    (15, "cmov"),
+    (23, "mmx"),
+    (25, "sse"),
+    (26, "sse2"),
+];
+
+/// Bits de ECX (folha 1)
+const ECX_FEATURES: [(u32, &str); 8] = [
+    (0, "sse3"),
+    (9, "ssse3"),
+    (19, "sse4_1"),
+    (20, "sse4_2"),
+    (23, "popcnt"),
+    (26, "xsave"),
+    (28, "avx"),
+    (30, "rdrand"),
+];
+
+fn regs_to_string(regs: &[u32]) -> String {
+    let bytes: Vec<u8> = regs.iter().flat_map(|r| r.to_le_bytes()).collect();
+    String::from_utf8_lossy(&bytes).trim_matches(char::from(0)).trim().into()
+}
+
+pub fn info() -> CpuInfo {
+    let leaf0 = unsafe { __cpuid(0) };
+    let vendor = regs_to_string(&[leaf0.ebx, leaf0.edx, leaf0.ecx]);
+
+    let leaf1 = unsafe { __cpuid(1) };
+    let base_family = (leaf1.eax >> 8) & 0xF;
+    let base_model = (leaf1.eax >> 4) & 0xF;
+    let family = if base_family == 0xF {
+        base_family + ((leaf1.eax >> 20) & 0xFF)
+    } else {
+        base_family
+    };
+    let model = if base_family == 0x6 || base_family == 0xF {
+        base_model | ((leaf1.eax >> 16) & 0xF) << 4
+    } else {
+        base_model
+    };
+
+    let mut features: Vec<&'static str> = EDX_FEATURES
+        .iter()
+        .filter(|(bit, _)| leaf1.edx & (1 << bit) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    features.extend(
+        ECX_FEATURES
+            .iter()
+            .filter(|(bit, _)| leaf1.ecx & (1 << bit) != 0)
+            .map(|(_, name)| *name),
+    );
+
+    // Brand string nas folhas estendidas 0x80000002..=0x80000004
+    let max_ext = unsafe { __cpuid(0x8000_0000) }.eax;
+    let brand = if max_ext >= 0x8000_0004 {
+        let mut regs = Vec::new();
+        for leaf in 0x8000_0002..=0x8000_0004u32 {
+            let r = unsafe { __cpuid(leaf) };
+            regs.extend_from_slice(&[r.eax, r.ebx, r.ecx, r.edx]);
+        }
+        regs_to_string(&regs)
+    } else {
+        String::from("desconhecido")
+    };
+
+    CpuInfo {
+        vendor,
+        brand,
+        family,
+        model,
+        stepping: leaf1.eax & 0xF,
+        features,
+    }
+}
+
+fn cmd_cpuinfo(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    let info = info();
+    sh_println!(ctx, "Fabricante: {}", info.vendor);
+    sh_println!(ctx, "Modelo:     {}", info.brand);
+    sh_println!(ctx, "Família {}, modelo {}, stepping {}", info.family, info.model, info.stepping);
+    sh_println!(ctx, "Recursos:   {}", info.features.join(" "));
+    Ok(())
+}
+
+pub fn register_commands() {
+    shell::register(Command {
+        name: "cpuinfo",
+        usage: "",
+        help: "mostra o processador (CPUID)",
+        min_args: 0,
+        max_args: Some(0),
+        handler: cmd_cpuinfo,
+    });
+}
@@ -0,0 +1,337 @@
+use alloc::{string::String, vec, vec::Vec};
+use x86_64::instructions::random::RdRand;
+
+use crate::block::{BlockDevice, SECTOR_SIZE};
+use crate::keyboard;
+use crate::serial::SERIAL1;
+use crate::sync::Mutex;
+use crate::rtc;
+use crate::scheduler;
+use crate::vfs::{DirEntry, Directory, FileType, Filesystem, InodeId, Metadata, VfsError};
+
+const ROOT_INODE: InodeId = 1;
+
+/// Dispositivo de caracteres: fluxo de bytes sem posição
+pub trait CharDevice: Send + Sync {
+    fn read(&self, buf: &mut [u8]) -> Result<usize, VfsError>;
+    fn write(&self, buf: &[u8]) -> Result<usize, VfsError>;
+}
+
+#[derive(Clone, Copy)]
+pub enum Device {
+    Char(&'static dyn CharDevice),
+    Block(&'static dyn BlockDevice),
+}
+
+/// Dispositivos registrados; o inode é o índice + 2 (o 1 é a raiz)
+static DEVICES: Mutex<Vec<(String, Device)>> = Mutex::named("devfs", Vec::new());
+
+/// Registra um dispositivo de caracteres em /dev
+pub fn register_char(name: &str, device: &'static dyn CharDevice) {
+    DEVICES.lock().push((name.into(), Device::Char(device)));
+}
+
+/// Registra um dispositivo de blocos em /dev
+pub fn register_block(device: &'static dyn BlockDevice) {
+    DEVICES.lock().push((device.name().into(), Device::Block(device)));
+}
+
+fn device(inode: InodeId) -> Result<Device, VfsError> {
+    let index = inode.checked_sub(2).ok_or(VfsError::IsADirectory)? as usize;
+    DEVICES
+        .lock()
+        .get(index)
+        .map(|(_, dev)| *dev)
+        .ok_or(VfsError::NotFound)
+}
+
+/// /dev/null: descarta escritas, leitura sempre no fim
+pub struct NullDevice;
+
+impl CharDevice for NullDevice {
+    fn read(&self, _buf: &mut [u8]) -> Result<usize, VfsError> {
+        Ok(0)
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, VfsError> {
+        Ok(buf.len())
+    }
+}
+
+/// /dev/zero: leitura retorna zeros
+pub struct ZeroDevice;
+
+impl CharDevice for ZeroDevice {
+    fn read(&self, buf: &mut [u8]) -> Result<usize, VfsError> {
+        buf.fill(0);
+        Ok(buf.len())
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, VfsError> {
+        Ok(buf.len())
+    }
+}
+
+/// /dev/random: RDRAND quando disponível, senão xorshift semeado pelo TSC
+pub struct RandomDevice {
+    state: Mutex<u64>,
+}
+
+impl RandomDevice {
+    fn next(&self) -> u64 {
+        if let Some(value) = RdRand::new().and_then(|r| r.get_u64()) {
+            return value;
+        }
+
+        let mut state = self.state.lock();
+        if *state == 0 {
+            *state = unsafe { core::arch::x86_64::_rdtsc() } | 1;
+        }
+        let mut x = *state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        *state = x;
+        x
+    }
+}
+
+impl CharDevice for RandomDevice {
+    fn read(&self, buf: &mut [u8]) -> Result<usize, VfsError> {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(buf.len())
+    }
+
+    /// Escritas misturam os bytes no estado do gerador
+    fn write(&self, buf: &[u8]) -> Result<usize, VfsError> {
+        let mut state = self.state.lock();
+        for &b in buf {
+            *state = state.rotate_left(8) ^ b as u64;
+        }
+        Ok(buf.len())
+    }
+}
+
+/// /dev/console: escreve no VGA e lê caracteres digitados no teclado
+pub struct ConsoleDevice;
+
+impl CharDevice for ConsoleDevice {
+    /// Bloqueia até a primeira tecla; devolve um byte por tecla com
+    /// equivalente ASCII (Enter vira '\n')
+    fn read(&self, buf: &mut [u8]) -> Result<usize, VfsError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            if let Some(byte) = keyboard::read_key().as_ascii() {
+                buf[0] = byte;
+                return Ok(1);
+            }
+        }
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, VfsError> {
+        use crate::vga_buffer::vga_print;
+        for &b in buf {
+            vga_print!("{}", b as char);
+        }
+        Ok(buf.len())
+    }
+}
+
+/// /dev/kbd: scancodes crus do teclado PS/2
+pub struct KeyboardDevice;
+
+impl CharDevice for KeyboardDevice {
+    fn read(&self, buf: &mut [u8]) -> Result<usize, VfsError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        buf[0] = keyboard::wait_scancode();
+        let mut n = 1;
+        while n < buf.len() {
+            match keyboard::pop_scancode() {
+                Some(s) => buf[n] = s,
+                None => break,
+            }
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    fn write(&self, _buf: &[u8]) -> Result<usize, VfsError> {
+        Err(VfsError::ReadOnly)
+    }
+}
+
+/// /dev/ttyS0: porta serial COM1
+pub struct SerialDevice;
+
+impl CharDevice for SerialDevice {
+    /// Bloqueia até chegar o primeiro byte
+    fn read(&self, buf: &mut [u8]) -> Result<usize, VfsError> {
+        let mut n = 0;
+        while n < buf.len() {
+            match SERIAL1.lock().try_read_byte() {
+                Some(b) => {
+                    buf[n] = b;
+                    n += 1;
+                }
+                None if n > 0 => break,
+                None => scheduler::yield_now(),
+            }
+        }
+        Ok(n)
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, VfsError> {
+        let mut serial = SERIAL1.lock();
+        for &b in buf {
+            serial.write_byte(b);
+        }
+        Ok(buf.len())
+    }
+}
+
+static NULL: NullDevice = NullDevice;
+static ZERO: ZeroDevice = ZeroDevice;
+static RANDOM: RandomDevice = RandomDevice { state: Mutex::named("random", 0) };
+static CONSOLE: ConsoleDevice = ConsoleDevice;
+static KBD: KeyboardDevice = KeyboardDevice;
+static TTYS0: SerialDevice = SerialDevice;
+
+/// Registra os dispositivos do kernel e os discos já detectados
+pub fn init() {
+    register_char("console", &CONSOLE);
+    register_char("ttyS0", &TTYS0);
+    register_char("null", &NULL);
+    register_char("zero", &ZERO);
+    register_char("random", &RANDOM);
+    register_char("kbd", &KBD);
+
+    for disk in crate::block::devices() {
+        register_block(disk);
+    }
+}
+
+fn metadata(inode: InodeId, device: Device) -> Metadata {
+    match device {
+        Device::Char(_) => Metadata::new(inode, FileType::CharDevice, 0),
+        Device::Block(dev) => {
+            Metadata::new(inode, FileType::BlockDevice, dev.sector_count() * SECTOR_SIZE as u64)
+        }
+    }
+}
+
+/// Lê `buf.len()` bytes de um disco a partir de um offset arbitrário
+fn block_read(dev: &dyn BlockDevice, offset: u64, buf: &mut [u8]) -> Result<usize, VfsError> {
+    let size = dev.sector_count() * SECTOR_SIZE as u64;
+    if offset >= size {
+        return Ok(0);
+    }
+    let len = core::cmp::min(buf.len() as u64, size - offset) as usize;
+
+    let first = offset / SECTOR_SIZE as u64;
+    let last = (offset + len as u64).div_ceil(SECTOR_SIZE as u64);
+    let mut tmp = vec![0u8; ((last - first) as usize) * SECTOR_SIZE];
+    dev.read_sectors(first, &mut tmp).map_err(|_| VfsError::Io)?;
+
+    let start = (offset % SECTOR_SIZE as u64) as usize;
+    buf[..len].copy_from_slice(&tmp[start..start + len]);
+    Ok(len)
+}
+
+/// Escreve com leitura-modificação-escrita dos setores parciais
+fn block_write(dev: &dyn BlockDevice, offset: u64, buf: &[u8]) -> Result<usize, VfsError> {
+    let size = dev.sector_count() * SECTOR_SIZE as u64;
+    if offset + buf.len() as u64 > size {
+        return Err(VfsError::NoSpace);
+    }
+
+    let first = offset / SECTOR_SIZE as u64;
+    let last = (offset + buf.len() as u64).div_ceil(SECTOR_SIZE as u64);
+    let mut tmp = vec![0u8; ((last - first) as usize) * SECTOR_SIZE];
+    dev.read_sectors(first, &mut tmp).map_err(|_| VfsError::Io)?;
+
+    let start = (offset % SECTOR_SIZE as u64) as usize;
+    tmp[start..start + buf.len()].copy_from_slice(buf);
+    dev.write_sectors(first, &tmp).map_err(|_| VfsError::Io)?;
+    Ok(buf.len())
+}
+
+/// Sistema de arquivos /dev: um arquivo por dispositivo registrado
+pub struct DevFs;
+
+impl Filesystem for DevFs {
+    fn name(&self) -> &str {
+        "devfs"
+    }
+
+    fn lookup(&self, path: &str) -> Result<InodeId, VfsError> {
+        let name = path.trim_matches('/');
+        if name.is_empty() {
+            return Ok(ROOT_INODE);
+        }
+        DEVICES
+            .lock()
+            .iter()
+            .position(|(n, _)| n == name)
+            .map(|i| i as InodeId + 2)
+            .ok_or(VfsError::NotFound)
+    }
+
+    /// Os nós existem desde o boot; discos têm o tamanho do dispositivo
+    fn stat(&self, inode: InodeId) -> Result<Metadata, VfsError> {
+        let metadata = if inode == ROOT_INODE {
+            Metadata::new(inode, FileType::Directory, 0)
+        } else {
+            metadata(inode, device(inode)?)
+        };
+        Ok(metadata.with_times(rtc::boot_time()))
+    }
+
+    fn read_at(&self, inode: InodeId, offset: u64, buf: &mut [u8]) -> Result<usize, VfsError> {
+        match device(inode)? {
+            Device::Char(dev) => dev.read(buf),
+            Device::Block(dev) => block_read(dev, offset, buf),
+        }
+    }
+
+    fn write_at(&self, inode: InodeId, offset: u64, buf: &[u8]) -> Result<usize, VfsError> {
+        match device(inode)? {
+            Device::Char(dev) => dev.write(buf),
+            Device::Block(dev) => block_write(dev, offset, buf),
+        }
+    }
+
+    /// Abrir um dispositivo "para escrita" não trunca nada
+    fn create(&self, path: &str) -> Result<InodeId, VfsError> {
+        self.lookup(path)
+    }
+
+    fn list_dir(&self, path: &str) -> Result<Directory, VfsError> {
+        if !path.trim_matches('/').is_empty() {
+            return Err(VfsError::NotADirectory);
+        }
+
+        let entries = DEVICES
+            .lock()
+            .iter()
+            .enumerate()
+            .map(|(i, (name, dev))| DirEntry {
+                name: name.clone(),
+                metadata: metadata(i as InodeId + 2, *dev).with_times(rtc::boot_time()),
+            })
+            .collect();
+
+        Ok(Directory {
+            name: String::from("/"),
+            entries,
+        })
+    }
+}
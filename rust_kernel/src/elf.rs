@@ -0,0 +1,647 @@
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+use core::fmt;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use x86_64::structures::paging::{Page, PageTableFlags, PhysFrame, Size4KiB};
+use x86_64::VirtAddr;
+
+use crate::address_space::{self, AddressSpace, Vma};
+use crate::fd::FdTable;
+use crate::memory;
+use crate::sync::Mutex;
+use crate::{gdt, scheduler};
+
+const PAGE_SIZE: u64 = 4096;
+
+/// Executáveis com posição independente (static-PIE) são carregados aqui
+pub const PIE_LOAD_BASE: u64 = 0x4000_0000;
+
+/// Topo e tamanho da pilha do programa
+pub const USER_STACK_TOP: u64 = 0x7000_0000_0000;
+pub const USER_STACK_SIZE: u64 = 64 * 1024;
+
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+const EM_X86_64: u16 = 62;
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+const PT_PHDR: u32 = 6;
+
+/// Tamanho mínimo de uma entrada da tabela de cabeçalhos de programa
+const PHDR_SIZE: usize = 56;
+/// Tamanho mínimo de uma relocação Elf64_Rela
+const RELA_SIZE: u64 = 24;
+
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+
+const DT_NULL: u64 = 0;
+const DT_RELA: u64 = 7;
+const DT_RELASZ: u64 = 8;
+const DT_RELAENT: u64 = 9;
+
+const R_X86_64_NONE: u32 = 0;
+const R_X86_64_RELATIVE: u32 = 8;
+
+/// Entradas do vetor auxiliar (auxv) passadas na pilha
+const AT_NULL: u64 = 0;
+const AT_PHDR: u64 = 3;
+const AT_PHENT: u64 = 4;
+const AT_PHNUM: u64 = 5;
+const AT_PAGESZ: u64 = 6;
+const AT_BASE: u64 = 7;
+const AT_ENTRY: u64 = 9;
+const AT_RANDOM: u64 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElfError {
+    Truncated,
+    BadMagic,
+    NotElf64,
+    NotLittleEndian,
+    WrongMachine,
+    WrongType,
+    /// Tem PT_INTERP: precisaria de um ligador dinâmico
+    Dynamic,
+    BadSegment,
+    UnsupportedRelocation(u32),
+    OutOfMemory,
+    MapFailed,
+}
+
+impl fmt::Display for ElfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ElfError::Truncated => write!(f, "arquivo ELF truncado"),
+            ElfError::BadMagic => write!(f, "não é um arquivo ELF"),
+            ElfError::NotElf64 => write!(f, "não é um ELF de 64 bits"),
+            ElfError::NotLittleEndian => write!(f, "ELF não é little-endian"),
+            ElfError::WrongMachine => write!(f, "ELF não é para x86_64"),
+            ElfError::WrongType => write!(f, "ELF não é executável"),
+            ElfError::Dynamic => write!(f, "executáveis dinâmicos não são suportados"),
+            ElfError::BadSegment => write!(f, "segmento inválido"),
+            ElfError::UnsupportedRelocation(kind) => write!(f, "relocação {} não suportada", kind),
+            ElfError::OutOfMemory => write!(f, "memória insuficiente"),
+            ElfError::MapFailed => write!(f, "falha ao mapear páginas"),
+        }
+    }
+}
+
+/// `len` bytes a partir de `offset`, sem estourar com valores do arquivo
+fn file_range(data: &[u8], offset: u64, len: u64) -> Result<&[u8], ElfError> {
+    let end = offset.checked_add(len).ok_or(ElfError::Truncated)?;
+    data.get(offset as usize..end as usize).ok_or(ElfError::Truncated)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ElfError> {
+    let bytes = file_range(data, offset as u64, 2)?;
+    Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ElfError> {
+    let bytes = file_range(data, offset as u64, 4)?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, ElfError> {
+    let bytes = file_range(data, offset as u64, 8)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Campos do cabeçalho ELF usados pelo loader
+#[derive(Debug, Clone, Copy)]
+pub struct ElfHeader {
+    pub elf_type: u16,
+    pub entry: u64,
+    pub phoff: u64,
+    pub phentsize: u16,
+    pub phnum: u16,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ProgramHeader {
+    pub p_type: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub filesz: u64,
+    pub memsz: u64,
+}
+
+impl ElfHeader {
+    /// Valida identificação, arquitetura e tipo
+    pub fn parse(data: &[u8]) -> Result<Self, ElfError> {
+        if data.len() < 64 {
+            return Err(ElfError::Truncated);
+        }
+        if &data[..4] != b"\x7fELF" {
+            return Err(ElfError::BadMagic);
+        }
+        if data[4] != 2 {
+            return Err(ElfError::NotElf64);
+        }
+        if data[5] != 1 {
+            return Err(ElfError::NotLittleEndian);
+        }
+        if read_u16(data, 18)? != EM_X86_64 {
+            return Err(ElfError::WrongMachine);
+        }
+
+        let elf_type = read_u16(data, 16)?;
+        if elf_type != ET_EXEC && elf_type != ET_DYN {
+            return Err(ElfError::WrongType);
+        }
+
+        Ok(Self {
+            elf_type,
+            entry: read_u64(data, 24)?,
+            phoff: read_u64(data, 32)?,
+            phentsize: read_u16(data, 54)?,
+            phnum: read_u16(data, 56)?,
+        })
+    }
+
+    pub fn program_headers(&self, data: &[u8]) -> Result<Vec<ProgramHeader>, ElfError> {
+        if self.phnum > 0 && (self.phentsize as usize) < PHDR_SIZE {
+            return Err(ElfError::BadSegment);
+        }
+        (0..self.phnum as u64)
+            .map(|i| {
+                let at = self.phoff.checked_add(i * self.phentsize as u64).ok_or(ElfError::Truncated)?;
+                let entry = file_range(data, at, PHDR_SIZE as u64)?;
+                Ok(ProgramHeader {
+                    p_type: read_u32(entry, 0)?,
+                    flags: read_u32(entry, 4)?,
+                    offset: read_u64(entry, 8)?,
+                    vaddr: read_u64(entry, 16)?,
+                    filesz: read_u64(entry, 32)?,
+                    memsz: read_u64(entry, 40)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Páginas do programa, montadas em frames novos antes de serem mapeadas
+struct Image {
+    pages: BTreeMap<Page, (PhysFrame, PageTableFlags)>,
+}
+
+impl Image {
+    /// Frame (zerado na primeira vez) que vai conter `page`
+    fn frame(&mut self, page: Page, flags: PageTableFlags) -> Result<PhysFrame, ElfError> {
+        if let Some((frame, existing)) = self.pages.get_mut(&page) {
+            // Segmentos que dividem uma página somam as permissões
+            let no_execute = existing.contains(PageTableFlags::NO_EXECUTE) && flags.contains(PageTableFlags::NO_EXECUTE);
+            *existing |= flags;
+            existing.set(PageTableFlags::NO_EXECUTE, no_execute);
+            return Ok(*frame);
+        }
+
+        let frame = memory::alloc_zeroed_frame().ok_or(ElfError::OutOfMemory)?;
+        self.pages.insert(page, (frame, flags));
+        Ok(frame)
+    }
+
+    /// Reserva as páginas de `[start, end)` com as permissões dadas
+    fn reserve(&mut self, start: u64, end: u64, flags: PageTableFlags) -> Result<(), ElfError> {
+        if end <= start {
+            return Ok(());
+        }
+        let first = Page::containing_address(VirtAddr::try_new(start).map_err(|_| ElfError::BadSegment)?);
+        let last = Page::containing_address(VirtAddr::try_new(end - 1).map_err(|_| ElfError::BadSegment)?);
+        for page in Page::range_inclusive(first, last) {
+            self.frame(page, flags)?;
+        }
+        Ok(())
+    }
+
+    /// Copia `bytes` para o endereço virtual `addr` (já reservado), escrevendo
+    /// pelo mapeamento físico, já que as páginas podem ser somente leitura
+    fn write(&mut self, mut addr: u64, mut bytes: &[u8]) -> Result<(), ElfError> {
+        while !bytes.is_empty() {
+            let addr_virt = VirtAddr::try_new(addr).map_err(|_| ElfError::BadSegment)?;
+            let page = Page::<Size4KiB>::containing_address(addr_virt);
+            let (frame, _) = *self.pages.get(&page).ok_or(ElfError::BadSegment)?;
+            let in_page = (addr % PAGE_SIZE) as usize;
+            let n = core::cmp::min(bytes.len(), PAGE_SIZE as usize - in_page);
+
+            let dst = memory::phys_to_virt(frame.start_address()) + in_page as u64;
+            unsafe {
+                core::ptr::copy_nonoverlapping(bytes.as_ptr(), dst.as_mut_ptr::<u8>(), n);
+            }
+            addr = addr.checked_add(n as u64).ok_or(ElfError::BadSegment)?;
+            bytes = &bytes[n..];
+        }
+        Ok(())
+    }
+
+    fn read_u64(&self, addr: u64) -> Result<u64, ElfError> {
+        let mut out = [0u8; 8];
+        for (i, byte) in out.iter_mut().enumerate() {
+            let addr = addr.checked_add(i as u64).ok_or(ElfError::BadSegment)?;
+            let page = Page::<Size4KiB>::containing_address(VirtAddr::try_new(addr).map_err(|_| ElfError::BadSegment)?);
+            let (frame, _) = self.pages.get(&page).ok_or(ElfError::BadSegment)?;
+            let src = memory::phys_to_virt(frame.start_address()) + addr % PAGE_SIZE;
+            *byte = unsafe { *src.as_ptr::<u8>() };
+        }
+        Ok(u64::from_le_bytes(out))
+    }
+
+    /// Monta um espaço de endereçamento novo com as páginas da imagem; cada
+    /// sequência contígua de páginas com as mesmas permissões vira uma VMA
+    fn into_address_space(mut self) -> Result<AddressSpace, ElfError> {
+        let mut space = AddressSpace::new().map_err(|_| ElfError::OutOfMemory)?;
+
+        let mut pages = core::mem::take(&mut self.pages).into_iter().peekable();
+        while let Some((first, (frame, flags))) = pages.next() {
+            let mut run = vec![(first, frame)];
+            while let Some(&(next, (next_frame, next_flags))) = pages.peek() {
+                if next_flags != flags || next != run[run.len() - 1].0 + 1 {
+                    break;
+                }
+                run.push((next, next_frame));
+                pages.next();
+            }
+
+            let vma = Vma {
+                start: first.start_address(),
+                end: first.start_address() + run.len() as u64 * PAGE_SIZE,
+                flags,
+                name: if first.start_address().as_u64() >= USER_STACK_TOP - USER_STACK_SIZE {
+                    "pilha"
+                } else if flags.contains(PageTableFlags::NO_EXECUTE) {
+                    "dados"
+                } else {
+                    "código"
+                },
+            };
+            let (start, end) = (vma.start, vma.end);
+            // O erro diz se a VMA chegou a ser registrada
+            let mut mapped = 0;
+            let result = space.insert_vma(vma).map_err(|_| false).and_then(|_| {
+                run.iter().try_for_each(|&(page, frame)| {
+                    space.map(page, frame, flags).map_err(|_| true)?;
+                    mapped += 1;
+                    Ok(())
+                })
+            });
+            if let Err(inserted) = result {
+                // Tira a VMA liberando só as páginas mapeadas aqui: as demais
+                // entradas da região podem ser do kernel
+                if inserted {
+                    let mapped_pages: Vec<Page> = run.iter().take(mapped).map(|&(page, _)| page).collect();
+                    space.remove_vma(start, end, &mapped_pages);
+                }
+                let rest = run.into_iter().skip(mapped).map(|(_, frame)| frame);
+                for frame in rest.chain(pages.map(|(_, (frame, _))| frame)) {
+                    unsafe { memory::free_frame(frame) };
+                }
+                return Err(ElfError::MapFailed);
+            }
+        }
+        Ok(space)
+    }
+}
+
+/// Carga interrompida: os frames ainda não mapeados voltam ao alocador
+impl Drop for Image {
+    fn drop(&mut self) {
+        for &(frame, _) in self.pages.values() {
+            unsafe { memory::free_frame(frame) };
+        }
+    }
+}
+
+fn segment_flags(ph: &ProgramHeader) -> PageTableFlags {
+    let mut flags = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+    if ph.flags & PF_W != 0 {
+        flags |= PageTableFlags::WRITABLE;
+    }
+    if ph.flags & PF_X == 0 {
+        flags |= PageTableFlags::NO_EXECUTE;
+    }
+    flags
+}
+
+/// Aplica as relocações R_X86_64_RELATIVE de um static-PIE
+fn relocate(image: &mut Image, data: &[u8], dynamic: &ProgramHeader, base: u64) -> Result<(), ElfError> {
+    let (mut rela, mut rela_size, mut rela_ent) = (0, 0, RELA_SIZE);
+
+    let table = file_range(data, dynamic.offset, dynamic.filesz)?;
+    for entry in table.chunks_exact(16) {
+        let (tag, value) = (read_u64(entry, 0)?, read_u64(entry, 8)?);
+        match tag {
+            DT_NULL => break,
+            DT_RELA => rela = value,
+            DT_RELASZ => rela_size = value,
+            DT_RELAENT => rela_ent = value,
+            _ => {}
+        }
+    }
+    if rela_size == 0 {
+        return Ok(());
+    }
+    // Entradas menores que uma Elf64_Rela (ou 0) fariam o laço não andar
+    if rela_ent < RELA_SIZE {
+        return Err(ElfError::BadSegment);
+    }
+
+    // A tabela está num segmento carregado: lê pela imagem já relocada
+    let mut at = base.checked_add(rela).ok_or(ElfError::BadSegment)?;
+    let end = at.checked_add(rela_size).ok_or(ElfError::BadSegment)?;
+    while at < end {
+        let offset = image.read_u64(at)?;
+        let kind = image.read_u64(at + 8)? as u32;
+        let addend = image.read_u64(at + 16)?;
+        match kind {
+            R_X86_64_NONE => {}
+            R_X86_64_RELATIVE => {
+                let target = base.checked_add(offset).ok_or(ElfError::BadSegment)?;
+                image.write(target, &(base.wrapping_add(addend)).to_le_bytes())?
+            }
+            other => return Err(ElfError::UnsupportedRelocation(other)),
+        }
+        at = at.checked_add(rela_ent).ok_or(ElfError::BadSegment)?;
+    }
+    Ok(())
+}
+
+/// Monta a pilha inicial da ABI System V: argc, argv, envp e auxv, com as
+/// strings no topo. Retorna o valor inicial de RSP.
+fn build_stack(image: &mut Image, argv: &[&str], envp: &[&str], auxv: &[(u64, u64)]) -> Result<u64, ElfError> {
+    let bottom = USER_STACK_TOP - USER_STACK_SIZE;
+    let flags = PageTableFlags::PRESENT
+        | PageTableFlags::WRITABLE
+        | PageTableFlags::USER_ACCESSIBLE
+        | PageTableFlags::NO_EXECUTE;
+    image.reserve(bottom, USER_STACK_TOP, flags)?;
+
+    let mut sp = USER_STACK_TOP;
+    let mut push_bytes = |image: &mut Image, bytes: &[u8]| -> Result<u64, ElfError> {
+        sp -= bytes.len() as u64;
+        if sp < bottom + PAGE_SIZE {
+            return Err(ElfError::OutOfMemory);
+        }
+        image.write(sp, bytes)?;
+        Ok(sp)
+    };
+
+    let mut push_strings = |image: &mut Image, strings: &[&str]| -> Result<Vec<u64>, ElfError> {
+        strings
+            .iter()
+            .map(|s| {
+                let mut bytes = Vec::from(s.as_bytes());
+                bytes.push(0);
+                push_bytes(image, &bytes)
+            })
+            .collect()
+    };
+    let argv_ptrs = push_strings(image, argv)?;
+    let envp_ptrs = push_strings(image, envp)?;
+
+    // AT_RANDOM: 16 bytes derivados do TSC (sem fonte melhor de entropia)
+    let seed = unsafe { core::arch::x86_64::_rdtsc() };
+    let mut random = [0u8; 16];
+    random[..8].copy_from_slice(&seed.to_le_bytes());
+    random[8..].copy_from_slice(&seed.rotate_left(29).wrapping_mul(0x9E37_79B9_7F4A_7C15).to_le_bytes());
+    let random_ptr = push_bytes(image, &random)?;
+
+    let mut words: Vec<u64> = Vec::new();
+    words.push(argv_ptrs.len() as u64);
+    words.extend(&argv_ptrs);
+    words.push(0);
+    words.extend(&envp_ptrs);
+    words.push(0);
+    for &(key, value) in auxv {
+        words.extend([key, value]);
+    }
+    words.extend([AT_RANDOM, random_ptr, AT_NULL, 0]);
+
+    // argc precisa ficar alinhado em 16 bytes
+    let size = words.len() as u64 * 8;
+    let rsp = (sp - size) & !0xF;
+    if rsp < bottom + PAGE_SIZE {
+        return Err(ElfError::OutOfMemory);
+    }
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    image.write(rsp, &bytes)?;
+    Ok(rsp)
+}
+
+/// Programa carregado e mapeado, pronto para executar
+pub struct LoadedProgram {
+    pub entry: u64,
+    pub stack_pointer: u64,
+    /// Endereço onde um static-PIE foi carregado (0 para ET_EXEC)
+    pub base: u64,
+    pub space: AddressSpace,
+    /// Descritores do programa; 0, 1 e 2 começam no /dev/console
+    pub fds: FdTable,
+    /// Diretório para caminhos relativos
+    pub cwd: String,
+    /// Início do heap (logo depois do último segmento) e o `brk` atual
+    pub heap_start: u64,
+    pub brk: u64,
+}
+
+/// Carrega um ELF64 estático (ET_EXEC) ou static-PIE (ET_DYN sem PT_INTERP)
+pub fn load(data: &[u8], argv: &[&str], envp: &[&str]) -> Result<LoadedProgram, ElfError> {
+    let header = ElfHeader::parse(data)?;
+    let headers = header.program_headers(data)?;
+
+    if headers.iter().any(|ph| ph.p_type == PT_INTERP) {
+        return Err(ElfError::Dynamic);
+    }
+    let base = if header.elf_type == ET_DYN { PIE_LOAD_BASE } else { 0 };
+
+    let mut image = Image { pages: BTreeMap::new() };
+    for ph in headers.iter().filter(|ph| ph.p_type == PT_LOAD) {
+        if ph.filesz > ph.memsz {
+            return Err(ElfError::BadSegment);
+        }
+        let contents = file_range(data, ph.offset, ph.filesz)?;
+
+        let start = base.checked_add(ph.vaddr).ok_or(ElfError::BadSegment)?;
+        let end = start.checked_add(ph.memsz).ok_or(ElfError::BadSegment)?;
+        if address_space::overlaps_kernel(start, end) {
+            return Err(ElfError::BadSegment);
+        }
+        // O resto até memsz (BSS) fica com os zeros dos frames novos
+        image.reserve(start, end, segment_flags(ph))?;
+        image.write(start, contents)?;
+    }
+
+    if let Some(dynamic) = headers.iter().find(|ph| ph.p_type == PT_DYNAMIC) {
+        if header.elf_type == ET_DYN {
+            relocate(&mut image, data, dynamic, base)?;
+        }
+    }
+
+    // Os cabeçalhos de programa estão no primeiro segmento que cobre e_phoff
+    // (os PT_LOAD já foram validados acima; o PT_PHDR não)
+    let phdr = match headers.iter().find(|ph| ph.p_type == PT_PHDR) {
+        Some(ph) => base.checked_add(ph.vaddr).ok_or(ElfError::BadSegment)?,
+        None => headers
+            .iter()
+            .find(|ph| {
+                ph.p_type == PT_LOAD
+                    && ph.offset <= header.phoff
+                    && header.phoff - ph.offset < ph.filesz
+            })
+            .map_or(0, |ph| base + ph.vaddr + (header.phoff - ph.offset)),
+    };
+    let entry = base.checked_add(header.entry).ok_or(ElfError::BadSegment)?;
+    let auxv = [
+        (AT_PHDR, phdr),
+        (AT_PHENT, header.phentsize as u64),
+        (AT_PHNUM, header.phnum as u64),
+        (AT_PAGESZ, PAGE_SIZE),
+        (AT_BASE, 0),
+        (AT_ENTRY, entry),
+    ];
+    let stack_pointer = build_stack(&mut image, argv, envp, &auxv)?;
+
+    let heap_start = headers
+        .iter()
+        .filter(|ph| ph.p_type == PT_LOAD)
+        .map(|ph| base + ph.vaddr + ph.memsz)
+        .max()
+        .unwrap_or(0)
+        .next_multiple_of(PAGE_SIZE);
+
+    let mut fds = FdTable::new();
+    for _ in 0..3 {
+        let _ = fds.open("/dev/console");
+    }
+
+    let space = image.into_address_space()?;
+    Ok(LoadedProgram {
+        entry,
+        stack_pointer,
+        base,
+        space,
+        fds,
+        cwd: String::from("/"),
+        heap_start,
+        brk: heap_start,
+    })
+}
+
+// Monta um quadro de `iretq` e desce para o ring 3 na entrada do programa;
+// `elf_return` (chamado pelo `exit`) volta para quem chamou `elf_enter`,
+// com o status em eax
+core::arch::global_asm!(
+    ".global elf_enter",
+    "elf_enter:",
+    "push rbx",
+    "push rbp",
+    "push r12",
+    "push r13",
+    "push r14",
+    "push r15",
+    "mov [rip + {saved}], rsp",
+    // SS, RSP, RFLAGS (IF ligado), CS e RIP
+    "push rcx",
+    "push rsi",
+    "push 0x202",
+    "push rdx",
+    "push rdi",
+    // Nada do kernel vaza para o programa; rdx = 0: nenhuma função de
+    // finalização para registrar (ABI System V)
+    "xor eax, eax",
+    "xor ebx, ebx",
+    "xor ecx, ecx",
+    "xor edx, edx",
+    "xor esi, esi",
+    "xor edi, edi",
+    "xor ebp, ebp",
+    "xor r8d, r8d",
+    "xor r9d, r9d",
+    "xor r10d, r10d",
+    "xor r11d, r11d",
+    "xor r12d, r12d",
+    "xor r13d, r13d",
+    "xor r14d, r14d",
+    "xor r15d, r15d",
+    "iretq",
+    ".global elf_return",
+    "elf_return:",
+    "mov rsp, [rip + {saved}]",
+    "mov eax, edi",
+    "pop r15",
+    "pop r14",
+    "pop r13",
+    "pop r12",
+    "pop rbp",
+    "pop rbx",
+    "ret",
+    saved = sym SAVED_RSP,
+);
+
+/// RSP do kernel durante a execução de um programa (um por vez)
+static mut SAVED_RSP: u64 = 0;
+
+/// Serializa `LoadedProgram::run`: `SAVED_RSP` e `CURRENT` são únicos, e
+/// estágios de um pipeline rodam em tarefas diferentes
+static RUNNING: Mutex<()> = Mutex::named("elf", ());
+
+/// Programa em execução, consultado pelas syscalls
+static CURRENT: AtomicPtr<LoadedProgram> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Executa `f` com o programa em execução, se houver um
+pub fn with_current<R>(f: impl FnOnce(&mut LoadedProgram) -> R) -> Option<R> {
+    let program = CURRENT.load(Ordering::Acquire);
+    unsafe { program.as_mut() }.map(f)
+}
+
+extern "C" {
+    fn elf_enter(entry: u64, stack_pointer: u64, code_selector: u64, stack_selector: u64) -> i32;
+    fn elf_return(status: i32) -> !;
+}
+
+impl LoadedProgram {
+    /// Executa no ring 3 até o programa chamar `exit` (ou ser morto por uma
+    /// exceção); retorna o status de saída. O `_start` não pode simplesmente
+    /// retornar: no topo da pilha está argc.
+    pub fn run(&mut self) -> i32 {
+        let _running = RUNNING.lock();
+        self.space.activate();
+        CURRENT.store(self, Ordering::Release);
+        let selectors = gdt::selectors();
+
+        // Entradas vindas do ring 3 usam a pilha da tarefa logo abaixo do
+        // quadro de `elf_enter`, sem pisar no que está acima dele
+        let rsp: u64;
+        unsafe { core::arch::asm!("mov {}, rsp", out(reg) rsp) };
+        let previous_stack = scheduler::set_kernel_stack(VirtAddr::new((rsp - 512) & !0xF));
+
+        let status = unsafe {
+            elf_enter(
+                self.entry,
+                self.stack_pointer,
+                selectors.user_code_selector.0 as u64,
+                selectors.user_data_selector.0 as u64,
+            )
+        };
+        // A saída pode ter vindo de dentro de uma syscall ou exceção, com as
+        // interrupções ainda desligadas
+        x86_64::instructions::interrupts::enable();
+        CURRENT.store(core::ptr::null_mut(), Ordering::Release);
+        scheduler::set_kernel_stack(previous_stack);
+        address_space::activate_kernel();
+        status
+    }
+
+    /// Destrói o espaço de endereçamento, devolvendo todos os frames, e
+    /// fecha os descritores que o programa deixou abertos
+    pub fn unload(self) {
+        drop(self.space);
+    }
+}
+
+/// Encerra o programa em execução e volta para `LoadedProgram::run`
+pub fn exit_program(status: i32) -> ! {
+    unsafe { elf_return(status) }
+}
@@ -1,13 +1,220 @@
-use alloc::{vec::Vec, string::String};
-use crate::vfs::{File, Filesystem, Directory, DirEntry};
+use alloc::{boxed::Box, vec, vec::Vec, string::String};
+use crate::block::{BlockDevice, RamDisk, SECTOR_SIZE};
+use crate::rtc::{self, DateTime, Timestamp};
+use crate::sync::Mutex;
+use crate::vfs::{Attributes, Filesystem, Directory, DirEntry, FileType, FsStats, InodeId, Metadata, VfsError};
 use crate::vga_println;
-use core::slice;
 use core::str;
 
-pub const SECTOR_SIZE: usize = 512;
+/// Campos do BIOS Parameter Block (setor 0) usados pelo driver
+#[derive(Debug, Clone, Copy)]
+pub struct BiosParameterBlock {
+    pub bytes_per_sector: u16,
+    pub sectors_per_cluster: u8,
+    pub reserved_sectors: u16,
+    pub fat_count: u8,
+    pub root_entries: u16,
+    pub total_sectors: u32,
+    pub sectors_per_fat: u16,
+}
+
+impl BiosParameterBlock {
+    /// Decodifica o BPB a partir do setor de boot
+    pub fn parse(boot: &[u8]) -> Result<Self, &'static str> {
+        if boot[510] != 0x55 || boot[511] != 0xAA {
+            return Err("assinatura de boot ausente");
+        }
+
+        let total16 = u16::from_le_bytes([boot[19], boot[20]]) as u32;
+        let total32 = u32::from_le_bytes([boot[32], boot[33], boot[34], boot[35]]);
+
+        let bpb = Self {
+            bytes_per_sector: u16::from_le_bytes([boot[11], boot[12]]),
+            sectors_per_cluster: boot[13],
+            reserved_sectors: u16::from_le_bytes([boot[14], boot[15]]),
+            fat_count: boot[16],
+            root_entries: u16::from_le_bytes([boot[17], boot[18]]),
+            total_sectors: if total16 != 0 { total16 } else { total32 },
+            sectors_per_fat: u16::from_le_bytes([boot[22], boot[23]]),
+        };
+
+        if bpb.bytes_per_sector as usize != SECTOR_SIZE {
+            return Err("tamanho de setor não suportado");
+        }
+        if bpb.sectors_per_cluster == 0 || bpb.fat_count == 0 || bpb.sectors_per_fat == 0 {
+            return Err("BPB inválido");
+        }
+
+        Ok(bpb)
+    }
+
+    /// Primeiro setor da primeira FAT
+    pub fn fat_start(&self) -> usize {
+        self.reserved_sectors as usize
+    }
+
+    /// Primeiro setor do Root Directory
+    pub fn root_dir_start(&self) -> usize {
+        self.fat_start() + self.fat_count as usize * self.sectors_per_fat as usize
+    }
+
+    /// Quantidade de setores ocupados pelo Root Directory
+    pub fn root_dir_sectors(&self) -> usize {
+        (self.root_entries as usize * 32).div_ceil(SECTOR_SIZE)
+    }
+
+    /// Primeiro setor da área de dados (cluster 2)
+    pub fn data_start(&self) -> usize {
+        self.root_dir_start() + self.root_dir_sectors()
+    }
+}
+
+/// Inode da raiz (nenhuma entrada de diretório real fica no byte 0 do disco)
+const ROOT_INODE: InodeId = 0;
+
+// Bits do byte de atributos de uma entrada de diretório
+const ATTR_READ_ONLY: u8 = 0x01;
+const ATTR_HIDDEN: u8 = 0x02;
+const ATTR_SYSTEM: u8 = 0x04;
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_ARCHIVE: u8 = 0x20;
+const ATTR_LFN: u8 = 0x0F;
+
+/// Valor de FAT que encerra uma cadeia de clusters
+const FAT_EOC: u16 = 0xFFF;
+/// Primeiro valor reservado; clusters de dados ficam abaixo dele
+const FAT_RESERVED: u16 = 0xFF0;
+
+/// Converte os campos de data/hora da FAT (resolução de 2s) em Timestamp
+fn fat_timestamp(date: u16, time: u16) -> Timestamp {
+    DateTime {
+        year: 1980 + (date >> 9),
+        month: ((date >> 5) & 0x0F) as u8,
+        day: (date & 0x1F) as u8,
+        hour: (time >> 11) as u8,
+        minute: ((time >> 5) & 0x3F) as u8,
+        second: ((time & 0x1F) * 2) as u8,
+    }
+    .to_unix()
+}
+
+/// Inverso de `fat_timestamp`: (data, hora) no formato da FAT
+fn fat_date_time(timestamp: Timestamp) -> (u16, u16) {
+    let dt = DateTime::from_unix(timestamp);
+    let year = dt.year.clamp(1980, 1980 + 127) - 1980;
+    let date = (year << 9) | ((dt.month as u16) << 5) | dt.day as u16;
+    let time = ((dt.hour as u16) << 11) | ((dt.minute as u16) << 5) | (dt.second as u16 / 2);
+    (date, time)
+}
+
+/// FAT12 usa 12 bits por entrada — lógica de decodificação
+fn fat_entry(fat: &[u8], cluster: u16) -> u16 {
+    let fat_offset = (cluster as usize * 3) / 2;
+    let (first, second) = (fat[fat_offset], fat[fat_offset + 1]);
+
+    if cluster & 1 == 0 {
+        ((second as u16 & 0x0F) << 8) | first as u16
+    } else {
+        ((second as u16) << 4) | ((first as u16 & 0xF0) >> 4)
+    }
+}
+
+/// Grava uma entrada de 12 bits sem mexer no meio byte da vizinha
+fn set_fat_entry(fat: &mut [u8], cluster: u16, value: u16) {
+    let fat_offset = (cluster as usize * 3) / 2;
+
+    if cluster & 1 == 0 {
+        fat[fat_offset] = value as u8;
+        fat[fat_offset + 1] = (fat[fat_offset + 1] & 0xF0) | ((value >> 8) as u8 & 0x0F);
+    } else {
+        fat[fat_offset] = (fat[fat_offset] & 0x0F) | ((value << 4) as u8);
+        fat[fat_offset + 1] = (value >> 4) as u8;
+    }
+}
+
+/// Nome 8.3 como gravado na entrada (maiúsculas, completado com espaços);
+/// `None` se `name` não cabe no formato
+fn short_name(name: &str) -> Option<[u8; 11]> {
+    let (base, ext) = name.rsplit_once('.').unwrap_or((name, ""));
+    let valid = |part: &str, max: usize| {
+        part.len() <= max
+            && part.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'()-@^_`{}~".contains(&b))
+    };
+    if base.is_empty() || !valid(base, 8) || !valid(ext, 3) {
+        return None;
+    }
+
+    let mut raw = [b' '; 11];
+    for (dst, b) in raw[..8].iter_mut().zip(base.bytes()) {
+        *dst = b.to_ascii_uppercase();
+    }
+    for (dst, b) in raw[8..].iter_mut().zip(ext.bytes()) {
+        *dst = b.to_ascii_uppercase();
+    }
+    Some(raw)
+}
+
+/// Atualiza primeiro cluster, tamanho e datas de modificação/acesso de uma
+/// entrada crua de 32 bytes
+fn set_entry_data(raw: &mut [u8], cluster: u16, size: u32) {
+    let (date, time) = fat_date_time(rtc::now());
+    raw[18..20].copy_from_slice(&date.to_le_bytes());
+    raw[22..24].copy_from_slice(&time.to_le_bytes());
+    raw[24..26].copy_from_slice(&date.to_le_bytes());
+    raw[26..28].copy_from_slice(&cluster.to_le_bytes());
+    raw[28..32].copy_from_slice(&size.to_le_bytes());
+}
+
+/// Entrada de diretório FAT decodificada (uso interno do driver)
+#[derive(Debug, Clone)]
+struct FatEntry {
+    name: String,
+    attr: u8,
+    cluster: u16,
+    size: u32,
+    created: Timestamp,
+    modified: Timestamp,
+    accessed: Timestamp,
+}
+
+impl FatEntry {
+    fn is_dir(&self) -> bool {
+        self.attr & ATTR_DIRECTORY != 0
+    }
+
+    /// Sem donos na FAT: tudo pertence a root; somente leitura tira os bits de escrita
+    fn metadata(&self, inode: InodeId) -> Metadata {
+        let (file_type, size) = if self.is_dir() {
+            (FileType::Directory, 0)
+        } else {
+            (FileType::File, self.size as u64)
+        };
+
+        let mut metadata = Metadata::new(inode, file_type, size);
+        metadata.created = self.created;
+        metadata.modified = self.modified;
+        metadata.accessed = self.accessed;
+        metadata.attributes = Attributes {
+            readonly: self.attr & ATTR_READ_ONLY != 0,
+            hidden: self.attr & ATTR_HIDDEN != 0,
+            system: self.attr & ATTR_SYSTEM != 0,
+        };
+        if metadata.attributes.readonly {
+            metadata.mode &= !0o222;
+        }
+        metadata
+    }
+}
 
 pub struct Fat12Volume {
-    image: &'static [u8],
+    device: &'static dyn BlockDevice,
+    bpb: BiosParameterBlock,
+    /// Cópia da primeira FAT, lida na montagem e gravada em todas as
+    /// cópias do disco a cada alteração
+    fat: Mutex<Vec<u8>>,
+    /// Serializa as operações que alteram o volume
+    writer: Mutex<()>,
 }
 
 impl Fat12Volume {
@@ -16,19 +223,66 @@ impl Fat12Volume {
         let data = env!("FAT12_BYTES");
         let decoded = base64::decode(data).expect("base64 inválido");
         let image = Box::leak(decoded.into_boxed_slice());
-        Self { image }
+        let disk = Box::leak(Box::new(RamDisk::new("ram0", image)));
+        crate::block::register(disk);
+        Self::from_device(disk).expect("imagem FAT12 embutida inválida")
+    }
+
+    /// Monta um volume FAT12 a partir de um dispositivo de blocos
+    pub fn from_device(device: &'static dyn BlockDevice) -> Result<Self, &'static str> {
+        let mut boot = [0u8; SECTOR_SIZE];
+        device.read_sectors(0, &mut boot)?;
+        let bpb = BiosParameterBlock::parse(&boot)?;
+
+        let mut fat = vec![0u8; bpb.sectors_per_fat as usize * SECTOR_SIZE];
+        device.read_sectors(bpb.fat_start() as u64, &mut fat)?;
+
+        Ok(Self {
+            device,
+            bpb,
+            fat: Mutex::named("fat12", fat),
+            writer: Mutex::named("fat12-writer", ()),
+        })
     }
 
     /// Lê um setor lógico (512 bytes)
-    pub fn read_sector(&self, lba: usize) -> &[u8] {
-        let start = lba * SECTOR_SIZE;
-        let end = start + SECTOR_SIZE;
-        &self.image[start..end]
+    pub fn read_sector(&self, lba: usize) -> [u8; SECTOR_SIZE] {
+        let mut buf = [0u8; SECTOR_SIZE];
+        if self.device.read_sectors(lba as u64, &mut buf).is_err() {
+            vga_println!("FAT12: falha ao ler setor {}", lba);
+        }
+        buf
+    }
+
+    /// Intervalo de setores do Root Directory
+    fn root_dir_range(&self) -> core::ops::Range<usize> {
+        let start = self.bpb.root_dir_start();
+        start..start + self.bpb.root_dir_sectors()
+    }
+
+    /// Setor inicial de um cluster de dados
+    fn cluster_to_lba(&self, cluster: u16) -> usize {
+        self.bpb.data_start() + (cluster as usize - 2) * self.bpb.sectors_per_cluster as usize
     }
 
-    /// Lê e imprime entradas do Root Directory (setores 19 a 32)
+    /// Tamanho de um cluster em bytes
+    fn cluster_size(&self) -> usize {
+        self.bpb.sectors_per_cluster as usize * SECTOR_SIZE
+    }
+
+    /// Lê todos os setores de um cluster
+    fn read_cluster(&self, cluster: u16) -> Vec<u8> {
+        let mut buf = vec![0u8; self.cluster_size()];
+        let lba = self.cluster_to_lba(cluster) as u64;
+        if self.device.read_sectors(lba, &mut buf).is_err() {
+            vga_println!("FAT12: falha ao ler cluster {}", cluster);
+        }
+        buf
+    }
+
+    /// Lê e imprime entradas do Root Directory
     pub fn list_root_dir(&self) {
-        for sector in 19..=32 {
+        for sector in self.root_dir_range() {
             let data = self.read_sector(sector);
 
             for entry in data.chunks(32) {
@@ -50,104 +304,342 @@ impl Fat12Volume {
             }
         }
     }
-}
 
-/// FAT12 usa 12 bits por entrada — lógica de decodificação
-fn read_fat_entry(&self, cluster: u16) -> u16 {
-    let fat_offset = (cluster as usize * 3) / 2;
-    let fat = self.read_fat(); // setores 1–9 (9 * 512 = 4608 bytes)
+    fn read_fat_entry(&self, cluster: u16) -> u16 {
+        fat_entry(&self.fat.lock(), cluster)
+    }
 
-    let (first, second) = (fat[fat_offset], fat[fat_offset + 1]);
+    /// Clusters de dados que cabem no disco
+    fn data_clusters(&self) -> usize {
+        let data_sectors = (self.bpb.total_sectors as usize).saturating_sub(self.bpb.data_start());
+        data_sectors / self.bpb.sectors_per_cluster as usize
+    }
 
-    if cluster & 1 == 0 {
-        ((second as u16 & 0x0F) << 8) | first as u16
-    } else {
-        ((second as u16) << 4) | ((first as u16 & 0xF0) >> 4)
+    /// Fim (exclusivo) dos clusters utilizáveis; a FAT pode ter menos
+    /// entradas que o disco tem clusters
+    fn cluster_limit(&self) -> u16 {
+        let fat_entries = self.fat.lock().len() * 2 / 3;
+        (self.data_clusters() + 2).min(fat_entries).min(FAT_RESERVED as usize) as u16
     }
-}
 
-/// Retorna slice com os 9 setores da FAT
-fn read_fat(&self) -> &[u8] {
-    let start = SECTOR_SIZE * 1;
-    let end = SECTOR_SIZE * 10;
-    &self.image[start..end]
-}
+    /// Decodifica uma entrada de 32 bytes (ignora livres, LFN e rótulo de volume)
+    fn decode_entry(entry: &[u8]) -> Option<FatEntry> {
+        if entry[0] == 0x00 || entry[0] == 0xE5 {
+            return None;
+        }
 
-fn read_directory_from_cluster(&self, start_cluster: u16) -> Vec<DirEntry> {
-    let mut entries = Vec::new();
-    let mut cluster = start_cluster;
+        let attr = entry[11];
+        if attr & ATTR_LFN == ATTR_LFN || attr & ATTR_VOLUME_ID != 0 {
+            return None;
+        }
 
-    while cluster < 0xFF8 {
-        let lba = 33 + (cluster - 2) as usize;
-        let data = self.read_sector(lba);
+        let raw_name = &entry[0..11];
+        let name = core::str::from_utf8(raw_name).unwrap_or("").trim().replace(" ", "");
+
+        let word = |at: usize| u16::from_le_bytes([entry[at], entry[at + 1]]);
+        let cluster = word(26);
+        let size = u32::from_le_bytes([entry[28], entry[29], entry[30], entry[31]]);
+
+        Some(FatEntry {
+            name,
+            attr,
+            cluster,
+            size,
+            created: fat_timestamp(word(16), word(14)),
+            modified: fat_timestamp(word(24), word(22)),
+            // Só a data do último acesso é registrada
+            accessed: fat_timestamp(word(18), 0),
+        })
+    }
 
-        for entry in data.chunks(32) {
-            if entry[0] == 0x00 || entry[0] == 0xE5 {
-                continue;
+    /// Entradas de um setor/cluster de diretório, com o inode de cada uma
+    /// (o inode é o deslocamento em bytes da entrada no disco)
+    fn decode_entries(data: &[u8], first_lba: usize, out: &mut Vec<(FatEntry, InodeId)>) {
+        for (i, raw) in data.chunks(32).enumerate() {
+            if let Some(entry) = Self::decode_entry(raw) {
+                let inode = (first_lba * SECTOR_SIZE + i * 32) as InodeId;
+                out.push((entry, inode));
             }
+        }
+    }
+
+    /// Entradas do Root Directory (área fixa logo após as FATs)
+    fn scan_root_directory(&self) -> Vec<(FatEntry, InodeId)> {
+        let mut entries = Vec::new();
 
-            let raw_name = &entry[0..11];
-            let name = core::str::from_utf8(raw_name).unwrap_or("").trim().replace(" ", "");
+        for sector in self.root_dir_range() {
+            let data = self.read_sector(sector);
+            Self::decode_entries(&data, sector, &mut entries);
+        }
 
-            let attr = entry[11];
-            let is_dir = attr & 0x10 != 0;
+        entries
+    }
 
-            let cluster = u16::from_le_bytes([entry[26], entry[27]]);
-            let size = u32::from_le_bytes([entry[28], entry[29], entry[30], entry[31]]);
+    fn scan_directory_from_cluster(&self, start_cluster: u16) -> Vec<(FatEntry, InodeId)> {
+        let mut entries = Vec::new();
+        let mut cluster = start_cluster;
 
-            entries.push(DirEntry {
-                name,
-                is_dir,
-                cluster,
-                size,
-            });
+        while cluster >= 2 && cluster < 0xFF8 {
+            let data = self.read_cluster(cluster);
+            Self::decode_entries(&data, self.cluster_to_lba(cluster), &mut entries);
+            cluster = self.read_fat_entry(cluster);
         }
 
-        cluster = self.read_fat_entry(cluster);
+        entries
     }
 
-    entries
-}
+    /// Lista um diretório; cluster 0 (raiz ou ".." apontando para ela) usa a área fixa
+    fn scan_directory(&self, cluster: u16) -> Vec<(FatEntry, InodeId)> {
+        if cluster == 0 {
+            self.scan_root_directory()
+        } else {
+            self.scan_directory_from_cluster(cluster)
+        }
+    }
+
+    fn root_entry() -> FatEntry {
+        FatEntry {
+            name: "/".into(),
+            attr: ATTR_DIRECTORY,
+            cluster: 0,
+            size: 0,
+            created: 0,
+            modified: 0,
+            accessed: 0,
+        }
+    }
+
+    /// Relê a entrada de diretório correspondente a um inode
+    fn entry_for_inode(&self, inode: InodeId) -> Result<FatEntry, VfsError> {
+        if inode == ROOT_INODE {
+            return Ok(Self::root_entry());
+        }
 
-pub fn find_file(&self, name: &str) -> Option<(u16, u32)> {
-    for sector in 19..=32 {
-        let data = self.read_sector(sector);
+        let lba = inode as usize / SECTOR_SIZE;
+        let offset = inode as usize % SECTOR_SIZE;
+        let data = self.read_sector(lba);
+        Self::decode_entry(&data[offset..offset + 32]).ok_or(VfsError::NotFound)
+    }
+
+    /// Compara um nome 8.3 (sem espaços) aceitando também a forma "NOME.EXT"
+    fn name_matches(entry_name: &str, query: &str) -> bool {
+        entry_name.eq_ignore_ascii_case(query)
+            || entry_name.eq_ignore_ascii_case(&query.replace(".", ""))
+    }
 
-        for entry in data.chunks(32) {
-            if entry[0] == 0x00 || entry[0] == 0xE5 {
-                continue;
+    /// Resolve um caminho componente a componente a partir da raiz
+    fn lookup_entry(&self, path: &str) -> Result<(FatEntry, InodeId), VfsError> {
+        let mut current = (Self::root_entry(), ROOT_INODE);
+
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            if !current.0.is_dir() {
+                return Err(VfsError::NotADirectory);
             }
 
-            let raw_name = &entry[0..11];
-            let file_name = core::str::from_utf8(raw_name).unwrap_or("").trim();
+            current = self
+                .scan_directory(current.0.cluster)
+                .into_iter()
+                .find(|(e, _)| Self::name_matches(&e.name, part))
+                .ok_or(VfsError::NotFound)?;
 
-            let cleaned_name = file_name.replace(" ", "");
+            // ".." de um subdiretório de primeiro nível aponta para a raiz
+            if current.0.is_dir() && current.0.cluster == 0 {
+                current = (Self::root_entry(), ROOT_INODE);
+            }
+        }
 
-            if cleaned_name.eq_ignore_ascii_case(name) {
-                let cluster = u16::from_le_bytes([entry[26], entry[27]]);
-                let size = u32::from_le_bytes([
-                    entry[28], entry[29], entry[30], entry[31],
-                ]);
-                return Some((cluster, size));
+        Ok(current)
+    }
+
+    pub fn find_file(&self, name: &str) -> Option<(u16, u32)> {
+        let (entry, _) = self.lookup_entry(name).ok()?;
+        Some((entry.cluster, entry.size))
+    }
+
+    /// Lê o conteúdo de um arquivo seguindo a cadeia de clusters
+    fn read_chain(&self, mut cluster: u16, size: u32) -> Vec<u8> {
+        let mut remaining = size as usize;
+        let mut data = Vec::new();
+
+        while cluster >= 2 && cluster < 0xFF8 && remaining > 0 {
+            let content = self.read_cluster(cluster);
+            let to_read = core::cmp::min(remaining, content.len());
+            data.extend_from_slice(&content[..to_read]);
+
+            remaining -= to_read;
+            cluster = self.read_fat_entry(cluster);
+        }
+
+        data
+    }
+
+    /// Clusters de uma cadeia, na ordem (limitada ao tamanho do disco, para
+    /// uma FAT corrompida com ciclo não prender o laço)
+    fn chain(&self, mut cluster: u16) -> Vec<u16> {
+        let limit = self.cluster_limit();
+        let mut clusters = Vec::new();
+        while (2..limit).contains(&cluster) && clusters.len() < limit as usize {
+            clusters.push(cluster);
+            cluster = self.read_fat_entry(cluster);
+        }
+        clusters
+    }
+
+    /// Aumenta ou encurta `clusters` (uma cadeia inteira) para `count`
+    /// clusters, só na FAT em memória; clusters novos não são zerados
+    fn resize_chain(&self, clusters: &mut Vec<u16>, count: usize) -> Result<(), VfsError> {
+        let limit = self.cluster_limit();
+        let mut fat = self.fat.lock();
+
+        if clusters.len() < count {
+            let missing = count - clusters.len();
+            let free: Vec<u16> = (2..limit).filter(|&c| fat_entry(&fat, c) == 0).take(missing).collect();
+            if free.len() < missing {
+                return Err(VfsError::NoSpace);
             }
+            clusters.extend(free);
+        } else {
+            for &cluster in &clusters[count..] {
+                set_fat_entry(&mut fat, cluster, 0);
+            }
+            clusters.truncate(count);
+        }
+
+        for (i, &cluster) in clusters.iter().enumerate() {
+            set_fat_entry(&mut fat, cluster, clusters.get(i + 1).copied().unwrap_or(FAT_EOC));
         }
+        Ok(())
+    }
+
+    /// Grava a FAT em memória em todas as cópias do disco
+    fn flush_fat(&self) -> Result<(), VfsError> {
+        let fat = self.fat.lock();
+        for copy in 0..self.bpb.fat_count as usize {
+            let lba = self.bpb.fat_start() + copy * self.bpb.sectors_per_fat as usize;
+            self.device.write_sectors(lba as u64, &fat).map_err(|_| VfsError::Io)?;
+        }
+        Ok(())
+    }
+
+    fn write_cluster(&self, cluster: u16, data: &[u8]) -> Result<(), VfsError> {
+        let lba = self.cluster_to_lba(cluster) as u64;
+        self.device.write_sectors(lba, data).map_err(|_| VfsError::Io)
+    }
+
+    /// Altera no disco os 32 bytes da entrada de diretório de `inode`
+    fn update_entry(&self, inode: InodeId, update: impl FnOnce(&mut [u8])) -> Result<(), VfsError> {
+        let lba = inode / SECTOR_SIZE as u64;
+        let offset = inode as usize % SECTOR_SIZE;
+        let mut sector = [0u8; SECTOR_SIZE];
+        self.device.read_sectors(lba, &mut sector).map_err(|_| VfsError::Io)?;
+        update(&mut sector[offset..offset + 32]);
+        self.device.write_sectors(lba, &sector).map_err(|_| VfsError::Io)
+    }
+
+    /// Entrada de um arquivo que pode ser alterado
+    fn writable_entry(&self, inode: InodeId) -> Result<FatEntry, VfsError> {
+        let entry = self.entry_for_inode(inode)?;
+        if entry.is_dir() {
+            return Err(VfsError::IsADirectory);
+        }
+        if entry.attr & ATTR_READ_ONLY != 0 {
+            return Err(VfsError::ReadOnly);
+        }
+        Ok(entry)
+    }
+
+    /// Grava `buf` em `offset`, alocando os clusters que faltarem; o trecho
+    /// entre o fim antigo e `offset` vira zeros. Chamado com `writer` travado.
+    fn write_locked(&self, inode: InodeId, entry: &FatEntry, offset: u64, buf: &[u8]) -> Result<(), VfsError> {
+        let end = offset
+            .checked_add(buf.len() as u64)
+            .filter(|&end| end <= u32::MAX as u64)
+            .ok_or(VfsError::FileTooLarge)?;
+        let size = entry.size as u64;
+        let cluster_size = self.cluster_size() as u64;
+
+        let mut clusters = self.chain(entry.cluster);
+        let existing = clusters.len();
+        let needed = (end.div_ceil(cluster_size) as usize).max(existing);
+        self.resize_chain(&mut clusters, needed)?;
+
+        let start = offset.min(size);
+        let mut touched = (start / cluster_size) as usize..end.div_ceil(cluster_size) as usize;
+        let result = touched.try_for_each(|index| {
+            let cluster_start = index as u64 * cluster_size;
+            let mut data = if index < existing {
+                self.read_cluster(clusters[index])
+            } else {
+                vec![0u8; cluster_size as usize]
+            };
+            for pos in start.max(cluster_start)..end.min(cluster_start + cluster_size) {
+                data[(pos - cluster_start) as usize] = if pos < offset { 0 } else { buf[(pos - offset) as usize] };
+            }
+            self.write_cluster(clusters[index], &data)
+        });
+        if let Err(e) = result {
+            // Devolve os clusters recém-alocados; o que já estava no arquivo fica
+            self.resize_chain(&mut clusters, existing)?;
+            return Err(e);
+        }
+
+        self.flush_fat()?;
+        let first = clusters.first().copied().unwrap_or(0);
+        self.update_entry(inode, |raw| set_entry_data(raw, first, end.max(size) as u32))
+    }
+
+    /// Encurta o arquivo para `size` bytes, liberando os clusters que sobram.
+    /// Chamado com `writer` travado.
+    fn shrink_locked(&self, inode: InodeId, entry: &FatEntry, size: u64) -> Result<(), VfsError> {
+        let mut clusters = self.chain(entry.cluster);
+        self.resize_chain(&mut clusters, size.div_ceil(self.cluster_size() as u64) as usize)?;
+        self.flush_fat()?;
+        let first = clusters.first().copied().unwrap_or(0);
+        self.update_entry(inode, |raw| set_entry_data(raw, first, size as u32))
     }
-    None
-}
 
-pub fn read_file_contents(&self, filename: &str) {
-    if let Some((mut cluster, size)) = self.find_file(filename) {
-        vga_println!("Arquivo {} ({} bytes)", filename, size);
+    /// Inode (posição no disco) de uma entrada livre do diretório; um
+    /// subdiretório cheio ganha mais um cluster, a raiz tem tamanho fixo
+    fn free_slot(&self, dir_cluster: u16) -> Result<InodeId, VfsError> {
+        let sectors: Vec<usize> = if dir_cluster == 0 {
+            self.root_dir_range().collect()
+        } else {
+            let per_cluster = self.bpb.sectors_per_cluster as usize;
+            self.chain(dir_cluster)
+                .into_iter()
+                .flat_map(|c| {
+                    let lba = self.cluster_to_lba(c);
+                    lba..lba + per_cluster
+                })
+                .collect()
+        };
+
+        for lba in sectors {
+            let data = self.read_sector(lba);
+            if let Some(i) = data.chunks(32).position(|raw| raw[0] == 0x00 || raw[0] == 0xE5) {
+                return Ok((lba * SECTOR_SIZE + i * 32) as InodeId);
+            }
+        }
+        if dir_cluster == 0 {
+            return Err(VfsError::NoSpace);
+        }
 
-        let mut remaining = size;
-        while cluster < 0xFF8 {
-            let lba = 33 + (cluster - 2) as usize;
-            let sector = self.read_sector(lba);
+        let mut clusters = self.chain(dir_cluster);
+        let last = clusters.len();
+        self.resize_chain(&mut clusters, last + 1)?;
+        if let Err(e) = self.write_cluster(clusters[last], &vec![0u8; self.cluster_size()]) {
+            self.resize_chain(&mut clusters, last)?;
+            return Err(e);
+        }
+        self.flush_fat()?;
+        Ok((self.cluster_to_lba(clusters[last]) * SECTOR_SIZE) as InodeId)
+    }
 
-            let to_read = core::cmp::min(remaining, SECTOR_SIZE as u32);
-            let content = &sector[..to_read as usize];
+    pub fn read_file_contents(&self, filename: &str) {
+        if let Some((cluster, size)) = self.find_file(filename) {
+            vga_println!("Arquivo {} ({} bytes)", filename, size);
 
-            for &byte in content {
+            for &byte in &self.read_chain(cluster, size) {
                 if byte == b'\r' || byte == 0 {
                     continue;
                 } else if byte == b'\n' {
@@ -158,115 +650,152 @@ pub fn read_file_contents(&self, filename: &str) {
                 }
             }
 
-            remaining -= to_read;
-            cluster = self.read_fat_entry(cluster);
+            vga_println!();
+        } else {
+            vga_println!("Arquivo '{}' não encontrado", filename);
         }
-
-        vga_println!();
-    } else {
-        vga_println!("Arquivo '{}' não encontrado", filename);
     }
 }
 
 impl Filesystem for Fat12Volume {
-    fn open(&self, filename: &str) -> Option<File> {
-        let (mut cluster, size) = self.find_file(filename)?;
-        let mut remaining = size;
-        let mut data = Vec::new();
-
-        while cluster < 0xFF8 && remaining > 0 {
-            let lba = 33 + (cluster - 2) as usize;
-            let sector = self.read_sector(lba);
-            let to_read = core::cmp::min(remaining, SECTOR_SIZE as u32);
-            data.extend_from_slice(&sector[..to_read as usize]);
+    fn name(&self) -> &str {
+        "fat12"
+    }
 
-            remaining -= to_read;
-            cluster = self.read_fat_entry(cluster);
-        }
+    fn lookup(&self, path: &str) -> Result<InodeId, VfsError> {
+        Ok(self.lookup_entry(path)?.1)
+    }
 
-        Some(File {
-            name: filename.to_string(),
-            pos: 0,
-            data,
-        })
+    fn stat(&self, inode: InodeId) -> Result<Metadata, VfsError> {
+        Ok(self.entry_for_inode(inode)?.metadata(inode))
     }
 
-    fn open(&self, path: &str) -> Option<File> {
-        let path = path.trim_matches('/');
+    /// Lê só os clusters necessários, pulando a cadeia até `offset`
+    fn read_at(&self, inode: InodeId, offset: u64, buf: &mut [u8]) -> Result<usize, VfsError> {
+        let entry = self.entry_for_inode(inode)?;
+        if entry.is_dir() {
+            return Err(VfsError::IsADirectory);
+        }
+
+        let size = entry.size as u64;
+        if offset >= size || buf.is_empty() {
+            return Ok(0);
+        }
 
-        if let Some((mut cluster, size)) = self.find_file(path) {
-            let mut remaining = size;
-            let mut data = Vec::new();
+        let cluster_size = self.cluster_size() as u64;
+        let mut cluster = entry.cluster;
+        for _ in 0..offset / cluster_size {
+            cluster = self.read_fat_entry(cluster);
+            if cluster < 2 || cluster >= 0xFF8 {
+                return Err(VfsError::Io);
+            }
+        }
 
-            while cluster < 0xFF8 && remaining > 0 {
-                let lba = 33 + (cluster - 2) as usize;
-                let sector = self.read_sector(lba);
-                let to_read = core::cmp::min(remaining, SECTOR_SIZE as u32);
-                data.extend_from_slice(&sector[..to_read as usize]);
+        let to_read = core::cmp::min(buf.len() as u64, size - offset) as usize;
+        let mut done = 0;
+        let mut in_cluster = (offset % cluster_size) as usize;
 
-                remaining -= to_read;
-                cluster = self.read_fat_entry(cluster);
+        while done < to_read {
+            if cluster < 2 || cluster >= 0xFF8 {
+                return Err(VfsError::Io);
             }
+            let data = self.read_cluster(cluster);
+            let n = core::cmp::min(to_read - done, data.len() - in_cluster);
+            buf[done..done + n].copy_from_slice(&data[in_cluster..in_cluster + n]);
 
-            return Some(File {
-                name: path.to_string(),
-                pos: 0,
-                data,
-            });
+            done += n;
+            in_cluster = 0;
+            cluster = self.read_fat_entry(cluster);
         }
 
-        None
+        Ok(done)
     }
 
-    fn list_dir(&self, path: &str) -> Option<Directory> {
-        let path = path.trim_matches('/');
-
-        if path.is_empty() {
-            // root dir: setores 19–32
-            let mut entries = Vec::new();
+    fn write_at(&self, inode: InodeId, offset: u64, buf: &[u8]) -> Result<usize, VfsError> {
+        let _writer = self.writer.lock();
+        let entry = self.writable_entry(inode)?;
+        if !buf.is_empty() {
+            self.write_locked(inode, &entry, offset, buf)?;
+        }
+        Ok(buf.len())
+    }
 
-            for sector in 19..=32 {
-                let data = self.read_sector(sector);
+    /// Só nomes 8.3; um arquivo existente é truncado
+    fn create(&self, path: &str) -> Result<InodeId, VfsError> {
+        let _writer = self.writer.lock();
+        match self.lookup_entry(path) {
+            Ok((_, inode)) => {
+                let entry = self.writable_entry(inode)?;
+                self.shrink_locked(inode, &entry, 0)?;
+                Ok(inode)
+            }
+            Err(VfsError::NotFound) => {
+                let (parent, name) = path.trim_end_matches('/').rsplit_once('/').ok_or(VfsError::InvalidPath)?;
+                let name = short_name(name).ok_or(VfsError::InvalidPath)?;
+                let (dir, _) = self.lookup_entry(parent)?;
+                if !dir.is_dir() {
+                    return Err(VfsError::NotADirectory);
+                }
 
-                for entry in data.chunks(32) {
-                    if entry[0] == 0x00 || entry[0] == 0xE5 {
-                        continue;
-                    }
+                let inode = self.free_slot(dir.cluster)?;
+                let (date, time) = fat_date_time(rtc::now());
+                self.update_entry(inode, |raw| {
+                    raw.fill(0);
+                    raw[..11].copy_from_slice(&name);
+                    raw[11] = ATTR_ARCHIVE;
+                    raw[14..16].copy_from_slice(&time.to_le_bytes());
+                    raw[16..18].copy_from_slice(&date.to_le_bytes());
+                    set_entry_data(raw, 0, 0);
+                })?;
+                Ok(inode)
+            }
+            Err(e) => Err(e),
+        }
+    }
 
-                    let raw_name = &entry[0..11];
-                    let name = core::str::from_utf8(raw_name).unwrap_or("").trim().replace(" ", "");
+    fn truncate(&self, inode: InodeId, size: u64) -> Result<(), VfsError> {
+        let _writer = self.writer.lock();
+        let entry = self.writable_entry(inode)?;
+        let current = entry.size as u64;
+        if size <= current {
+            return self.shrink_locked(inode, &entry, size);
+        }
+        if size > u32::MAX as u64 {
+            return Err(VfsError::FileTooLarge);
+        }
+        self.write_locked(inode, &entry, current, &vec![0u8; (size - current) as usize])
+    }
 
-                    let attr = entry[11];
-                    let is_dir = attr & 0x10 != 0;
+    fn list_dir(&self, path: &str) -> Result<Directory, VfsError> {
+        let (entry, _) = self.lookup_entry(path)?;
+        if !entry.is_dir() {
+            return Err(VfsError::NotADirectory);
+        }
 
-                    let cluster = u16::from_le_bytes([entry[26], entry[27]]);
-                    let size = u32::from_le_bytes([entry[28], entry[29], entry[30], entry[31]]);
+        let entries = self
+            .scan_directory(entry.cluster)
+            .into_iter()
+            .map(|(e, inode)| DirEntry {
+                metadata: e.metadata(inode),
+                name: e.name,
+            })
+            .collect();
 
-                    entries.push(DirEntry {
-                        name,
-                        is_dir,
-                        cluster,
-                        size,
-                    });
-                }
-            }
+        Ok(Directory {
+            name: String::from(path),
+            entries,
+        })
+    }
 
-            Some(Directory {
-                name: "/".into(),
-                entries,
-            })
-        } else {
-            // subdiretório
-            let dir = self.read_directory_from_cluster(
-                self.find_file(path)?.0
-            );
-
-            Some(Directory {
-                name: path.into(),
-                entries: dir,
-            })
-        }
+    /// Conta os clusters livres (entrada 0) na FAT
+    fn statfs(&self) -> Result<FsStats, VfsError> {
+        let last = self.cluster_limit();
+        let fat = self.fat.lock();
+        let free = (2..last).filter(|&c| fat_entry(&fat, c) == 0).count();
+        Ok(FsStats {
+            block_size: self.cluster_size() as u64,
+            total_blocks: self.data_clusters() as u64,
+            free_blocks: free as u64,
+        })
     }
 }
-
-
@@ -0,0 +1,192 @@
+use alloc::{string::String, sync::Arc, vec::Vec};
+use lazy_static::lazy_static;
+
+use crate::sync::Mutex;
+use crate::vfs::{self, File, FileType, Metadata, SeekFrom, VfsError, VFS_INSTANCE};
+
+/// Número máximo de arquivos abertos por tabela
+pub const MAX_OPEN_FILES: usize = 64;
+
+/// Descritor de arquivo: índice na tabela do processo
+pub type Fd = usize;
+
+/// Diretório aberto para listagem (`getdents`)
+pub struct DirHandle {
+    pub path: String,
+    /// Índice da próxima entrada a devolver
+    pub next: usize,
+}
+
+/// Bytes em memória no lugar de um arquivo: a entrada que a shell já leu
+/// para um programa, ou a saída que ela repassa quando ele termina
+#[derive(Clone, Default)]
+pub struct MemoryStream {
+    /// Dados e posição de leitura
+    inner: Arc<Mutex<(Vec<u8>, usize)>>,
+}
+
+impl MemoryStream {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self { inner: Arc::new(Mutex::named("memory_stream", (data, 0))) }
+    }
+
+    fn read(&self, buf: &mut [u8]) -> usize {
+        let mut inner = self.inner.lock();
+        let (data, pos) = &mut *inner;
+        let n = buf.len().min(data.len() - *pos);
+        buf[..n].copy_from_slice(&data[*pos..*pos + n]);
+        *pos += n;
+        n
+    }
+
+    fn write(&self, buf: &[u8]) -> usize {
+        self.inner.lock().0.extend_from_slice(buf);
+        buf.len()
+    }
+
+    fn len(&self) -> u64 {
+        self.inner.lock().0.len() as u64
+    }
+
+    /// Tira tudo o que foi escrito
+    pub fn take(&self) -> Vec<u8> {
+        core::mem::take(&mut self.inner.lock().0)
+    }
+}
+
+/// O que um descritor referencia
+pub enum Descriptor {
+    File(File),
+    Dir(DirHandle),
+    Memory(MemoryStream),
+}
+
+/// Tabela de descritores de arquivo de um processo
+pub struct FdTable {
+    files: Vec<Option<Descriptor>>,
+}
+
+impl FdTable {
+    pub const fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    /// Coloca um handle já aberto no menor descritor livre
+    pub fn insert(&mut self, file: File) -> Result<Fd, VfsError> {
+        self.insert_descriptor(Descriptor::File(file))
+    }
+
+    fn insert_descriptor(&mut self, descriptor: Descriptor) -> Result<Fd, VfsError> {
+        if let Some(fd) = self.files.iter().position(|f| f.is_none()) {
+            self.files[fd] = Some(descriptor);
+            return Ok(fd);
+        }
+        if self.files.len() >= MAX_OPEN_FILES {
+            return Err(VfsError::TooManyOpenFiles);
+        }
+        self.files.push(Some(descriptor));
+        Ok(self.files.len() - 1)
+    }
+
+    /// Troca o que `fd` referencia, fechando o anterior
+    pub fn replace(&mut self, fd: Fd, descriptor: Descriptor) {
+        if fd < self.files.len() {
+            let _ = self.close(fd);
+            self.files[fd] = Some(descriptor);
+        } else if fd < MAX_OPEN_FILES {
+            self.files.resize_with(fd, || None);
+            self.files.push(Some(descriptor));
+        }
+    }
+
+    /// Abre `path` (absoluto) pelo VFS e retorna o descritor
+    pub fn open(&mut self, path: &str) -> Result<Fd, VfsError> {
+        let file = VFS_INSTANCE.lock().open(path)?;
+        self.insert(file)
+    }
+
+    /// Cria (ou trunca) `path` e retorna o descritor
+    pub fn create(&mut self, path: &str) -> Result<Fd, VfsError> {
+        let file = VFS_INSTANCE.lock().create(path)?;
+        self.insert(file)
+    }
+
+    /// Abre o diretório `path` (absoluto) para listagem
+    pub fn open_dir(&mut self, path: &str) -> Result<Fd, VfsError> {
+        if !VFS_INSTANCE.lock().stat(path)?.is_dir() {
+            return Err(VfsError::NotADirectory);
+        }
+        let path = vfs::normalize(path)?;
+        self.insert_descriptor(Descriptor::Dir(DirHandle { path, next: 0 }))
+    }
+
+    fn descriptor(&mut self, fd: Fd) -> Result<&mut Descriptor, VfsError> {
+        self.files
+            .get_mut(fd)
+            .and_then(|f| f.as_mut())
+            .ok_or(VfsError::BadDescriptor)
+    }
+
+    pub fn get(&mut self, fd: Fd) -> Result<&mut File, VfsError> {
+        match self.descriptor(fd)? {
+            Descriptor::File(file) => Ok(file),
+            Descriptor::Dir(_) => Err(VfsError::IsADirectory),
+            Descriptor::Memory(_) => Err(VfsError::InvalidSeek),
+        }
+    }
+
+    pub fn dir(&mut self, fd: Fd) -> Result<&mut DirHandle, VfsError> {
+        match self.descriptor(fd)? {
+            Descriptor::Dir(dir) => Ok(dir),
+            _ => Err(VfsError::NotADirectory),
+        }
+    }
+
+    pub fn read(&mut self, fd: Fd, buf: &mut [u8]) -> Result<usize, VfsError> {
+        match self.descriptor(fd)? {
+            Descriptor::Memory(stream) => Ok(stream.read(buf)),
+            _ => self.get(fd)?.read(buf),
+        }
+    }
+
+    pub fn write(&mut self, fd: Fd, buf: &[u8]) -> Result<usize, VfsError> {
+        match self.descriptor(fd)? {
+            Descriptor::Memory(stream) => Ok(stream.write(buf)),
+            _ => self.get(fd)?.write(buf),
+        }
+    }
+
+    pub fn seek(&mut self, fd: Fd, from: SeekFrom) -> Result<u64, VfsError> {
+        self.get(fd)?.seek(from)
+    }
+
+    pub fn stat(&mut self, fd: Fd) -> Result<Metadata, VfsError> {
+        match self.descriptor(fd)? {
+            Descriptor::File(file) => file.stat(),
+            Descriptor::Dir(dir) => VFS_INSTANCE.lock().stat(&dir.path),
+            Descriptor::Memory(stream) => Ok(Metadata::new(0, FileType::File, stream.len())),
+        }
+    }
+
+    pub fn close(&mut self, fd: Fd) -> Result<(), VfsError> {
+        let descriptor = self
+            .files
+            .get_mut(fd)
+            .and_then(|f| f.take())
+            .ok_or(VfsError::BadDescriptor)?;
+        if let Descriptor::File(file) = descriptor {
+            file.close();
+        }
+        Ok(())
+    }
+
+    /// Fecha todos os descritores (ex: na saída do processo)
+    pub fn close_all(&mut self) {
+        self.files.clear();
+    }
+}
+
+lazy_static! {
+    /// Tabela do kernel/shell, usada enquanto não há processos
+    pub static ref KERNEL_FDS: Mutex<FdTable> = Mutex::named("fds", FdTable::new());
+}
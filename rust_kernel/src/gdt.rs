@@ -1,49 +1,90 @@
+use core::ptr::{addr_of, addr_of_mut};
 use lazy_static::lazy_static;
 use x86_64::structures::gdt::{GlobalDescriptorTable, Descriptor, SegmentSelector};
 use x86_64::structures::tss::TaskStateSegment;
 use x86_64::VirtAddr;
-use spin::Once;
 
 pub const DOUBLE_FAULT_IST_INDEX: u16 = 0;
 
 static mut STACK: [u8; 4096 * 5] = [0; 4096 * 5];
 
-lazy_static! {
-    static ref TSS: TaskStateSegment = {
-        let mut tss = TaskStateSegment::new();
+/// Pilha usada ao entrar no kernel vindo do ring 3 (interrupções e syscall)
+static mut PRIVILEGE_STACK: [u8; 4096 * 5] = [0; 4096 * 5];
+
+/// O RSP0 muda a cada troca de tarefa, então o TSS não pode ficar atrás de
+/// uma referência compartilhada: só é acessado por ponteiro bruto
+static mut TSS: TaskStateSegment = TaskStateSegment::new();
+
+/// Preenche as pilhas do TSS; chamado por `init` antes de carregá-lo
+fn init_tss() {
+    unsafe {
+        let tss = addr_of_mut!(TSS);
 
         // Define uma pilha dedicada para double fault
-        let stack_start = VirtAddr::from_ptr(unsafe { &STACK });
-        let stack_end = stack_start + STACK.len();
-        tss.interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] = stack_end;
+        let stack_start = VirtAddr::from_ptr(addr_of!(STACK));
+        (*tss).interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] = stack_start + STACK.len();
 
-        tss
-    };
+        // RSP0: carregado pela CPU em interrupções vindas do ring 3
+        let privilege_start = VirtAddr::from_ptr(addr_of!(PRIVILEGE_STACK));
+        (*tss).privilege_stack_table[0] = privilege_start + PRIVILEGE_STACK.len();
+    }
 }
 
 lazy_static! {
     static ref GDT: (GlobalDescriptorTable, Selectors) = {
         let mut gdt = GlobalDescriptorTable::new();
 
+        // A ordem importa para o sysret: STAR aponta para a base 0x10 e a CPU
+        // usa base+8 como SS e base+16 como CS do usuário
         let code_selector = gdt.add_entry(Descriptor::kernel_code_segment());
-        let tss_selector = gdt.add_entry(Descriptor::tss_segment(&TSS));
+        let data_selector = gdt.add_entry(Descriptor::kernel_data_segment());
+        let user_data_selector = gdt.add_entry(Descriptor::user_data_segment());
+        let user_code_selector = gdt.add_entry(Descriptor::user_code_segment());
+        // O descritor guarda só o endereço do TSS, não a referência
+        let tss_selector = gdt.add_entry(Descriptor::tss_segment(unsafe { &*addr_of!(TSS) }));
 
-        (gdt, Selectors { code_selector, tss_selector })
+        (gdt, Selectors { code_selector, data_selector, user_code_selector, user_data_selector, tss_selector })
     };
 }
 
 pub struct Selectors {
     pub code_selector: SegmentSelector,
+    pub data_selector: SegmentSelector,
+    /// Já com RPL 3
+    pub user_code_selector: SegmentSelector,
+    pub user_data_selector: SegmentSelector,
     pub tss_selector: SegmentSelector,
 }
 
+pub fn selectors() -> &'static Selectors {
+    &GDT.1
+}
+
+/// Topo da pilha de privilégio atual
+pub fn kernel_stack() -> VirtAddr {
+    unsafe { (*addr_of!(TSS)).privilege_stack_table[0] }
+}
+
+/// Troca a pilha usada ao entrar no kernel vindo do ring 3 (cada tarefa
+/// tem a sua); atualiza o RSP0 do TSS e a pilha do `syscall`
+pub fn set_kernel_stack(top: VirtAddr) {
+    // O TSS só é lido pela CPU na troca de privilégio, então pode ser
+    // alterado no lugar
+    unsafe {
+        (*addr_of_mut!(TSS)).privilege_stack_table[0] = top;
+    }
+    crate::syscall::set_kernel_stack(top);
+}
+
 pub fn init() {
-    use x86_64::instructions::segmentation::set_cs;
+    use x86_64::instructions::segmentation::{load_ss, set_cs};
     use x86_64::instructions::tables::load_tss;
 
+    init_tss();
     GDT.0.load();
     unsafe {
         set_cs(GDT.1.code_selector);
+        load_ss(GDT.1.data_selector);
         load_tss(GDT.1.tss_selector);
     }
 }
@@ -0,0 +1,271 @@
+use alloc::{boxed::Box, string::String};
+use core::str;
+
+use bootloader::BootInfo;
+
+use crate::tmpfs::TmpFs;
+use crate::vfs::{self, VfsError, VFS_INSTANCE};
+use crate::vga_println;
+
+/// Arquivo embutido pelo build.rs (vazio quando não há initramfs)
+static EMBEDDED: &[u8] = include_bytes!(env!("INITRAMFS_PATH"));
+
+// Bits de tipo do campo `mode` (iguais aos do POSIX)
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+const CPIO_HEADER_LEN: usize = 110;
+const CPIO_TRAILER: &str = "TRAILER!!!";
+const TAR_BLOCK: usize = 512;
+
+/// Formatos de arquivo aceitos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// cpio "newc" (070701) ou com checksum (070702)
+    Cpio,
+    /// tar POSIX ustar
+    Tar,
+}
+
+pub fn detect(archive: &[u8]) -> Option<Format> {
+    if archive.starts_with(b"070701") || archive.starts_with(b"070702") {
+        Some(Format::Cpio)
+    } else if archive.len() >= TAR_BLOCK && &archive[257..262] == b"ustar" {
+        Some(Format::Tar)
+    } else {
+        None
+    }
+}
+
+/// Tipo de uma entrada extraída
+enum EntryKind {
+    Dir,
+    File,
+    /// Link simbólico; o conteúdo da entrada é o destino
+    Symlink,
+    /// Link físico (tar); o conteúdo é o caminho do arquivo original
+    HardLink,
+    Other,
+}
+
+fn parse_hex(field: &[u8]) -> Result<usize, &'static str> {
+    let text = str::from_utf8(field).map_err(|_| "campo hexadecimal inválido")?;
+    usize::from_str_radix(text, 16).map_err(|_| "campo hexadecimal inválido")
+}
+
+fn parse_octal(field: &[u8]) -> Result<usize, &'static str> {
+    let text = str::from_utf8(field).map_err(|_| "campo octal inválido")?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    usize::from_str_radix(text, 8).map_err(|_| "campo octal inválido")
+}
+
+fn kind_from_mode(mode: u32) -> EntryKind {
+    match mode & S_IFMT {
+        S_IFDIR => EntryKind::Dir,
+        S_IFREG => EntryKind::File,
+        S_IFLNK => EntryKind::Symlink,
+        _ => EntryKind::Other,
+    }
+}
+
+/// Caminho absoluto dentro de `root`, sem "./" inicial
+fn target_path(root: &str, name: &str) -> Result<String, VfsError> {
+    let name = name.trim_start_matches("./").trim_start_matches('/');
+    vfs::resolve(root, name)
+}
+
+/// Cria `path` e todos os diretórios pais que faltarem
+fn mkdir_all(path: &str) -> Result<(), VfsError> {
+    let mut current = String::new();
+    for part in path.split('/').filter(|p| !p.is_empty()) {
+        current.push('/');
+        current.push_str(part);
+        match VFS_INSTANCE.lock().mkdir(&current) {
+            Ok(()) | Err(VfsError::AlreadyExists) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Grava uma entrada extraída no VFS
+fn extract(root: &str, name: &str, kind: EntryKind, data: &[u8]) -> Result<(), VfsError> {
+    let path = target_path(root, name)?;
+    if path == "/" {
+        return Ok(());
+    }
+
+    // Arquivos podem aparecer antes dos próprios diretórios no arquivo
+    if !matches!(kind, EntryKind::Dir) {
+        if let Some((parent, _)) = path.rsplit_once('/') {
+            mkdir_all(parent)?;
+        }
+    }
+
+    match kind {
+        EntryKind::Dir => mkdir_all(&path),
+        EntryKind::File => {
+            let mut file = VFS_INSTANCE.lock().create(&path)?;
+            file.write(data)?;
+            Ok(())
+        }
+        EntryKind::Symlink => {
+            let target = str::from_utf8(data).map_err(|_| VfsError::InvalidPath)?;
+            VFS_INSTANCE.lock().symlink(target, &path)
+        }
+        EntryKind::HardLink => {
+            let existing = str::from_utf8(data).map_err(|_| VfsError::InvalidPath)?;
+            let existing = target_path(root, existing)?;
+            VFS_INSTANCE.lock().link(&existing, &path)
+        }
+        EntryKind::Other => {
+            vga_println!("initramfs: ignorando {} (tipo não suportado)", path);
+            Ok(())
+        }
+    }
+}
+
+/// Extrai um cpio newc; cabeçalho+nome e dados são alinhados a 4 bytes
+fn unpack_cpio(archive: &[u8], root: &str) -> Result<usize, &'static str> {
+    let mut offset = 0;
+    let mut count = 0;
+
+    while offset + CPIO_HEADER_LEN <= archive.len() {
+        let header = &archive[offset..offset + CPIO_HEADER_LEN];
+        if &header[0..5] != b"07070" {
+            return Err("cabeçalho cpio inválido");
+        }
+
+        let field = |i: usize| parse_hex(&header[6 + i * 8..14 + i * 8]);
+        let mode = field(1)? as u32;
+        let file_size = field(6)?;
+        let name_size = field(11)?;
+
+        let name_start = offset + CPIO_HEADER_LEN;
+        let name_end = name_start + name_size;
+        if name_end > archive.len() || name_size == 0 {
+            return Err("nome cpio truncado");
+        }
+        // O tamanho do nome inclui o '\0' final
+        let name = str::from_utf8(&archive[name_start..name_end - 1]).map_err(|_| "nome cpio inválido")?;
+
+        let data_start = name_end.next_multiple_of(4);
+        let data_end = data_start + file_size;
+        if data_end > archive.len() {
+            return Err("conteúdo cpio truncado");
+        }
+
+        if name == CPIO_TRAILER {
+            return Ok(count);
+        }
+
+        extract(root, name, kind_from_mode(mode), &archive[data_start..data_end])
+            .map_err(|_| "falha ao gravar entrada do initramfs")?;
+        count += 1;
+
+        offset = data_end.next_multiple_of(4);
+    }
+
+    Err("cpio sem TRAILER!!!")
+}
+
+/// Extrai um tar ustar; termina em um bloco zerado
+fn unpack_tar(archive: &[u8], root: &str) -> Result<usize, &'static str> {
+    let mut offset = 0;
+    let mut count = 0;
+
+    while offset + TAR_BLOCK <= archive.len() {
+        let header = &archive[offset..offset + TAR_BLOCK];
+        if header.iter().all(|&b| b == 0) {
+            return Ok(count);
+        }
+
+        let cstr = |field: &[u8]| -> Result<String, &'static str> {
+            let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+            str::from_utf8(&field[..end]).map(String::from).map_err(|_| "nome tar inválido")
+        };
+
+        let mut name = cstr(&header[0..100])?;
+        let prefix = cstr(&header[345..500])?;
+        if !prefix.is_empty() {
+            name = alloc::format!("{}/{}", prefix, name);
+        }
+
+        let size = parse_octal(&header[124..136])?;
+        let kind = match header[156] {
+            b'0' | 0 => EntryKind::File,
+            b'1' => EntryKind::HardLink,
+            b'2' => EntryKind::Symlink,
+            b'5' => EntryKind::Dir,
+            _ => EntryKind::Other,
+        };
+
+        let data_start = offset + TAR_BLOCK;
+        let data_end = data_start + size;
+        if data_end > archive.len() {
+            return Err("conteúdo tar truncado");
+        }
+
+        // Links guardam o destino no campo linkname do cabeçalho
+        let data = match kind {
+            EntryKind::Symlink | EntryKind::HardLink => {
+                let field = &header[157..257];
+                &field[..field.iter().position(|&b| b == 0).unwrap_or(field.len())]
+            }
+            _ => &archive[data_start..data_end],
+        };
+
+        extract(root, &name, kind, data).map_err(|_| "falha ao gravar entrada do initramfs")?;
+        count += 1;
+
+        offset = data_start + size.next_multiple_of(TAR_BLOCK);
+    }
+
+    Ok(count)
+}
+
+/// Extrai um arquivo cpio/tar em `root`; retorna quantas entradas foram criadas
+pub fn unpack(archive: &[u8], root: &str) -> Result<usize, &'static str> {
+    match detect(archive) {
+        Some(Format::Cpio) => unpack_cpio(archive, root),
+        Some(Format::Tar) => unpack_tar(archive, root),
+        None => Err("formato de initramfs desconhecido"),
+    }
+}
+
+/// Arquivo carregado pelo bootloader junto com o kernel, já mapeado
+pub fn boot_ramdisk(boot_info: &'static BootInfo) -> Option<&'static [u8]> {
+    let addr = boot_info.ramdisk_addr.into_option()?;
+    let len = boot_info.ramdisk_len as usize;
+    (len > 0).then(|| unsafe { core::slice::from_raw_parts(addr as *const u8, len) })
+}
+
+/// Monta um tmpfs na raiz e extrai o initramfs nele.
+///
+/// `ramdisk` é o arquivo entregue pelo bootloader, se houver; sem ele usa o
+/// arquivo embutido no kernel. Retorna `false` se não há initramfs algum.
+/// O conteúdo extraído fica no heap do kernel, limitado pela capacidade do
+/// tmpfs (`tmpfs::DEFAULT_CAPACITY`).
+pub fn mount_root(ramdisk: Option<&'static [u8]>) -> bool {
+    let archive = ramdisk.unwrap_or(EMBEDDED);
+    if archive.is_empty() {
+        return false;
+    }
+
+    let root = Box::leak(Box::new(TmpFs::new()));
+    if let Err(e) = VFS_INSTANCE.lock().mount("/", root, "initramfs") {
+        vga_println!("initramfs: {}", e);
+        return false;
+    }
+
+    match unpack(archive, "/") {
+        Ok(count) => vga_println!("initramfs: {} entradas extraídas", count),
+        Err(e) => vga_println!("initramfs: {}", e),
+    }
+    true
+}
@@ -1,14 +1,19 @@
-use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
+use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode};
+use x86_64::PrivilegeLevel;
 use lazy_static::lazy_static;
 use crate::gdt::DOUBLE_FAULT_IST_INDEX;
 use crate::vga_println;
+use crate::shell::{self, Command, CommandResult, Context};
+use crate::sh_println;
 use crate::timer;
-use crate::keyboard::{read_scancode, scancode_to_ascii};
+use crate::{elf, scheduler, syscall};
+use crate::sync::IrqSpinLock;
+use crate::keyboard::read_scancode;
 
 use pic8259::ChainedPics;
-use spin::Mutex;
+use core::sync::atomic::{AtomicU64, Ordering};
 
-pub static PICS: Mutex<ChainedPics> = Mutex::new(unsafe {
+pub static PICS: IrqSpinLock<ChainedPics> = IrqSpinLock::named("pic", unsafe {
     ChainedPics::new(0x20, 0x28) // PIC master/slave
 });
 
@@ -23,11 +28,28 @@ lazy_static! {
             .set_handler_fn(double_fault_handler)
             .set_stack_index(DOUBLE_FAULT_IST_INDEX);
 
+        // Falhas de programas no ring 3 encerram só o programa
+        idt.page_fault.set_handler_fn(page_fault_handler);
+        idt.general_protection_fault.set_handler_fn(general_protection_handler);
+        idt.invalid_opcode.set_handler_fn(invalid_opcode_handler);
+
+        // Syscalls por `int 0x80` (DPL 3 para o ring 3 poder chamar)
+        unsafe {
+            idt[0x80]
+                .set_handler_addr(syscall::int80_handler_addr())
+                .set_privilege_level(PrivilegeLevel::Ring3);
+        }
 
         // Futuro: idt[32].set_handler_fn(timer_interrupt);
         idt[32].set_handler_fn(timer_interrupt_handler); // IRQ0
         // Futuro: idt[33].set_handler_fn(keyboard_interrupt);
         idt[33].set_handler_fn(keyboard_interrupt_handler); // IRQ1
+        // IRQs usadas por dispositivos PCI (INTx roteado pelo PIC)
+        idt[41].set_handler_fn(irq9_interrupt_handler);
+        idt[42].set_handler_fn(irq10_interrupt_handler);
+        idt[43].set_handler_fn(irq11_interrupt_handler);
+        idt[46].set_handler_fn(ata_primary_interrupt_handler); // IRQ14
+        idt[47].set_handler_fn(ata_secondary_interrupt_handler); // IRQ15
         idt
     };
 }
@@ -36,39 +58,114 @@ pub fn init_idt() {
     IDT.load();
 }
 
-/// Notifica ao PIC que IRQ0 foi tratada
+/// Contadores de interrupções por linha de IRQ (0..16)
+static IRQ_COUNTS: [AtomicU64; 16] = [const { AtomicU64::new(0) }; 16];
+
+/// Quantas vezes a IRQ foi tratada desde o boot
+pub fn irq_count(irq: u8) -> u64 {
+    IRQ_COUNTS[irq as usize].load(Ordering::Relaxed)
+}
+
+/// Nome das IRQs conhecidas, para /proc/interrupts e `lsirq`
+pub fn irq_name(irq: u8) -> &'static str {
+    match irq {
+        0 => "timer",
+        1 => "keyboard",
+        14 => "ata-primary",
+        15 => "ata-secondary",
+        _ => "",
+    }
+}
+
+/// Notifica ao PIC que a IRQ foi tratada (todo handler de IRQ termina aqui)
 fn send_eoi(irq: u8) {
+    if let Some(counter) = IRQ_COUNTS.get(irq.wrapping_sub(32) as usize) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
     unsafe { PICS.lock().notify_end_of_interrupt(irq); }
 }
 
+/// Handlers registrados por drivers para as IRQs compartilhadas
+static IRQ_HANDLERS: IrqSpinLock<[Option<fn()>; 16]> = IrqSpinLock::named("irq_handlers", [None; 16]);
+
+/// Linhas com stub na IDT que passam por `dispatch_irq`
+const DRIVER_IRQS: [u8; 3] = [9, 10, 11];
+
+/// Registra o handler de um driver para uma IRQ e a desmascara no PIC.
+/// Retorna false (sem desmascarar) se a linha não tem stub na IDT.
+pub fn register_irq_handler(irq: u8, handler: fn()) -> bool {
+    if !DRIVER_IRQS.contains(&irq) {
+        return false;
+    }
+    IRQ_HANDLERS.lock()[irq as usize] = Some(handler);
+    unmask_irq(irq);
+    true
+}
+
+/// Libera a IRQ na máscara do PIC (e a cascata, se for do slave)
+pub fn unmask_irq(irq: u8) {
+    let mut pics = PICS.lock();
+    unsafe {
+        let [master, slave] = pics.read_masks();
+        if irq < 8 {
+            pics.write_masks(master & !(1 << irq), slave);
+        } else {
+            pics.write_masks(master & !(1 << 2), slave & !(1 << (irq - 8)));
+        }
+    }
+}
+
+fn dispatch_irq(irq: u8) {
+    let handler = IRQ_HANDLERS.lock()[irq as usize];
+    if let Some(handler) = handler {
+        handler();
+    }
+    send_eoi(32 + irq);
+}
+
+extern "x86-interrupt" fn irq9_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    dispatch_irq(9);
+}
+
+extern "x86-interrupt" fn irq10_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    dispatch_irq(10);
+}
+
+extern "x86-interrupt" fn irq11_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    dispatch_irq(11);
+}
+
 extern "x86-interrupt" fn breakpoint_handler(stack_frame: InterruptStackFrame) {
     vga_println!("Interrupção: Breakpoint");
     vga_println!("{:#?}", stack_frame);
 }
 
-static mut TICKS: u64 = 0;
-
 extern "x86-interrupt" fn timer_interrupt_handler(_stack_frame: InterruptStackFrame) {
-    unsafe {
-        TICKS += 1;
-        if TICKS % 100 == 0 {
-            vga_println!("100 ticks (~1s)");
-        }
-    }
+    timer::tick();
     send_eoi(32); // IRQ0
+
+    // Pode trocar de tarefa; esta continua daqui (e sai pelo iretq) quando
+    // voltar a ser escolhida
+    scheduler::tick();
 }
 
 extern "x86-interrupt" fn keyboard_interrupt_handler(_stack_frame: InterruptStackFrame) {
     let scancode = read_scancode();
-
-    if let Some(c) = scancode_to_ascii(scancode) {
-        use crate::vga_buffer::vga_print;
-        vga_print!("{}", c);
-    }
+    crate::keyboard::push_scancode(scancode);
 
     send_eoi(33); // IRQ1
 }
 
+extern "x86-interrupt" fn ata_primary_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    crate::ata::PRIMARY.handle_irq();
+    send_eoi(46); // IRQ14
+}
+
+extern "x86-interrupt" fn ata_secondary_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    crate::ata::SECONDARY.handle_irq();
+    send_eoi(47); // IRQ15
+}
+
 extern "x86-interrupt" fn double_fault_handler(
     stack_frame: InterruptStackFrame,
     _error_code: u64,
@@ -76,4 +173,68 @@ extern "x86-interrupt" fn double_fault_handler(
     vga_println!("EXCEÇÃO: DOUBLE FAULT");
     vga_println!("{:#?}", stack_frame);
     loop {}
-}
\ No newline at end of file
+}
+
+/// Status de saída de um programa morto por uma exceção (128 + SIGSEGV/SIGILL)
+const STATUS_SEGFAULT: i32 = 139;
+const STATUS_ILLEGAL: i32 = 132;
+
+fn from_user(stack_frame: &InterruptStackFrame) -> bool {
+    stack_frame.code_segment & 3 == 3
+}
+
+extern "x86-interrupt" fn page_fault_handler(
+    stack_frame: InterruptStackFrame,
+    error_code: PageFaultErrorCode,
+) {
+    let address = x86_64::registers::control::Cr2::read();
+    if from_user(&stack_frame) {
+        vga_println!(
+            "Falha de segmentação em {:#x} (endereço {:#x}, {:?})",
+            stack_frame.instruction_pointer.as_u64(),
+            address.as_u64(),
+            error_code
+        );
+        elf::exit_program(STATUS_SEGFAULT);
+    }
+    panic!("EXCEÇÃO: PAGE FAULT em {:#x} ({:?})\n{:#?}", address.as_u64(), error_code, stack_frame);
+}
+
+extern "x86-interrupt" fn general_protection_handler(stack_frame: InterruptStackFrame, error_code: u64) {
+    if from_user(&stack_frame) {
+        vga_println!("Falha de proteção geral em {:#x}", stack_frame.instruction_pointer.as_u64());
+        elf::exit_program(STATUS_SEGFAULT);
+    }
+    panic!("EXCEÇÃO: GENERAL PROTECTION FAULT ({:#x})\n{:#?}", error_code, stack_frame);
+}
+
+extern "x86-interrupt" fn invalid_opcode_handler(stack_frame: InterruptStackFrame) {
+    if from_user(&stack_frame) {
+        vga_println!("Instrução ilegal em {:#x}", stack_frame.instruction_pointer.as_u64());
+        elf::exit_program(STATUS_ILLEGAL);
+    }
+    panic!("EXCEÇÃO: INVALID OPCODE\n{:#?}", stack_frame);
+}
+
+fn cmd_lsirq(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    let [master, slave] = unsafe { PICS.lock().read_masks() };
+    let mask = (slave as u16) << 8 | master as u16;
+
+    sh_println!(ctx, "IRQ  {:>10}  {:<10} {}", "Contagem", "Estado", "Uso");
+    for irq in 0..16u8 {
+        let state = if mask & (1 << irq) == 0 { "ativa" } else { "mascarada" };
+        sh_println!(ctx, "{:>3}  {:>10}  {:<10} {}", irq, irq_count(irq), state, irq_name(irq));
+    }
+    Ok(())
+}
+
+pub fn register_commands() {
+    shell::register(Command {
+        name: "lsirq",
+        usage: "",
+        help: "lista as IRQs com contadores e máscara do PIC",
+        min_args: 0,
+        max_args: Some(0),
+        handler: cmd_lsirq,
+    });
+}
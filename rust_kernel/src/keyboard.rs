@@ -1,51 +1,185 @@
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use x86_64::instructions::interrupts;
 use x86_64::instructions::port::Port;
 
+use crate::scheduler;
+use crate::sync::{IrqSpinLock, WaitQueue};
+
 const PS2_DATA_PORT: u16 = 0x60;
+const PS2_STATUS_PORT: u16 = 0x64;
+
+/// Tecla decodificada a partir dos scancodes (set 1, layout US)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    /// Ctrl + letra, sempre em minúscula (ex: Ctrl('a'))
+    Ctrl(char),
+    Enter,
+    Backspace,
+    Delete,
+    Tab,
+    Escape,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+impl Key {
+    /// Byte equivalente num terminal (Ctrl+letra vira o código de controle)
+    pub fn as_ascii(&self) -> Option<u8> {
+        match *self {
+            Key::Char(c) if c.is_ascii() => Some(c as u8),
+            Key::Ctrl(c) => Some(c as u8 & 0x1F),
+            Key::Enter => Some(b'\n'),
+            Key::Backspace => Some(0x08),
+            Key::Tab => Some(b'\t'),
+            Key::Escape => Some(0x1B),
+            Key::Delete => Some(0x7F),
+            _ => None,
+        }
+    }
+}
 
-/// Mapeamento parcial de scancodes para ASCII
-pub fn scancode_to_ascii(scancode: u8) -> Option<char> {
+/// Caractere (normal, com shift) de uma tecla imprimível
+fn printable(scancode: u8) -> Option<(char, char)> {
+    const ROW_NUMBERS: &[(char, char)] = &[
+        ('1', '!'), ('2', '@'), ('3', '#'), ('4', '$'), ('5', '%'), ('6', '^'),
+        ('7', '&'), ('8', '*'), ('9', '('), ('0', ')'), ('-', '_'), ('=', '+'),
+    ];
+    const ROW_TOP: &[(char, char)] = &[
+        ('q', 'Q'), ('w', 'W'), ('e', 'E'), ('r', 'R'), ('t', 'T'), ('y', 'Y'),
+        ('u', 'U'), ('i', 'I'), ('o', 'O'), ('p', 'P'), ('[', '{'), (']', '}'),
+    ];
+    const ROW_HOME: &[(char, char)] = &[
+        ('a', 'A'), ('s', 'S'), ('d', 'D'), ('f', 'F'), ('g', 'G'), ('h', 'H'),
+        ('j', 'J'), ('k', 'K'), ('l', 'L'), (';', ':'), ('\'', '"'), ('`', '~'),
+    ];
+    const ROW_BOTTOM: &[(char, char)] = &[
+        ('\\', '|'), ('z', 'Z'), ('x', 'X'), ('c', 'C'), ('v', 'V'), ('b', 'B'),
+        ('n', 'N'), ('m', 'M'), (',', '<'), ('.', '>'), ('/', '?'),
+    ];
+
+    let sc = scancode as usize;
     match scancode {
-        0x02 => Some('1'),
-        0x03 => Some('2'),
-        0x04 => Some('3'),
-        0x05 => Some('4'),
-        0x06 => Some('5'),
-        0x07 => Some('6'),
-        0x08 => Some('7'),
-        0x09 => Some('8'),
-        0x0A => Some('9'),
-        0x0B => Some('0'),
-        0x10 => Some('q'),
-        0x11 => Some('w'),
-        0x12 => Some('e'),
-        0x13 => Some('r'),
-        0x14 => Some('t'),
-        0x15 => Some('y'),
-        0x16 => Some('u'),
-        0x17 => Some('i'),
-        0x18 => Some('o'),
-        0x19 => Some('p'),
-        0x1E => Some('a'),
-        0x1F => Some('s'),
-        0x20 => Some('d'),
-        0x21 => Some('f'),
-        0x22 => Some('g'),
-        0x23 => Some('h'),
-        0x24 => Some('j'),
-        0x25 => Some('k'),
-        0x26 => Some('l'),
-        0x2C => Some('z'),
-        0x2D => Some('x'),
-        0x2E => Some('c'),
-        0x2F => Some('v'),
-        0x30 => Some('b'),
-        0x31 => Some('n'),
-        0x32 => Some('m'),
-        0x39 => Some(' '), // espaço
+        0x02..=0x0D => Some(ROW_NUMBERS[sc - 0x02]),
+        0x10..=0x1B => Some(ROW_TOP[sc - 0x10]),
+        0x1E..=0x29 => Some(ROW_HOME[sc - 0x1E]),
+        0x2B..=0x35 => Some(ROW_BOTTOM[sc - 0x2B]),
+        0x39 => Some((' ', ' ')),
         _ => None,
     }
 }
 
+const SC_EXTENDED: u8 = 0xE0;
+const SC_RELEASE: u8 = 0x80;
+const SC_CTRL: u8 = 0x1D;
+const SC_LEFT_SHIFT: u8 = 0x2A;
+const SC_RIGHT_SHIFT: u8 = 0x36;
+const SC_CAPS_LOCK: u8 = 0x3A;
+
+/// Máquina de estados que transforma scancodes em teclas
+pub struct KeyDecoder {
+    extended: bool,
+    shift: bool,
+    ctrl: bool,
+    caps_lock: bool,
+}
+
+impl KeyDecoder {
+    pub const fn new() -> Self {
+        Self {
+            extended: false,
+            shift: false,
+            ctrl: false,
+            caps_lock: false,
+        }
+    }
+
+    /// Processa um scancode; retorna a tecla quando uma é pressionada
+    pub fn feed(&mut self, scancode: u8) -> Option<Key> {
+        if scancode == SC_EXTENDED {
+            self.extended = true;
+            return None;
+        }
+        let extended = core::mem::replace(&mut self.extended, false);
+        let released = scancode & SC_RELEASE != 0;
+        let code = scancode & !SC_RELEASE;
+
+        // Modificadores: atualizados tanto ao pressionar quanto ao soltar
+        match code {
+            SC_CTRL => {
+                self.ctrl = !released;
+                return None;
+            }
+            // E0 2A / E0 36 são "shifts falsos" enviados junto com as setas
+            SC_LEFT_SHIFT | SC_RIGHT_SHIFT if !extended => {
+                self.shift = !released;
+                return None;
+            }
+            SC_LEFT_SHIFT | SC_RIGHT_SHIFT => return None,
+            SC_CAPS_LOCK if !released => {
+                self.caps_lock = !self.caps_lock;
+                return None;
+            }
+            _ => {}
+        }
+        if released {
+            return None;
+        }
+
+        if extended {
+            return match code {
+                0x1C => Some(Key::Enter),
+                0x47 => Some(Key::Home),
+                0x48 => Some(Key::Up),
+                0x49 => Some(Key::PageUp),
+                0x4B => Some(Key::Left),
+                0x4D => Some(Key::Right),
+                0x4F => Some(Key::End),
+                0x50 => Some(Key::Down),
+                0x51 => Some(Key::PageDown),
+                0x53 => Some(Key::Delete),
+                _ => None,
+            };
+        }
+
+        match code {
+            0x01 => return Some(Key::Escape),
+            0x0E => return Some(Key::Backspace),
+            0x0F => return Some(Key::Tab),
+            0x1C => return Some(Key::Enter),
+            _ => {}
+        }
+
+        let (normal, shifted) = printable(code)?;
+        if self.ctrl && normal.is_ascii_lowercase() {
+            return Some(Key::Ctrl(normal));
+        }
+
+        // Caps Lock só inverte letras
+        let upper = if normal.is_ascii_lowercase() { self.shift ^ self.caps_lock } else { self.shift };
+        Some(Key::Char(if upper { shifted } else { normal }))
+    }
+}
+
+/// Decodificador compartilhado por quem lê teclas do console
+static DECODER: IrqSpinLock<KeyDecoder> = IrqSpinLock::named("key_decoder", KeyDecoder::new());
+
+/// Espera a próxima tecla pressionada
+pub fn read_key() -> Key {
+    loop {
+        let scancode = wait_scancode();
+        if let Some(key) = DECODER.lock().feed(scancode) {
+            return key;
+        }
+    }
+}
+
 /// Lê a tecla (scancode) da porta 0x60
 pub fn read_scancode() -> u8 {
     unsafe {
@@ -53,3 +187,70 @@ pub fn read_scancode() -> u8 {
         port.read()
     }
 }
+
+/// Fila circular de scancodes preenchida pela IRQ1 (um produtor, sem locks)
+struct ScancodeQueue {
+    buf: [AtomicU8; 256],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+static QUEUE: ScancodeQueue = ScancodeQueue {
+    buf: [const { AtomicU8::new(0) }; 256],
+    head: AtomicUsize::new(0),
+    tail: AtomicUsize::new(0),
+};
+
+/// Chamado pelo handler da IRQ1; descarta o scancode se a fila estiver cheia
+pub fn push_scancode(scancode: u8) {
+    let tail = QUEUE.tail.load(Ordering::Relaxed);
+    let next = (tail + 1) % QUEUE.buf.len();
+    if next == QUEUE.head.load(Ordering::Acquire) {
+        return;
+    }
+    QUEUE.buf[tail].store(scancode, Ordering::Relaxed);
+    QUEUE.tail.store(next, Ordering::Release);
+    WAITERS.wake_all();
+}
+
+/// Tarefas bloqueadas esperando uma tecla
+static WAITERS: WaitQueue = WaitQueue::new();
+
+/// Consumidores concorrentes disputam a cabeça da fila
+static POP_LOCK: IrqSpinLock<()> = IrqSpinLock::named("scancode_pop", ());
+
+/// Retira o próximo scancode, se houver
+pub fn pop_scancode() -> Option<u8> {
+    let _guard = POP_LOCK.lock();
+    let head = QUEUE.head.load(Ordering::Relaxed);
+    if head == QUEUE.tail.load(Ordering::Acquire) {
+        return None;
+    }
+    let scancode = QUEUE.buf[head].load(Ordering::Relaxed);
+    QUEUE.head.store((head + 1) % QUEUE.buf.len(), Ordering::Release);
+    Some(scancode)
+}
+
+/// Espera um scancode. Com o escalonador ativo a tarefa dorme até a IRQ1;
+/// sem interrupções habilitadas, consulta o controlador PS/2.
+pub fn wait_scancode() -> u8 {
+    if interrupts::are_enabled() && scheduler::current_id().is_some() {
+        return WAITERS.wait_until(pop_scancode);
+    }
+
+    loop {
+        if let Some(scancode) = pop_scancode() {
+            return scancode;
+        }
+
+        if interrupts::are_enabled() {
+            x86_64::instructions::hlt();
+        } else {
+            let status: u8 = unsafe { Port::new(PS2_STATUS_PORT).read() };
+            if status & 1 != 0 {
+                return read_scancode();
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
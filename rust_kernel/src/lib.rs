@@ -14,13 +14,40 @@ use bootloader::{entry_point, BootInfo};
 mod vga_buffer;
 mod interrupts;
 mod timer;
+mod rtc;
 mod keyboard;
 mod memory;
+mod address_space;
 mod allocator;
 mod gdt;
+mod block;
+mod ata;
+mod pci;
+mod virtio_blk;
+mod partition;
 mod fat12;
 mod vfs;
+mod fd;
+mod tmpfs;
+mod initramfs;
+mod devfs;
+mod serial;
+mod procfs;
+mod cpu;
+mod acpi;
+mod power;
+mod elf;
+mod syscall;
+mod sync;
+mod task;
+mod sched_class;
+mod scheduler;
+mod line_editor;
+mod pipe;
+mod shell_parser;
+mod script;
 mod shell;
+mod coreutils;
 
 use core::panic::PanicInfo;
 use x86_64::instructions::interrupts as x86_interrupts;
@@ -34,8 +61,13 @@ use crate::gdt::init as init_gdt;
 use crate::interrupts::{init_idt, PICS};
 use crate::timer::init_pit;
 use crate::vga_buffer::vga_println;
+use alloc::boxed::Box;
+use devfs::DevFs;
 use fat12::Fat12Volume;
+use procfs::ProcFs;
 use shell::run_shell;
+use tmpfs::TmpFs;
+use vfs::VFS_INSTANCE;
 
 #[no_mangle]
 #pub extern "C" fn _start() -> ! {
@@ -75,12 +107,44 @@ fn init_mapper(physical_memory_offset: VirtAddr) -> OffsetPageTable<'static> {
     unsafe { OffsetPageTable::new(&mut *page_table_ptr, physical_memory_offset) }
 }
 
+/// Monta em `path` o primeiro volume FAT encontrado nos discos
+fn mount_disk(path: &str) -> bool {
+    ["vda1", "vda", "hda1", "hda"]
+        .iter()
+        .any(|name| VFS_INSTANCE.lock().mount_device(name, path).is_ok())
+}
+
+/// Raiz: initramfs (do bootloader ou embutido), com o disco em /mnt, senão
+/// partição/disco real, senão memória; depois /tmp, /dev e /proc
+fn mount_filesystems(ramdisk: Option<&'static [u8]>) {
+    let mounted = if initramfs::mount_root(ramdisk) {
+        mount_disk("/mnt");
+        true
+    } else {
+        mount_disk("/")
+    };
+    if !mounted {
+        // Sem disco: raiz em memória, com a imagem embutida em /boot
+        let root = Box::leak(Box::new(TmpFs::new()));
+        VFS_INSTANCE.lock().mount("/", root, "tmpfs").expect("falha ao montar a raiz");
+
+        let fat = Box::leak(Box::new(Fat12Volume::new()));
+        VFS_INSTANCE.lock().mount("/boot", fat, "ram0").expect("falha ao montar /boot");
+    }
+
+    let tmp = Box::leak(Box::new(TmpFs::new()));
+    VFS_INSTANCE.lock().mount("/tmp", tmp, "tmpfs").expect("falha ao montar /tmp");
+    VFS_INSTANCE.lock().mount("/dev", &DevFs, "devfs").expect("falha ao montar /dev");
+    VFS_INSTANCE.lock().mount("/proc", &ProcFs, "proc").expect("falha ao montar /proc");
+}
+
 fn kernel_main(boot_info: &'static BootInfo) -> ! {
     vga_println!("Kernel iniciado!");
     vga_println!("Endereço do BootInfo: {:?}", boot_info);
 
     init_gdt();
     init_idt();
+    syscall::init();
 
     let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
     let mut mapper = unsafe { init_mapper(phys_mem_offset) };
@@ -111,19 +175,37 @@ fn kernel_main(boot_info: &'static BootInfo) -> ! {
     .expect("Falha ao mapear heap");
 
     init_heap_allocator(HEAP_START as usize, HEAP_SIZE);
-
-    use alloc::{boxed::Box, vec::Vec};
-
-    let heap_val = Box::new(42);
-    vga_println!("Box alocado: {}", heap_val);
-
-    let mut vec = Vec::new();
-    for i in 0..10 {
-        vec.push(i * i);
-    }
-    vga_println!("Vec: {:?}", vec);
-
-    loop {}
+    memory::init_globals(mapper, frame_allocator, phys_mem_offset);
+    address_space::init();
+    acpi::init();
+
+    ata::init();
+    virtio_blk::init();
+    partition::scan_all();
+    serial::init();
+    rtc::init();
+    devfs::init();
+    mount_filesystems(initramfs::boot_ramdisk(boot_info));
+
+    shell::init();
+    script::register_commands();
+    coreutils::register_commands();
+    pci::register_commands();
+    memory::register_commands();
+    interrupts::register_commands();
+    cpu::register_commands();
+    power::register_commands();
+    scheduler::register_commands();
+
+    // O fluxo do boot vira a tarefa "kernel"; a IRQ0 passa a preemptar
+    scheduler::init();
+    unsafe { PICS.lock().initialize() };
+    interrupts::unmask_irq(0);
+    interrupts::unmask_irq(1);
+    init_pit();
+    x86_interrupts::enable();
+
+    run_shell()
 }
 
 #[panic_handler]
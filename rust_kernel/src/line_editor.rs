@@ -0,0 +1,392 @@
+use alloc::{format, string::String, vec::Vec};
+
+use crate::keyboard::{self, Key};
+use crate::vfs::{SeekFrom, VfsError, VFS_INSTANCE};
+use crate::vga_buffer::{self, WRITER};
+use crate::vga_println;
+
+/// Quantos comandos o histórico guarda
+const HISTORY_SIZE: usize = 100;
+
+/// Largura da tela, usada para alinhar a listagem de candidatos
+const SCREEN_WIDTH: usize = 80;
+
+/// Fonte de sugestões para o Tab
+pub trait Completer {
+    /// Substituições possíveis para `word`; `command` indica que a palavra
+    /// é a primeira da linha. Diretórios terminam em '/'.
+    fn complete(&self, word: &str, command: bool) -> Vec<String>;
+}
+
+/// Maior prefixo comum entre os candidatos
+fn common_prefix(candidates: &[String]) -> String {
+    let mut prefix: Vec<char> = candidates[0].chars().collect();
+    for candidate in &candidates[1..] {
+        let len = prefix
+            .iter()
+            .zip(candidate.chars())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        prefix.truncate(len);
+    }
+    prefix.into_iter().collect()
+}
+
+/// Imprime os candidatos em colunas, mostrando só o último componente
+fn print_candidates(candidates: &[String]) {
+    let names: Vec<&str> = candidates
+        .iter()
+        .map(|c| {
+            let trimmed = c.trim_end_matches('/');
+            match trimmed.rfind('/') {
+                Some(i) => &c[i + 1..],
+                None => c.as_str(),
+            }
+        })
+        .collect();
+
+    let width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0) + 2;
+    let per_row = (SCREEN_WIDTH / width).max(1);
+
+    vga_println!();
+    for row in names.chunks(per_row) {
+        let mut out = String::new();
+        for name in row {
+            out.push_str(&format!("{:<width$}", name, width = width));
+        }
+        vga_println!("{}", out.trim_end());
+    }
+}
+
+/// Editor de linha no estilo readline: cursor, atalhos Ctrl, histórico
+/// navegável e busca reversa (Ctrl+R)
+pub struct LineEditor {
+    history: Vec<String>,
+    /// Arquivo onde o histórico é persistido, se houver
+    history_file: Option<String>,
+    /// Linhas no arquivo; ao passar de `2 * HISTORY_SIZE` ele é reescrito
+    /// só com as últimas
+    saved_lines: usize,
+}
+
+/// Estado da linha sendo editada
+struct Line {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl Line {
+    fn new() -> Self {
+        Self { chars: Vec::new(), cursor: 0 }
+    }
+
+    fn set(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// Início da palavra sob o cursor (depois do último espaço)
+    fn word_start(&self) -> usize {
+        self.chars[..self.cursor]
+            .iter()
+            .rposition(|&c| c == ' ')
+            .map_or(0, |i| i + 1)
+    }
+
+    /// Troca `chars[start..cursor]` por `text`
+    fn replace_word(&mut self, start: usize, text: &str) {
+        self.chars.splice(start..self.cursor, text.chars());
+        self.cursor = start + text.chars().count();
+    }
+
+    /// Ctrl+W: apaga a palavra antes do cursor (e os espaços que a seguem)
+    fn delete_word(&mut self) {
+        let mut start = self.cursor;
+        while start > 0 && self.chars[start - 1] == ' ' {
+            start -= 1;
+        }
+        while start > 0 && self.chars[start - 1] != ' ' {
+            start -= 1;
+        }
+        self.chars.drain(start..self.cursor);
+        self.cursor = start;
+    }
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        Self {
+            history: Vec::new(),
+            history_file: None,
+            saved_lines: 0,
+        }
+    }
+
+    /// Carrega o histórico de `path` (um comando por linha) e passa a
+    /// gravá-lo lá a cada comando aceito
+    pub fn with_history_file(path: &str) -> Self {
+        let mut editor = Self::new();
+        editor.history_file = Some(path.into());
+
+        let data = VFS_INSTANCE.lock().open(path).and_then(|mut file| file.read_to_end());
+        if let Ok(data) = data {
+            editor.history = String::from_utf8_lossy(&data)
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect();
+            editor.saved_lines = editor.history.len();
+            let excess = editor.history.len().saturating_sub(HISTORY_SIZE);
+            editor.history.drain(..excess);
+        }
+        editor
+    }
+
+    /// Cópia do histórico em memória, sem o arquivo: linhas aceitas nela
+    /// não são gravadas
+    pub fn detached(&self) -> Self {
+        Self {
+            history: self.history.clone(),
+            history_file: None,
+            saved_lines: 0,
+        }
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Acrescenta um comando ao histórico (ignorando repetições seguidas)
+    pub fn add_history(&mut self, line: &str) {
+        if line.is_empty() || self.history.last().map(String::as_str) == Some(line) {
+            return;
+        }
+        if self.history.len() == HISTORY_SIZE {
+            self.history.remove(0);
+        }
+        self.history.push(line.into());
+        self.save_history();
+    }
+
+    /// Acrescenta o último comando ao arquivo, reescrevendo-o inteiro só
+    /// quando acumulou linhas demais; falhas (ex: disco cheio ou protegido)
+    /// são ignoradas
+    fn save_history(&mut self) {
+        let Some(path) = &self.history_file else { return };
+        let Some(last) = self.history.last() else { return };
+
+        if self.saved_lines < 2 * HISTORY_SIZE {
+            let opened = VFS_INSTANCE.lock().open(path);
+            let file = match opened {
+                Err(VfsError::NotFound) => VFS_INSTANCE.lock().create(path),
+                other => other,
+            };
+            if let Ok(mut file) = file {
+                let appended = file.seek(SeekFrom::End(0)).and_then(|_| file.write(format!("{}\n", last).as_bytes()));
+                if appended.is_ok() {
+                    self.saved_lines += 1;
+                }
+            }
+            return;
+        }
+
+        let mut data = String::new();
+        for line in &self.history {
+            data.push_str(line);
+            data.push('\n');
+        }
+        let file = VFS_INSTANCE.lock().create(path);
+        if let Ok(mut file) = file {
+            if file.write(data.as_bytes()).is_ok() {
+                self.saved_lines = self.history.len();
+            }
+        }
+    }
+
+    fn redraw(prompt: &str, line: &Line) {
+        let text = format!("{}{}", prompt, line.text());
+        WRITER.lock().redraw_row(&text, prompt.chars().count() + line.cursor);
+    }
+
+    /// Lê uma linha do teclado mostrando `prompt`; a linha aceita entra no
+    /// histórico. Ctrl+C descarta a linha e retorna uma string vazia.
+    pub fn read_line(&mut self, prompt: &str) -> String {
+        self.read_line_with(prompt, None)
+    }
+
+    /// Como `read_line`, com Tab completando via `completer`
+    pub fn read_line_with(&mut self, prompt: &str, completer: Option<&dyn Completer>) -> String {
+        let mut line = Line::new();
+        // Um segundo Tab seguido lista os candidatos ambíguos
+        let mut last_was_tab = false;
+        // Posição na navegação do histórico; igual a len() = linha nova
+        let mut history_pos = self.history.len();
+        let mut draft = String::new();
+
+        Self::redraw(prompt, &line);
+
+        loop {
+            let key = keyboard::read_key();
+            let double_tab = key == Key::Tab && last_was_tab;
+            last_was_tab = key == Key::Tab;
+
+            match key {
+                Key::Enter => break,
+                Key::Tab => {
+                    if let Some(completer) = completer {
+                        Self::complete(completer, &mut line, double_tab);
+                    }
+                }
+                Key::Char(c) => line.insert(c),
+                Key::Backspace => line.backspace(),
+                Key::Delete => line.delete(),
+                Key::Left => line.cursor = line.cursor.saturating_sub(1),
+                Key::Right => line.cursor = (line.cursor + 1).min(line.chars.len()),
+                Key::Home | Key::Ctrl('a') => line.cursor = 0,
+                Key::End | Key::Ctrl('e') => line.cursor = line.chars.len(),
+                Key::Ctrl('k') => line.chars.truncate(line.cursor),
+                Key::Ctrl('u') => {
+                    line.chars.drain(..line.cursor);
+                    line.cursor = 0;
+                }
+                Key::Ctrl('w') => line.delete_word(),
+                Key::Ctrl('l') => vga_buffer::clear_screen(),
+                Key::Ctrl('c') => {
+                    Self::redraw(prompt, &line);
+                    vga_println!("^C");
+                    return String::new();
+                }
+                Key::Up if history_pos > 0 => {
+                    if history_pos == self.history.len() {
+                        draft = line.text();
+                    }
+                    history_pos -= 1;
+                    line.set(&self.history[history_pos]);
+                }
+                Key::Down if history_pos < self.history.len() => {
+                    history_pos += 1;
+                    match self.history.get(history_pos) {
+                        Some(entry) => line.set(entry),
+                        None => line.set(&draft),
+                    }
+                }
+                Key::Ctrl('r') => {
+                    if self.reverse_search(&mut line) {
+                        Self::redraw(prompt, &line);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            Self::redraw(prompt, &line);
+        }
+
+        vga_println!();
+        let text = line.text();
+        self.add_history(text.trim());
+        text
+    }
+
+    /// Completa a palavra sob o cursor: um candidato é inserido direto;
+    /// vários avançam até o prefixo comum e, no Tab duplo, são listados
+    fn complete(completer: &dyn Completer, line: &mut Line, list: bool) {
+        let start = line.word_start();
+        let word: String = line.chars[start..line.cursor].iter().collect();
+        let command = line.chars[..start].iter().all(|c| *c == ' ');
+
+        let mut candidates = completer.complete(&word, command);
+        candidates.sort();
+        candidates.dedup();
+
+        match candidates.len() {
+            0 => {}
+            1 => {
+                let mut text = candidates.remove(0);
+                if !text.ends_with('/') {
+                    text.push(' ');
+                }
+                line.replace_word(start, &text);
+            }
+            _ => {
+                let prefix = common_prefix(&candidates);
+                if prefix.chars().count() > word.chars().count() {
+                    line.replace_word(start, &prefix);
+                } else if list {
+                    print_candidates(&candidates);
+                }
+            }
+        }
+    }
+
+    /// Busca incremental para trás no histórico. Ao sair, `line` recebe o
+    /// comando encontrado; retorna `true` se Enter foi usado para executá-lo
+    fn reverse_search(&self, line: &mut Line) -> bool {
+        let original = line.text();
+        let mut query = String::new();
+        // Índice do resultado atual; a próxima busca começa antes dele
+        let mut found: Option<usize> = None;
+
+        loop {
+            let matched = found.map_or("", |i| self.history[i].as_str());
+            let text = format!("(busca-reversa)`{}': {}", query, matched);
+            let cursor = "(busca-reversa)`".len() + query.chars().count();
+            WRITER.lock().redraw_row(&text, cursor);
+
+            let search_before = match keyboard::read_key() {
+                Key::Char(c) => {
+                    query.push(c);
+                    // Refina a partir do resultado atual (inclusive)
+                    found.map_or(self.history.len(), |i| i + 1)
+                }
+                Key::Backspace => {
+                    query.pop();
+                    self.history.len()
+                }
+                Key::Ctrl('r') => found.unwrap_or(self.history.len()),
+                Key::Enter => {
+                    line.set(if found.is_some() { matched } else { &original });
+                    return true;
+                }
+                Key::Ctrl('c') | Key::Ctrl('g') => {
+                    line.set(&original);
+                    return false;
+                }
+                // Qualquer outra tecla aceita o resultado para edição
+                _ => {
+                    line.set(if found.is_some() { matched } else { &original });
+                    return false;
+                }
+            };
+
+            if query.is_empty() {
+                found = None;
+                continue;
+            }
+            if let Some(i) = self.history[..search_before].iter().rposition(|h| h.contains(query.as_str())) {
+                found = Some(i);
+            }
+        }
+    }
+}
@@ -16,10 +16,7 @@ fn panic(_info: &PanicInfo) -> ! {
     loop {}
 }
 
-let fat = Box::leak(Box::new(Fat12Volume::new()));
-VFS_INSTANCE.lock().mount(fat);
-
-if let Some(mut file) = VFS_INSTANCE.lock().open("HELLOTXT") {
+if let Ok(mut file) = VFS_INSTANCE.lock().open("/boot/HELLOTXT") {
     vga_println!("Arquivo: {}", file.name);
     while let Some(b) = file.read_byte() {
         use crate::vga_buffer::vga_print;
@@ -35,12 +32,12 @@ if let Some(mut file) = VFS_INSTANCE.lock().open("HELLOTXT") {
 
 vga_println!("Conteúdo do diretório '/':");
 
-if let Some(dir) = VFS_INSTANCE.lock().list_dir("/") {
+if let Ok(dir) = VFS_INSTANCE.lock().list_dir("/") {
     for entry in dir.entries {
-        if entry.is_dir {
+        if entry.is_dir() {
             vga_println!("<DIR> {}", entry.name);
         } else {
-            vga_println!("     {} ({} bytes)", entry.name, entry.size);
+            vga_println!("     {} ({} bytes)", entry.name, entry.metadata.size);
         }
     }
 }
@@ -1,16 +1,25 @@
+use alloc::vec::Vec;
 use bootloader::bootinfo::{MemoryMap, MemoryRegionType};
+use core::sync::atomic::{AtomicU64, Ordering};
+
 use x86_64::{
     PhysAddr,
     VirtAddr,
     structures::paging::{
-        FrameAllocator, Mapper, Page, PageTableFlags, PhysFrame, Size4KiB,
+        FrameAllocator, FrameDeallocator, Mapper, OffsetPageTable, Page, PageTableFlags, PhysFrame, Size4KiB,
     }
 };
 
+use crate::shell::{self, Command, CommandResult, Context};
+use crate::sync::IrqSpinLock;
+use crate::sh_println;
+
 /// Um alocador simples de quadros físicos com base no mapa de memória fornecido
 pub struct BootInfoFrameAllocator {
     memory_map: &'static MemoryMap,
     next: usize,
+    /// Frames devolvidos, reutilizados antes de avançar no mapa
+    free: Vec<PhysFrame>,
 }
 
 impl BootInfoFrameAllocator {
@@ -19,6 +28,7 @@ impl BootInfoFrameAllocator {
         BootInfoFrameAllocator {
             memory_map,
             next: 0,
+            free: Vec::new(),
         }
     }
 
@@ -40,16 +50,62 @@ impl BootInfoFrameAllocator {
     }
 }
 
+impl BootInfoFrameAllocator {
+    /// Quantidade de frames usáveis no mapa de memória
+    pub fn total_frames(&self) -> usize {
+        self.usable_frames().count()
+    }
+
+    /// Quantidade de frames em uso (entregues e não devolvidos)
+    pub fn allocated_frames(&self) -> usize {
+        self.next - self.free.len()
+    }
+
+    /// Entrega `pages` frames fisicamente contíguos ainda não usados. A
+    /// sequência é procurada antes de avançar `next`; os frames pulados no
+    /// caminho vão para a lista de livres.
+    fn allocate_contiguous(&mut self, pages: usize) -> Option<PhysFrame> {
+        let (mut start, mut run) = (self.next, 0);
+        let mut prev: Option<PhysFrame> = None;
+        for (i, frame) in self.usable_frames().enumerate().skip(self.next) {
+            if !prev.is_some_and(|p| frame.start_address() == p.start_address() + 4096u64) {
+                start = i;
+                run = 0;
+            }
+            prev = Some(frame);
+            run += 1;
+            if run == pages {
+                let skipped: Vec<PhysFrame> = self.usable_frames().skip(self.next).take(start - self.next).collect();
+                self.free.extend(skipped);
+                self.next = start + pages;
+                return self.usable_frames().nth(start);
+            }
+        }
+        None
+    }
+}
+
 unsafe impl FrameAllocator<Size4KiB> for BootInfoFrameAllocator {
     fn allocate_frame(&mut self) -> Option<PhysFrame> {
+        if let Some(frame) = self.free.pop() {
+            return Some(frame);
+        }
         let frame = self.usable_frames().nth(self.next);
         self.next += 1;
         frame
     }
 }
 
+impl FrameDeallocator<Size4KiB> for BootInfoFrameAllocator {
+    /// O frame não pode mais estar mapeado em lugar nenhum. A lista só cresce
+    /// depois que o heap existe, pois nada é liberado antes disso.
+    unsafe fn deallocate_frame(&mut self, frame: PhysFrame) {
+        self.free.push(frame);
+    }
+}
+
 pub const HEAP_START: u64 = 0x_4444_4444_0000;
-pub const HEAP_SIZE: usize = 100 * 1024; // 100 KiB
+pub const HEAP_SIZE: usize = 8 * 1024 * 1024; // 8 MiB
 
 /// Mapeia a região do heap virtual para frames físicos
 pub fn init_heap(
@@ -77,4 +133,133 @@ pub fn init_heap(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+/// Deslocamento onde o bootloader mapeou toda a memória física
+static PHYS_MEM_OFFSET: AtomicU64 = AtomicU64::new(0);
+
+/// Mapper e alocador de frames globais, disponíveis após `init_globals`
+pub static MAPPER: IrqSpinLock<Option<OffsetPageTable<'static>>> = IrqSpinLock::named("mapper", None);
+pub static FRAME_ALLOCATOR: IrqSpinLock<Option<BootInfoFrameAllocator>> = IrqSpinLock::named("frames", None);
+
+/// Janela virtual usada para mapear BARs de dispositivos (MMIO)
+pub const MMIO_START: u64 = 0x_5555_0000_0000;
+static MMIO_NEXT: AtomicU64 = AtomicU64::new(MMIO_START);
+
+/// Torna o mapper e o alocador acessíveis para drivers (DMA/MMIO)
+pub fn init_globals(
+    mapper: OffsetPageTable<'static>,
+    frame_allocator: BootInfoFrameAllocator,
+    physical_memory_offset: VirtAddr,
+) {
+    PHYS_MEM_OFFSET.store(physical_memory_offset.as_u64(), Ordering::Relaxed);
+    *MAPPER.lock() = Some(mapper);
+    *FRAME_ALLOCATOR.lock() = Some(frame_allocator);
+}
+
+/// Converte um endereço físico para o mapeamento linear do bootloader
+pub fn phys_to_virt(addr: PhysAddr) -> VirtAddr {
+    VirtAddr::new(PHYS_MEM_OFFSET.load(Ordering::Relaxed) + addr.as_u64())
+}
+
+/// Aloca um frame zerado do alocador global
+pub fn alloc_zeroed_frame() -> Option<PhysFrame> {
+    let frame = FRAME_ALLOCATOR.lock().as_mut()?.allocate_frame()?;
+    unsafe {
+        core::ptr::write_bytes(phys_to_virt(frame.start_address()).as_mut_ptr::<u8>(), 0, 4096);
+    }
+    Some(frame)
+}
+
+/// Devolve um frame ao alocador global
+///
+/// # Safety
+/// O frame não pode estar mapeado nem ser usado depois disso.
+pub unsafe fn free_frame(frame: PhysFrame) {
+    if let Some(allocator) = FRAME_ALLOCATOR.lock().as_mut() {
+        allocator.deallocate_frame(frame);
+    }
+}
+
+/// Aloca `pages` frames fisicamente contíguos e zerados (para DMA)
+pub fn alloc_dma(pages: usize) -> Option<(PhysAddr, VirtAddr)> {
+    let first = FRAME_ALLOCATOR.lock().as_mut()?.allocate_contiguous(pages)?;
+
+    let phys = first.start_address();
+    let virt = phys_to_virt(phys);
+    unsafe {
+        core::ptr::write_bytes(virt.as_mut_ptr::<u8>(), 0, pages * 4096);
+    }
+    Some((phys, virt))
+}
+
+/// Mapeia uma região de MMIO sem cache e retorna o endereço virtual
+pub fn map_mmio(phys: PhysAddr, size: usize) -> Result<VirtAddr, &'static str> {
+    let offset = phys.as_u64() & 0xFFF;
+    let first_frame = PhysFrame::<Size4KiB>::containing_address(phys);
+    let pages = (offset as usize + size).div_ceil(4096);
+
+    let base = MMIO_NEXT.fetch_add(pages as u64 * 4096, Ordering::Relaxed);
+
+    let mut mapper = MAPPER.lock();
+    let mapper = mapper.as_mut().ok_or("mapper não inicializado")?;
+    let mut allocator = FRAME_ALLOCATOR.lock();
+    let allocator = allocator.as_mut().ok_or("alocador não inicializado")?;
+
+    let flags = PageTableFlags::PRESENT
+        | PageTableFlags::WRITABLE
+        | PageTableFlags::NO_CACHE
+        | PageTableFlags::WRITE_THROUGH;
+
+    for i in 0..pages as u64 {
+        let page = Page::<Size4KiB>::containing_address(VirtAddr::new(base + i * 4096));
+        let frame = first_frame + i;
+        unsafe {
+            mapper
+                .map_to(page, frame, flags, allocator)
+                .map_err(|_| "falha ao mapear MMIO")?
+                .flush();
+        }
+    }
+
+    Ok(VirtAddr::new(base + offset))
+}
+
+/// Retorna (frames totais, frames alocados), se o alocador global existir
+pub fn frame_stats() -> Option<(usize, usize)> {
+    let guard = FRAME_ALLOCATOR.lock();
+    let allocator = guard.as_ref()?;
+    Some((allocator.total_frames(), allocator.allocated_frames()))
+}
+
+fn cmd_meminfo(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    let (total, allocated) = frame_stats().ok_or("alocador de frames não inicializado")?;
+    sh_println!(
+        ctx,
+        "Frames: {} de {} em uso ({} KiB de {} KiB)",
+        allocated,
+        total,
+        allocated * 4,
+        total * 4
+    );
+
+    let (heap_size, heap_used) = crate::allocator::heap_stats();
+    sh_println!(
+        ctx,
+        "Heap:   {} de {} bytes em uso ({} livres)",
+        heap_used,
+        heap_size,
+        heap_size - heap_used
+    );
+    Ok(())
+}
+
+pub fn register_commands() {
+    shell::register(Command {
+        name: "meminfo",
+        usage: "",
+        help: "mostra o uso de memória física e do heap",
+        min_args: 0,
+        max_args: Some(0),
+        handler: cmd_meminfo,
+    });
+}
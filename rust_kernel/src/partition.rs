@@ -0,0 +1,321 @@
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+
+use crate::block::{self, BlockDevice, SECTOR_SIZE};
+use crate::fat12::BiosParameterBlock;
+use crate::vga_println;
+
+/// Tipos de partição MBR que indicam uma partição estendida
+const MBR_EXTENDED_TYPES: [u8; 3] = [0x05, 0x0F, 0x85];
+/// MBR protetivo de um disco GPT
+const MBR_GPT_PROTECTIVE: u8 = 0xEE;
+
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+
+/// Limite de EBRs seguidos (evita laços em tabelas corrompidas)
+const MAX_LOGICAL_PARTITIONS: usize = 64;
+
+/// Origem da partição na tabela
+#[derive(Debug, Clone)]
+pub enum PartitionKind {
+    /// Tipo de sistema de arquivos do MBR (ex: 0x01 = FAT12, 0x0C = FAT32 LBA)
+    Mbr(u8),
+    /// GUID do tipo de partição e nome UTF-16 do GPT
+    Gpt { type_guid: [u8; 16], label: String },
+}
+
+/// Uma faixa de setores de um disco, exposta como dispositivo próprio
+pub struct Partition {
+    name: String,
+    parent: &'static dyn BlockDevice,
+    start: u64,
+    sectors: u64,
+    pub kind: PartitionKind,
+}
+
+impl Partition {
+    fn check_range(&self, lba: u64, len: usize) -> Result<(), &'static str> {
+        if !lba.checked_add((len / SECTOR_SIZE) as u64).is_some_and(|end| end <= self.sectors) {
+            return Err("LBA fora da partição");
+        }
+        Ok(())
+    }
+}
+
+impl BlockDevice for Partition {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sector_count(&self) -> u64 {
+        self.sectors
+    }
+
+    fn read_sectors(&self, lba: u64, buf: &mut [u8]) -> Result<(), &'static str> {
+        self.check_range(lba, buf.len())?;
+        self.parent.read_sectors(self.start + lba, buf)
+    }
+
+    fn write_sectors(&self, lba: u64, buf: &[u8]) -> Result<(), &'static str> {
+        self.check_range(lba, buf.len())?;
+        self.parent.write_sectors(self.start + lba, buf)
+    }
+
+    fn volatile(&self) -> bool {
+        self.parent.volatile()
+    }
+}
+
+/// Entrada bruta de partição: (tipo, LBA inicial, quantidade de setores)
+struct MbrEntry {
+    kind: u8,
+    start: u32,
+    sectors: u32,
+}
+
+fn read_sector(device: &dyn BlockDevice, lba: u64) -> Result<[u8; SECTOR_SIZE], &'static str> {
+    let mut buf = [0u8; SECTOR_SIZE];
+    device.read_sectors(lba, &mut buf)?;
+    Ok(buf)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    u32_at(data, offset) as u64 | (u32_at(data, offset + 4) as u64) << 32
+}
+
+/// Decodifica as 4 entradas da tabela de um MBR/EBR
+fn mbr_entries(sector: &[u8]) -> Option<[MbrEntry; 4]> {
+    if sector[510] != 0x55 || sector[511] != 0xAA {
+        return None;
+    }
+
+    let entry = |i: usize| {
+        let base = 446 + i * 16;
+        MbrEntry {
+            kind: sector[base + 4],
+            start: u32_at(sector, base + 8),
+            sectors: u32_at(sector, base + 12),
+        }
+    };
+
+    Some([entry(0), entry(1), entry(2), entry(3)])
+}
+
+/// CRC32 (IEEE 802.3, refletido), usado pelo cabeçalho e entradas do GPT
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Faixa de setores encontrada numa tabela de partições
+pub struct PartitionInfo {
+    pub number: usize,
+    pub start: u64,
+    pub sectors: u64,
+    pub kind: PartitionKind,
+}
+
+/// Lê as partições primárias e lógicas de um MBR
+fn parse_mbr(device: &dyn BlockDevice, entries: &[MbrEntry; 4]) -> Vec<PartitionInfo> {
+    let mut parts = Vec::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.kind == 0 || entry.sectors == 0 {
+            continue;
+        }
+
+        if MBR_EXTENDED_TYPES.contains(&entry.kind) {
+            parse_extended(device, entry.start as u64, &mut parts);
+            continue;
+        }
+
+        parts.push(PartitionInfo {
+            number: i + 1,
+            start: entry.start as u64,
+            sectors: entry.sectors as u64,
+            kind: PartitionKind::Mbr(entry.kind),
+        });
+    }
+
+    parts
+}
+
+/// Percorre a cadeia de EBRs; lógicas são numeradas a partir de 5
+fn parse_extended(device: &dyn BlockDevice, extended_start: u64, parts: &mut Vec<PartitionInfo>) {
+    let mut ebr_lba = extended_start;
+    let mut number = 5;
+
+    for _ in 0..MAX_LOGICAL_PARTITIONS {
+        let Ok(sector) = read_sector(device, ebr_lba) else { return };
+        let Some(entries) = mbr_entries(&sector) else { return };
+
+        // Entrada 0: partição lógica, relativa a este EBR
+        let logical = &entries[0];
+        if logical.kind != 0 && logical.sectors != 0 {
+            parts.push(PartitionInfo {
+                number,
+                start: ebr_lba + logical.start as u64,
+                sectors: logical.sectors as u64,
+                kind: PartitionKind::Mbr(logical.kind),
+            });
+            number += 1;
+        }
+
+        // Entrada 1: próximo EBR, relativo ao início da estendida
+        let next = &entries[1];
+        if next.start == 0 || !MBR_EXTENDED_TYPES.contains(&next.kind) {
+            return;
+        }
+        ebr_lba = extended_start + next.start as u64;
+    }
+}
+
+/// Valida um cabeçalho GPT e suas entradas; retorna as partições
+fn parse_gpt_at(device: &dyn BlockDevice, header_lba: u64) -> Result<Vec<PartitionInfo>, &'static str> {
+    let header = read_sector(device, header_lba)?;
+    if &header[0..8] != GPT_SIGNATURE {
+        return Err("assinatura GPT ausente");
+    }
+
+    let header_size = u32_at(&header, 12) as usize;
+    if !(92..=SECTOR_SIZE).contains(&header_size) {
+        return Err("tamanho de cabeçalho GPT inválido");
+    }
+
+    // O CRC é calculado com o próprio campo zerado
+    let mut copy = [0u8; SECTOR_SIZE];
+    copy[..header_size].copy_from_slice(&header[..header_size]);
+    copy[16..20].fill(0);
+    if crc32(&copy[..header_size]) != u32_at(&header, 16) {
+        return Err("CRC do cabeçalho GPT inválido");
+    }
+
+    let entries_lba = u64_at(&header, 72);
+    let entry_count = u32_at(&header, 80) as usize;
+    let entry_size = u32_at(&header, 84) as usize;
+    // Entradas de 128 bytes, 256, ... até um setor: outros tamanhos não
+    // dividem a tabela em setores inteiros
+    if entry_size < 128 || entry_size > SECTOR_SIZE || entry_size % 128 != 0 || entry_count > 1024 {
+        return Err("tabela de entradas GPT inválida");
+    }
+
+    let table_bytes = entry_count * entry_size;
+    let table_sectors = table_bytes.div_ceil(SECTOR_SIZE) as u64;
+    if !entries_lba.checked_add(table_sectors).is_some_and(|end| end <= device.sector_count()) {
+        return Err("tabela de entradas GPT além do fim do disco");
+    }
+    let mut table = vec![0u8; table_bytes.div_ceil(SECTOR_SIZE) * SECTOR_SIZE];
+    device.read_sectors(entries_lba, &mut table)?;
+    if crc32(&table[..table_bytes]) != u32_at(&header, 88) {
+        return Err("CRC das entradas GPT inválido");
+    }
+
+    let mut parts = Vec::new();
+    for (i, entry) in table[..table_bytes].chunks(entry_size).enumerate() {
+        let mut type_guid = [0u8; 16];
+        type_guid.copy_from_slice(&entry[0..16]);
+        if type_guid == [0u8; 16] {
+            continue;
+        }
+
+        let first = u64_at(entry, 32);
+        let last = u64_at(entry, 40);
+        if last < first || last >= device.sector_count() {
+            continue;
+        }
+
+        let units = entry[56..128]
+            .chunks(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|&c| c != 0);
+        let label = char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+
+        parts.push(PartitionInfo {
+            number: i + 1,
+            start: first,
+            sectors: last - first + 1,
+            kind: PartitionKind::Gpt { type_guid, label },
+        });
+    }
+
+    Ok(parts)
+}
+
+/// Lê o GPT primário (LBA 1); se estiver corrompido, tenta o backup
+fn parse_gpt(device: &dyn BlockDevice) -> Result<Vec<PartitionInfo>, &'static str> {
+    match parse_gpt_at(device, 1) {
+        Ok(parts) => Ok(parts),
+        Err(e) => {
+            vga_println!("{}: GPT primário: {}; tentando backup", device.name(), e);
+            let backup = device.sector_count().checked_sub(1).ok_or("disco vazio")?;
+            parse_gpt_at(device, backup)
+        }
+    }
+}
+
+/// Lê a tabela de partições (MBR ou GPT) de um disco
+pub fn read_table(device: &dyn BlockDevice) -> Result<Vec<PartitionInfo>, &'static str> {
+    let sector0 = read_sector(device, 0)?;
+    let Some(entries) = mbr_entries(&sector0) else {
+        return Ok(Vec::new());
+    };
+
+    if entries.iter().any(|e| e.kind == MBR_GPT_PROTECTIVE) {
+        return parse_gpt(device);
+    }
+
+    // Setor de boot FAT sem tabela (ex: disquete): o disco inteiro é o volume
+    let has_jump = sector0[0] == 0xEB || sector0[0] == 0xE9;
+    if has_jump && BiosParameterBlock::parse(&sector0).is_ok() {
+        return Ok(Vec::new());
+    }
+
+    Ok(parse_mbr(device, &entries))
+}
+
+/// Registra cada partição dos discos conhecidos como `<disco><n>` (ex: hda1)
+pub fn scan_all() {
+    for device in block::devices() {
+        let parts = match read_table(device) {
+            Ok(parts) => parts,
+            Err(e) => {
+                vga_println!("{}: tabela de partições inválida: {}", device.name(), e);
+                continue;
+            }
+        };
+
+        for info in parts {
+            if !info.start.checked_add(info.sectors).is_some_and(|end| end <= device.sector_count()) {
+                vga_println!("{}: partição {} além do fim do disco", device.name(), info.number);
+                continue;
+            }
+
+            let partition = Partition {
+                name: format!("{}{}", device.name(), info.number),
+                parent: device,
+                start: info.start,
+                sectors: info.sectors,
+                kind: info.kind,
+            };
+            vga_println!(
+                "Partição {}: início {} ({} setores)",
+                partition.name,
+                partition.start,
+                partition.sectors
+            );
+            block::register(Box::leak(Box::new(partition)));
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use alloc::vec::Vec;
+use x86_64::instructions::port::Port;
+
+use crate::shell::{self, Command, CommandResult, Context};
+use crate::sh_println;
+
+const CONFIG_ADDRESS: u16 = 0xCF8;
+const CONFIG_DATA: u16 = 0xCFC;
+
+/// ID de capability "vendor specific" (usada pelo virtio moderno)
+pub const CAP_VENDOR_SPECIFIC: u8 = 0x09;
+
+/// Lê 32 bits do espaço de configuração PCI (mecanismo #1)
+pub fn config_read32(bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+    let address = 0x8000_0000u32
+        | (bus as u32) << 16
+        | (device as u32) << 11
+        | (function as u32) << 8
+        | (offset as u32 & 0xFC);
+
+    unsafe {
+        Port::<u32>::new(CONFIG_ADDRESS).write(address);
+        Port::<u32>::new(CONFIG_DATA).read()
+    }
+}
+
+/// Escreve 32 bits no espaço de configuração PCI
+pub fn config_write32(bus: u8, device: u8, function: u8, offset: u8, value: u32) {
+    let address = 0x8000_0000u32
+        | (bus as u32) << 16
+        | (device as u32) << 11
+        | (function as u32) << 8
+        | (offset as u32 & 0xFC);
+
+    unsafe {
+        Port::<u32>::new(CONFIG_ADDRESS).write(address);
+        Port::<u32>::new(CONFIG_DATA).write(value);
+    }
+}
+
+/// Endereço base decodificado de um BAR
+#[derive(Debug, Clone, Copy)]
+pub enum Bar {
+    Io(u16),
+    Memory(u64),
+}
+
+/// Um dispositivo encontrado na enumeração do barramento
+#[derive(Debug, Clone, Copy)]
+pub struct PciDevice {
+    pub bus: u8,
+    pub device: u8,
+    pub function: u8,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub class: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
+    pub interrupt_line: u8,
+}
+
+impl PciDevice {
+    pub fn read32(&self, offset: u8) -> u32 {
+        config_read32(self.bus, self.device, self.function, offset)
+    }
+
+    pub fn write32(&self, offset: u8, value: u32) {
+        config_write32(self.bus, self.device, self.function, offset, value)
+    }
+
+    pub fn read8(&self, offset: u8) -> u8 {
+        (self.read32(offset) >> ((offset & 3) * 8)) as u8
+    }
+
+    pub fn read16(&self, offset: u8) -> u16 {
+        (self.read32(offset) >> ((offset & 2) * 8)) as u16
+    }
+
+    /// Decodifica o BAR `index` (0..6), juntando BARs de 64 bits
+    pub fn bar(&self, index: u8) -> Option<Bar> {
+        let offset = 0x10 + index * 4;
+        let raw = self.read32(offset);
+
+        if raw & 1 == 1 {
+            return Some(Bar::Io((raw & 0xFFFC) as u16));
+        }
+
+        let base = (raw & 0xFFFF_FFF0) as u64;
+        let address = if (raw >> 1) & 0b11 == 0b10 {
+            base | (self.read32(offset + 4) as u64) << 32
+        } else {
+            base
+        };
+
+        if address == 0 { None } else { Some(Bar::Memory(address)) }
+    }
+
+    /// Habilita decodificação de I/O, memória e bus mastering (DMA)
+    pub fn enable_bus_master(&self) {
+        let command = self.read32(0x04);
+        self.write32(0x04, command | 0b111);
+    }
+
+    /// Percorre a lista de capabilities, retornando o offset de cada uma
+    pub fn capabilities(&self) -> Vec<u8> {
+        let mut caps = Vec::new();
+
+        // Bit 4 do status indica que a lista existe
+        if self.read16(0x06) & (1 << 4) == 0 {
+            return caps;
+        }
+
+        let mut offset = self.read8(0x34) & 0xFC;
+        while offset != 0 && caps.len() < 48 {
+            caps.push(offset);
+            offset = self.read8(offset + 1) & 0xFC;
+        }
+
+        caps
+    }
+}
+
+fn probe(bus: u8, device: u8, function: u8) -> Option<PciDevice> {
+    let id = config_read32(bus, device, function, 0x00);
+    let vendor_id = id as u16;
+    if vendor_id == 0xFFFF {
+        return None;
+    }
+
+    let class = config_read32(bus, device, function, 0x08);
+    let irq = config_read32(bus, device, function, 0x3C);
+
+    Some(PciDevice {
+        bus,
+        device,
+        function,
+        vendor_id,
+        device_id: (id >> 16) as u16,
+        class: (class >> 24) as u8,
+        subclass: (class >> 16) as u8,
+        prog_if: (class >> 8) as u8,
+        interrupt_line: irq as u8,
+    })
+}
+
+/// Enumera todos os dispositivos PCI (força bruta em todos os barramentos)
+pub fn scan() -> Vec<PciDevice> {
+    let mut devices = Vec::new();
+
+    for bus in 0..=255u8 {
+        for device in 0..32u8 {
+            let Some(first) = probe(bus, device, 0) else { continue };
+            devices.push(first);
+
+            // Bit 7 do header type = dispositivo multifunção
+            let header_type = (config_read32(bus, device, 0, 0x0C) >> 16) as u8;
+            if header_type & 0x80 != 0 {
+                for function in 1..8u8 {
+                    if let Some(dev) = probe(bus, device, function) {
+                        devices.push(dev);
+                    }
+                }
+            }
+        }
+    }
+
+    devices
+}
+
+/// Procura dispositivos por vendor/device ID
+pub fn find(vendor_id: u16, device_ids: &[u16]) -> Vec<PciDevice> {
+    scan()
+        .into_iter()
+        .filter(|d| d.vendor_id == vendor_id && device_ids.contains(&d.device_id))
+        .collect()
+}
+
+/// Nome genérico da classe/subclasse (tabela da PCI-SIG, só as comuns)
+pub fn class_name(class: u8, subclass: u8) -> &'static str {
+    match (class, subclass) {
+        (0x01, 0x01) => "controlador IDE",
+        (0x01, 0x06) => "controlador SATA",
+        (0x01, 0x08) => "controlador NVMe",
+        (0x01, _) => "controlador de armazenamento",
+        (0x02, _) => "controlador de rede",
+        (0x03, _) => "controlador de vídeo",
+        (0x04, _) => "dispositivo multimídia",
+        (0x06, 0x00) => "ponte host",
+        (0x06, 0x01) => "ponte ISA",
+        (0x06, 0x04) => "ponte PCI-PCI",
+        (0x06, _) => "ponte",
+        (0x0C, 0x03) => "controlador USB",
+        (0x0C, _) => "controlador de barramento serial",
+        _ => "dispositivo desconhecido",
+    }
+}
+
+fn cmd_lspci(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    for dev in scan() {
+        sh_println!(
+            ctx,
+            "{:02x}:{:02x}.{} {} [{:04x}:{:04x}] irq {}",
+            dev.bus,
+            dev.device,
+            dev.function,
+            class_name(dev.class, dev.subclass),
+            dev.vendor_id,
+            dev.device_id,
+            dev.interrupt_line
+        );
+    }
+    Ok(())
+}
+
+pub fn register_commands() {
+    shell::register(Command {
+        name: "lspci",
+        usage: "",
+        help: "lista os dispositivos PCI",
+        min_args: 0,
+        max_args: Some(0),
+        handler: cmd_lspci,
+    });
+}
@@ -0,0 +1,122 @@
+use alloc::{collections::VecDeque, sync::Arc};
+use core::fmt;
+
+use crate::sync::{IrqSpinLock, WaitQueue};
+use crate::vfs::VfsError;
+
+/// Bytes que um pipe guarda antes de a escrita bloquear (o mesmo do Linux)
+pub const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// Buffer compartilhado pelas duas pontas de um pipe
+struct PipeBuffer {
+    data: VecDeque<u8>,
+    reader_open: bool,
+    writer_open: bool,
+}
+
+/// Estado do pipe e as filas de quem espera por dados ou por espaço
+struct Pipe {
+    buffer: IrqSpinLock<PipeBuffer>,
+    readable: WaitQueue,
+    writable: WaitQueue,
+}
+
+/// Ponta de leitura; ler de um pipe vazio com o escritor fechado é o fim
+pub struct PipeReader {
+    pipe: Arc<Pipe>,
+}
+
+/// Ponta de escrita; ao ser descartada, o leitor passa a ver o fim
+pub struct PipeWriter {
+    pipe: Arc<Pipe>,
+}
+
+/// Cria um pipe. Leitor e escritor rodam em tarefas diferentes: ler de um
+/// pipe vazio e escrever num cheio bloqueiam até a outra ponta agir.
+pub fn pipe() -> (PipeReader, PipeWriter) {
+    let pipe = Arc::new(Pipe {
+        buffer: IrqSpinLock::named("pipe", PipeBuffer {
+            data: VecDeque::with_capacity(PIPE_CAPACITY),
+            reader_open: true,
+            writer_open: true,
+        }),
+        readable: WaitQueue::new(),
+        writable: WaitQueue::new(),
+    });
+    (
+        PipeReader { pipe: pipe.clone() },
+        PipeWriter { pipe },
+    )
+}
+
+impl PipeReader {
+    /// Bloqueia enquanto o pipe está vazio e o escritor segue aberto;
+    /// retorna 0 no fim
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, VfsError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let n = self.pipe.readable.wait_until(|| {
+            let mut pipe = self.pipe.buffer.lock();
+            if pipe.data.is_empty() {
+                return (!pipe.writer_open).then_some(0);
+            }
+            let n = core::cmp::min(buf.len(), pipe.data.len());
+            for (dst, src) in buf.iter_mut().zip(pipe.data.drain(..n)) {
+                *dst = src;
+            }
+            Some(n)
+        });
+        self.pipe.writable.wake_all();
+        Ok(n)
+    }
+}
+
+impl PipeWriter {
+    /// Escreve o que couber, bloqueando enquanto o buffer está cheio;
+    /// `BrokenPipe` se o leitor já fechou
+    pub fn write(&mut self, data: &[u8]) -> Result<usize, VfsError> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+        let written = self.pipe.writable.wait_until(|| {
+            let mut pipe = self.pipe.buffer.lock();
+            if !pipe.reader_open {
+                return Some(Err(VfsError::BrokenPipe));
+            }
+            let n = core::cmp::min(data.len(), PIPE_CAPACITY - pipe.data.len());
+            if n == 0 {
+                return None;
+            }
+            pipe.data.extend(&data[..n]);
+            Some(Ok(n))
+        });
+        self.pipe.readable.wake_all();
+        written
+    }
+}
+
+impl fmt::Write for PipeWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut rest = s.as_bytes();
+        while !rest.is_empty() {
+            let n = self.write(rest).map_err(|_| fmt::Error)?;
+            rest = &rest[n..];
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        self.pipe.buffer.lock().reader_open = false;
+        self.pipe.writable.wake_all();
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        self.pipe.buffer.lock().writer_open = false;
+        self.pipe.readable.wake_all();
+    }
+}
@@ -0,0 +1,99 @@
+use x86_64::instructions::{interrupts, port::Port};
+use x86_64::structures::DescriptorTablePointer;
+use x86_64::VirtAddr;
+
+use crate::acpi;
+use crate::shell::{self, Command, CommandResult, Context};
+use crate::vga_println;
+
+/// Porta do dispositivo `isa-debug-exit` do QEMU
+/// (`-device isa-debug-exit,iobase=0xf4,iosize=0x04`)
+const QEMU_EXIT_PORT: u16 = 0xf4;
+
+/// Códigos para `exit_qemu`; o QEMU termina com `(código << 1) | 1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum QemuExitCode {
+    Success = 0x10,
+    Failed = 0x11,
+}
+
+/// Encerra o QEMU pelo `isa-debug-exit`; fora do QEMU não faz nada
+pub fn exit_qemu(code: QemuExitCode) {
+    unsafe { Port::<u32>::new(QEMU_EXIT_PORT).write(code as u32) };
+}
+
+/// Pede o reset ao controlador de teclado (pulso na linha de reset da CPU)
+fn keyboard_reset() {
+    let mut status: Port<u8> = Port::new(0x64);
+    unsafe {
+        // Espera o buffer de entrada esvaziar
+        for _ in 0..100_000 {
+            if status.read() & 0x02 == 0 {
+                break;
+            }
+        }
+        status.write(0xFE);
+    }
+}
+
+/// Último recurso: com uma IDT vazia, qualquer exceção vira triple fault
+fn triple_fault() -> ! {
+    let empty = DescriptorTablePointer { limit: 0, base: VirtAddr::new(0) };
+    unsafe {
+        x86_64::instructions::tables::lidt(&empty);
+    }
+    interrupts::int3();
+    loop {
+        x86_64::instructions::hlt();
+    }
+}
+
+/// Reinicia a máquina: registrador de reset ACPI, controlador de teclado e,
+/// se nada funcionar, triple fault
+pub fn reboot() -> ! {
+    interrupts::disable();
+    acpi::reset();
+    keyboard_reset();
+    triple_fault()
+}
+
+/// Desliga via ACPI S5; no QEMU sem ACPI, usa a porta de desligamento e o
+/// `isa-debug-exit`. Se nada funcionar, para a CPU.
+pub fn shutdown() -> ! {
+    interrupts::disable();
+    acpi::power_off();
+
+    unsafe {
+        // QEMU (PIIX4) e Bochs/QEMU antigos
+        Port::<u16>::new(0x604).write(0x2000);
+        Port::<u16>::new(0xB004).write(0x2000);
+    }
+    exit_qemu(QemuExitCode::Success);
+
+    vga_println!("Não foi possível desligar; a máquina pode ser desligada com segurança");
+    loop {
+        x86_64::instructions::hlt();
+    }
+}
+
+fn cmd_reboot(_ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    vga_println!("Reiniciando...");
+    reboot()
+}
+
+fn cmd_shutdown(_ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    vga_println!("Desligando...");
+    shutdown()
+}
+
+pub fn register_commands() {
+    let commands = [
+        Command { name: "reboot", usage: "", help: "reinicia a máquina", min_args: 0, max_args: Some(0), handler: cmd_reboot },
+        Command { name: "shutdown", usage: "", help: "desliga a máquina (ACPI S5)", min_args: 0, max_args: Some(0), handler: cmd_shutdown },
+        Command { name: "poweroff", usage: "", help: "o mesmo que shutdown", min_args: 0, max_args: Some(0), handler: cmd_shutdown },
+    ];
+    for command in commands {
+        shell::register(command);
+    }
+}
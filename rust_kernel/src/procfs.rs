@@ -0,0 +1,262 @@
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::scheduler::{self, TaskInfo};
+use crate::task::{TaskId, TaskState};
+use crate::vfs::{DirEntry, Directory, FileType, Filesystem, InodeId, Metadata, VfsError, VFS_INSTANCE};
+use crate::{allocator, cpu, interrupts, memory, rtc, timer};
+
+const ROOT_INODE: InodeId = 1;
+
+/// Arquivo sintético: nome e função que gera o conteúdo a cada leitura
+struct ProcFile {
+    name: &'static str,
+    generate: fn() -> String,
+}
+
+static FILES: [ProcFile; 5] = [
+    ProcFile { name: "meminfo", generate: meminfo },
+    ProcFile { name: "uptime", generate: uptime },
+    ProcFile { name: "interrupts", generate: interrupts },
+    ProcFile { name: "mounts", generate: mounts },
+    ProcFile { name: "cpuinfo", generate: cpuinfo },
+];
+
+pub fn meminfo() -> String {
+    let mut out = String::new();
+
+    if let Some((total, allocated)) = memory::frame_stats() {
+        let _ = writeln!(out, "MemTotal:     {:>8} kB", total * 4);
+        let _ = writeln!(out, "MemFree:      {:>8} kB", (total - allocated) * 4);
+        let _ = writeln!(out, "MemUsed:      {:>8} kB", allocated * 4);
+    }
+
+    let (heap_size, heap_used) = allocator::heap_stats();
+    let _ = writeln!(out, "HeapTotal:    {:>8} kB", heap_size / 1024);
+    let _ = writeln!(out, "HeapUsed:     {:>8} kB", heap_used / 1024);
+    let _ = writeln!(out, "HeapFree:     {:>8} kB", (heap_size - heap_used) / 1024);
+    out
+}
+
+pub fn uptime() -> String {
+    let ms = timer::uptime_ms();
+    format!("{}.{:02}\n", ms / 1000, (ms % 1000) / 10)
+}
+
+pub fn interrupts() -> String {
+    let mut out = String::new();
+    for irq in 0..16u8 {
+        let count = interrupts::irq_count(irq);
+        if count > 0 || !interrupts::irq_name(irq).is_empty() {
+            let _ = writeln!(out, "{:>3}: {:>10}  {}", irq, count, interrupts::irq_name(irq));
+        }
+    }
+    out
+}
+
+pub fn mounts() -> String {
+    let mut out = String::new();
+    for (path, fs, source) in VFS_INSTANCE.lock().mounts() {
+        let _ = writeln!(out, "{} {} {}", source, path, fs);
+    }
+    out
+}
+
+pub fn cpuinfo() -> String {
+    let info = cpu::info();
+    let mut out = String::new();
+    let _ = writeln!(out, "vendor_id  : {}", info.vendor);
+    let _ = writeln!(out, "model name : {}", info.brand);
+    let _ = writeln!(out, "cpu family : {}", info.family);
+    let _ = writeln!(out, "model      : {}", info.model);
+    let _ = writeln!(out, "stepping   : {}", info.stepping);
+    let _ = writeln!(out, "flags      : {}", info.features.join(" "));
+    out
+}
+
+/// Arquivo de `/proc/<pid>`, gerado a partir do retrato da tarefa
+struct PidFile {
+    name: &'static str,
+    generate: fn(&TaskInfo) -> String,
+}
+
+static PID_FILES: [PidFile; 2] = [
+    PidFile { name: "status", generate: pid_status },
+    PidFile { name: "stat", generate: pid_stat },
+];
+
+/// Letra do estado no formato do Linux
+fn state_letter(state: TaskState) -> char {
+    match state {
+        TaskState::Ready | TaskState::Running => 'R',
+        TaskState::Sleeping(_) => 'S',
+        TaskState::Blocked => 'D',
+        TaskState::Dead => 'Z',
+    }
+}
+
+fn pid_status(task: &TaskInfo) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Name:\t{}", task.name);
+    let _ = writeln!(out, "State:\t{} ({})", state_letter(task.state), task.state.name());
+    let _ = writeln!(out, "Pid:\t{}", task.id);
+    let _ = writeln!(out, "Policy:\t{}", task.policy.class_name());
+    let _ = writeln!(out, "Runtime:\t{} ticks", task.stats.runtime);
+    let _ = writeln!(out, "Wait:\t{} ticks", task.stats.wait);
+    let _ = writeln!(out, "voluntary_ctxt_switches:\t{}", task.stats.voluntary_switches);
+    let _ = writeln!(out, "nonvoluntary_ctxt_switches:\t{}", task.stats.involuntary_switches);
+    out
+}
+
+/// Uma linha: pid (nome) estado runtime espera trocas-voluntárias trocas-involuntárias
+fn pid_stat(task: &TaskInfo) -> String {
+    format!(
+        "{} ({}) {} {} {} {} {}\n",
+        task.id,
+        task.name,
+        state_letter(task.state),
+        task.stats.runtime,
+        task.stats.wait,
+        task.stats.voluntary_switches,
+        task.stats.involuntary_switches
+    )
+}
+
+/// Inodes de `/proc/<pid>`: (pid + 1) nos bits altos e, nos baixos, 0 para o
+/// diretório ou 1 + o índice em `PID_FILES`
+const PID_SHIFT: u32 = 8;
+
+fn pid_inode(id: TaskId, file: usize) -> InodeId {
+    ((id.0 + 1) << PID_SHIFT) | file as InodeId
+}
+
+fn find_task(id: TaskId) -> Result<TaskInfo, VfsError> {
+    scheduler::tasks().into_iter().find(|t| t.id == id).ok_or(VfsError::NotFound)
+}
+
+/// O que um inode representa
+enum Node {
+    Root,
+    File(&'static ProcFile),
+    PidDir(TaskInfo),
+    PidFile(TaskInfo, &'static PidFile),
+}
+
+/// Sistema de arquivos /proc: conteúdo gerado no momento da leitura
+pub struct ProcFs;
+
+impl ProcFs {
+    fn node(inode: InodeId) -> Result<Node, VfsError> {
+        if inode == ROOT_INODE {
+            return Ok(Node::Root);
+        }
+        if inode >> PID_SHIFT == 0 {
+            let index = inode.checked_sub(2).ok_or(VfsError::NotFound)? as usize;
+            return FILES.get(index).map(Node::File).ok_or(VfsError::NotFound);
+        }
+
+        let task = find_task(TaskId((inode >> PID_SHIFT) - 1))?;
+        match (inode & ((1 << PID_SHIFT) - 1)) as usize {
+            0 => Ok(Node::PidDir(task)),
+            file => PID_FILES.get(file - 1).map(|f| Node::PidFile(task, f)).ok_or(VfsError::NotFound),
+        }
+    }
+
+    /// Somente leitura, com a data do momento da consulta
+    fn file_metadata(inode: InodeId) -> Metadata {
+        let mut metadata = Metadata::new(inode, FileType::File, 0).with_times(rtc::now());
+        metadata.mode = 0o444;
+        metadata
+    }
+
+    fn dir_metadata(inode: InodeId) -> Metadata {
+        let mut metadata = Metadata::new(inode, FileType::Directory, 0).with_times(rtc::boot_time());
+        metadata.mode = 0o555;
+        metadata
+    }
+}
+
+impl Filesystem for ProcFs {
+    fn name(&self) -> &str {
+        "proc"
+    }
+
+    fn lookup(&self, path: &str) -> Result<InodeId, VfsError> {
+        let path = path.trim_matches('/');
+        if path.is_empty() {
+            return Ok(ROOT_INODE);
+        }
+
+        let (first, rest) = path.split_once('/').unwrap_or((path, ""));
+        if let Some(i) = FILES.iter().position(|f| f.name == first) {
+            return if rest.is_empty() { Ok(i as InodeId + 2) } else { Err(VfsError::NotADirectory) };
+        }
+
+        let id = first.parse().map(TaskId).map_err(|_| VfsError::NotFound)?;
+        find_task(id)?;
+        if rest.is_empty() {
+            return Ok(pid_inode(id, 0));
+        }
+        PID_FILES
+            .iter()
+            .position(|f| f.name == rest)
+            .map(|i| pid_inode(id, i + 1))
+            .ok_or(VfsError::NotFound)
+    }
+
+    /// Como no Linux, os arquivos têm tamanho 0: o conteúdo só existe ao ler
+    fn stat(&self, inode: InodeId) -> Result<Metadata, VfsError> {
+        Ok(match Self::node(inode)? {
+            Node::Root | Node::PidDir(_) => Self::dir_metadata(inode),
+            Node::File(_) | Node::PidFile(..) => Self::file_metadata(inode),
+        })
+    }
+
+    fn read_at(&self, inode: InodeId, offset: u64, buf: &mut [u8]) -> Result<usize, VfsError> {
+        let content = match Self::node(inode)? {
+            Node::File(file) => (file.generate)(),
+            Node::PidFile(task, file) => (file.generate)(&task),
+            Node::Root | Node::PidDir(_) => return Err(VfsError::IsADirectory),
+        };
+        let bytes = content.as_bytes();
+
+        let offset = offset as usize;
+        if offset >= bytes.len() {
+            return Ok(0);
+        }
+        let n = core::cmp::min(buf.len(), bytes.len() - offset);
+        buf[..n].copy_from_slice(&bytes[offset..offset + n]);
+        Ok(n)
+    }
+
+    fn list_dir(&self, path: &str) -> Result<Directory, VfsError> {
+        let path = path.trim_matches('/');
+        let entries: Vec<DirEntry> = match Self::node(self.lookup(path)?)? {
+            Node::Root => {
+                let files = FILES.iter().enumerate().map(|(i, f)| DirEntry {
+                    name: f.name.into(),
+                    metadata: Self::file_metadata(i as InodeId + 2),
+                });
+                let pids = scheduler::tasks().into_iter().map(|t| DirEntry {
+                    name: format!("{}", t.id),
+                    metadata: Self::dir_metadata(pid_inode(t.id, 0)),
+                });
+                files.chain(pids).collect()
+            }
+            Node::PidDir(task) => PID_FILES
+                .iter()
+                .enumerate()
+                .map(|(i, f)| DirEntry {
+                    name: f.name.into(),
+                    metadata: Self::file_metadata(pid_inode(task.id, i + 1)),
+                })
+                .collect(),
+            Node::File(_) | Node::PidFile(..) => return Err(VfsError::NotADirectory),
+        };
+
+        Ok(Directory {
+            name: format!("/{}", path),
+            entries,
+        })
+    }
+}
@@ -0,0 +1,218 @@
+use alloc::collections::{BTreeMap, VecDeque};
+
+use crate::task::{Task, TaskId};
+use crate::timer;
+
+/// Duração de um tick do PIT em nanossegundos
+pub const TICK_NS: u64 = 1_000_000_000 / timer::TARGET_HZ as u64;
+
+/// Política de escalonamento de uma tarefa
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedPolicy {
+    /// Classe justa (estilo CFS); nice de -20 (mais CPU) a 19
+    Fair { nice: i8 },
+    /// Prioridade fixa de 1 a 99; sempre passa na frente da classe justa
+    RealTime { priority: u8 },
+}
+
+impl SchedPolicy {
+    pub const NICE_MIN: i8 = -20;
+    pub const NICE_MAX: i8 = 19;
+    pub const RT_PRIORITY_MAX: u8 = 99;
+
+    /// Índice da classe em `Scheduler::classes`, da mais prioritária à menos
+    pub fn class_index(&self) -> usize {
+        match self {
+            SchedPolicy::RealTime { .. } => 0,
+            SchedPolicy::Fair { .. } => 1,
+        }
+    }
+
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            SchedPolicy::RealTime { .. } => "RT",
+            SchedPolicy::Fair { .. } => "CFS",
+        }
+    }
+}
+
+impl Default for SchedPolicy {
+    fn default() -> Self {
+        SchedPolicy::Fair { nice: 0 }
+    }
+}
+
+/// Classe de escalonamento: guarda as tarefas prontas de uma política e
+/// decide qual executa. A tarefa em execução não fica na fila da classe.
+pub trait SchedClass: Send {
+    /// Prepara uma tarefa que acabou de entrar na classe (nova ou mudando
+    /// de política), antes do primeiro `enqueue`
+    fn attach(&mut self, _task: &mut Task) {}
+    /// Coloca uma tarefa pronta na fila; `wakeup` se ela estava bloqueada
+    /// ou dormindo (e não apenas saindo da CPU)
+    fn enqueue(&mut self, task: &mut Task, wakeup: bool);
+    /// Tira uma tarefa pronta da fila (mudança de política)
+    fn dequeue(&mut self, task: &Task);
+    /// Retira a próxima tarefa a executar
+    fn pick_next(&mut self) -> Option<TaskId>;
+    /// Fatia de tempo, em ticks, da tarefa que acabou de ser escolhida
+    fn time_slice(&self, task: &Task) -> u64;
+    /// Contabiliza um tick da tarefa em execução
+    fn tick(&mut self, task: &mut Task);
+    /// A tarefa acordada deve tirar da CPU a atual, da mesma classe?
+    fn should_preempt(&self, current: &Task, woken: &Task) -> bool;
+    fn is_empty(&self) -> bool;
+}
+
+/// Pesos por nice, os mesmos do Linux: cada nível muda ~10% da CPU
+const NICE_TO_WEIGHT: [u64; 40] = [
+    88761, 71755, 56483, 46273, 36291, 29154, 23254, 18705, 14949, 11916, 9548, 7620, 6100, 4904, 3906, 3121, 2501,
+    1991, 1586, 1277, 1024, 820, 655, 526, 423, 335, 272, 215, 172, 137, 110, 87, 70, 56, 45, 36, 29, 23, 18, 15,
+];
+const NICE_0_WEIGHT: u64 = 1024;
+
+pub fn nice_weight(nice: i8) -> u64 {
+    let nice = nice.clamp(SchedPolicy::NICE_MIN, SchedPolicy::NICE_MAX);
+    NICE_TO_WEIGHT[(nice - SchedPolicy::NICE_MIN) as usize]
+}
+
+fn weight(task: &Task) -> u64 {
+    match task.policy {
+        SchedPolicy::Fair { nice } => nice_weight(nice),
+        SchedPolicy::RealTime { .. } => NICE_0_WEIGHT,
+    }
+}
+
+/// Período em que toda tarefa pronta deveria executar uma vez
+const SCHED_LATENCY_NS: u64 = 30_000_000;
+/// Menor fatia concedida (um tick, a resolução do timer)
+const MIN_GRANULARITY_NS: u64 = TICK_NS;
+/// Vantagem mínima de vruntime para quem acorda tomar a CPU
+const WAKEUP_GRANULARITY_NS: u64 = 5_000_000;
+
+/// Escalonador justo: executa sempre a tarefa com menor tempo virtual
+/// (vruntime), que cresce mais devagar para pesos maiores (nice menor)
+pub struct FairClass {
+    /// Tarefas prontas por vruntime, com o peso que tinham ao entrar
+    queue: BTreeMap<(u64, TaskId), u64>,
+    /// Soma dos pesos das tarefas na fila (a em execução fica de fora)
+    load: u64,
+    /// Piso do vruntime; tarefas que chegam partem daqui
+    min_vruntime: u64,
+}
+
+impl FairClass {
+    pub const fn new() -> Self {
+        Self { queue: BTreeMap::new(), load: 0, min_vruntime: 0 }
+    }
+}
+
+impl SchedClass for FairClass {
+    fn attach(&mut self, task: &mut Task) {
+        task.vruntime = self.min_vruntime;
+    }
+
+    fn enqueue(&mut self, task: &mut Task, wakeup: bool) {
+        // Quem dormiu não acumula crédito infinito: no máximo meia latência
+        // à frente das outras, o que favorece tarefas interativas como a shell
+        if wakeup {
+            let floor = self.min_vruntime.saturating_sub(SCHED_LATENCY_NS / 2);
+            task.vruntime = task.vruntime.max(floor);
+        }
+        let weight = weight(task);
+        self.queue.insert((task.vruntime, task.id), weight);
+        self.load += weight;
+    }
+
+    // Sai o peso guardado na entrada, mesmo que o nice tenha mudado depois
+    fn dequeue(&mut self, task: &Task) {
+        if let Some(weight) = self.queue.remove(&(task.vruntime, task.id)) {
+            self.load -= weight;
+        }
+    }
+
+    fn pick_next(&mut self) -> Option<TaskId> {
+        let ((vruntime, id), weight) = self.queue.pop_first()?;
+        self.load -= weight;
+        self.min_vruntime = self.min_vruntime.max(vruntime);
+        Some(id)
+    }
+
+    fn time_slice(&self, task: &Task) -> u64 {
+        let weight = weight(task);
+        let slice = SCHED_LATENCY_NS * weight / (self.load + weight);
+        slice.max(MIN_GRANULARITY_NS) / TICK_NS
+    }
+
+    fn tick(&mut self, task: &mut Task) {
+        task.vruntime += TICK_NS * NICE_0_WEIGHT / weight(task);
+    }
+
+    fn should_preempt(&self, current: &Task, woken: &Task) -> bool {
+        woken.vruntime + WAKEUP_GRANULARITY_NS < current.vruntime
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// Fatia das tarefas de tempo real com a mesma prioridade (round-robin)
+const RT_TIME_SLICE_TICKS: u64 = 10;
+
+/// Prioridade fixa: executa a fila de maior prioridade, em round-robin
+pub struct RealTimeClass {
+    queues: BTreeMap<u8, VecDeque<TaskId>>,
+}
+
+impl RealTimeClass {
+    pub const fn new() -> Self {
+        Self { queues: BTreeMap::new() }
+    }
+}
+
+fn rt_priority(task: &Task) -> u8 {
+    match task.policy {
+        SchedPolicy::RealTime { priority } => priority,
+        SchedPolicy::Fair { .. } => 0,
+    }
+}
+
+impl SchedClass for RealTimeClass {
+    fn enqueue(&mut self, task: &mut Task, _wakeup: bool) {
+        self.queues.entry(rt_priority(task)).or_default().push_back(task.id);
+    }
+
+    fn dequeue(&mut self, task: &Task) {
+        let priority = rt_priority(task);
+        if let Some(queue) = self.queues.get_mut(&priority) {
+            queue.retain(|&id| id != task.id);
+            if queue.is_empty() {
+                self.queues.remove(&priority);
+            }
+        }
+    }
+
+    fn pick_next(&mut self) -> Option<TaskId> {
+        let mut entry = self.queues.last_entry()?;
+        let id = entry.get_mut().pop_front();
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+        id
+    }
+
+    fn time_slice(&self, _task: &Task) -> u64 {
+        RT_TIME_SLICE_TICKS
+    }
+
+    fn tick(&mut self, _task: &mut Task) {}
+
+    fn should_preempt(&self, current: &Task, woken: &Task) -> bool {
+        rt_priority(woken) > rt_priority(current)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+}
@@ -0,0 +1,498 @@
+use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+use x86_64::registers::control::{Cr3, Cr3Flags};
+use x86_64::VirtAddr;
+
+use crate::gdt;
+use crate::sched_class::{FairClass, RealTimeClass, SchedClass, SchedPolicy};
+use crate::sh_println;
+use crate::shell::{self, Command, CommandResult, Context};
+use crate::task::{self, Task, TaskId, TaskState, TaskStats};
+use crate::timer;
+
+/// Escalonador com classes: a tarefa vem da classe mais prioritária que
+/// tiver alguém pronto (tempo real, depois a justa); sem ninguém, a ociosa
+struct Scheduler {
+    /// Em `Box` para que o campo `rsp` não mude de lugar durante a troca
+    tasks: BTreeMap<TaskId, Box<Task>>,
+    /// Indexadas por `SchedPolicy::class_index`
+    classes: [Box<dyn SchedClass>; 2],
+    current: TaskId,
+    /// Executa só quando todas as classes estão vazias; nunca entra nelas
+    idle: TaskId,
+    /// Alguém mais prioritário acordou: troca no próximo tick
+    need_resched: bool,
+}
+
+impl Scheduler {
+    fn task(&mut self, id: TaskId) -> &mut Task {
+        self.tasks.get_mut(&id).expect("tarefa inexistente")
+    }
+
+    fn has_ready(&self) -> bool {
+        self.classes.iter().any(|class| !class.is_empty())
+    }
+
+    fn enqueue(&mut self, id: TaskId, wakeup: bool) {
+        if id == self.idle {
+            return;
+        }
+        let task = self.tasks.get_mut(&id).expect("tarefa inexistente");
+        task.state = TaskState::Ready;
+        task.stats.ready_since = timer::ticks();
+        self.classes[task.policy.class_index()].enqueue(task, wakeup);
+    }
+
+    /// Enfileira quem acordou e decide se a tarefa atual deve ceder a CPU
+    fn wake_up(&mut self, id: TaskId) {
+        self.enqueue(id, true);
+
+        let current = &self.tasks[&self.current];
+        let woken = &self.tasks[&id];
+        let (current_class, woken_class) = (current.policy.class_index(), woken.policy.class_index());
+        if self.current == self.idle
+            || woken_class < current_class
+            || (woken_class == current_class && self.classes[woken_class].should_preempt(current, woken))
+        {
+            self.need_resched = true;
+        }
+    }
+
+    fn pick_next(&mut self) -> TaskId {
+        self.classes.iter_mut().find_map(|class| class.pick_next()).unwrap_or(self.idle)
+    }
+
+    /// Escolhe a próxima tarefa e atualiza estados, estatísticas, CR3 e RSP0.
+    /// Retorna os ponteiros para `switch_context`, ou `None` se a atual continua.
+    fn switch(&mut self) -> Option<(*mut u64, u64)> {
+        let current = self.current;
+        let preempted = self.task(current).state == TaskState::Running;
+        if preempted {
+            self.enqueue(current, false);
+        }
+        self.need_resched = false;
+
+        let next = self.pick_next();
+        let is_idle = next == self.idle;
+        let now = timer::ticks();
+        let task = self.tasks.get_mut(&next).expect("tarefa inexistente");
+        if task.state == TaskState::Ready {
+            task.stats.wait += now - task.stats.ready_since;
+        }
+        task.state = TaskState::Running;
+        task.slice = if is_idle { 1 } else { self.classes[task.policy.class_index()].time_slice(task) };
+        if next == current {
+            return None;
+        }
+
+        let (new_rsp, cr3, kernel_stack_top) = (task.rsp, task.cr3, task.kernel_stack_top);
+        let old = self.task(current);
+        if preempted {
+            old.stats.involuntary_switches += 1;
+        } else {
+            old.stats.voluntary_switches += 1;
+        }
+        old.cr3 = Cr3::read().0;
+        let old_rsp = &mut old.rsp as *mut u64;
+
+        if cr3 != Cr3::read().0 {
+            unsafe { Cr3::write(cr3, Cr3Flags::empty()) };
+        }
+        gdt::set_kernel_stack(kernel_stack_top);
+        self.current = next;
+        CURRENT.store(next.0 + 1, Ordering::Relaxed);
+        Some((old_rsp, new_rsp))
+    }
+}
+
+static SCHEDULER: Mutex<Option<Scheduler>> = Mutex::new(None);
+
+/// Id + 1 da tarefa em execução (0 antes do escalonador), lido sem o lock
+/// do escalonador: os locks de `sync` o consultam mesmo com ele preso
+static CURRENT: AtomicU64 = AtomicU64::new(0);
+
+/// Troca de tarefa; precisa ser chamada com as interrupções desligadas (a
+/// partir de uma IRQ ou dentro de `without_interrupts`)
+fn schedule() {
+    let switch = SCHEDULER.lock().as_mut().and_then(|s| s.switch());
+    if let Some((old_rsp, new_rsp)) = switch {
+        unsafe { task::switch_context(old_rsp, new_rsp) };
+    }
+}
+
+/// Transforma o fluxo do boot na primeira tarefa e cria a tarefa ociosa.
+/// A pilha da ociosa cria a janela de pilhas nas tabelas do kernel antes
+/// de qualquer espaço de endereçamento ser clonado delas.
+pub fn init() {
+    let boot = Task::current("kernel", gdt::kernel_stack());
+    let idle = Task::new("idle", Box::new(idle_loop)).expect("sem memória para a tarefa ociosa");
+
+    let (current, idle_id) = (boot.id, idle.id);
+    let mut tasks = BTreeMap::new();
+    tasks.insert(boot.id, Box::new(boot));
+    tasks.insert(idle.id, Box::new(idle));
+
+    *SCHEDULER.lock() = Some(Scheduler {
+        tasks,
+        classes: [Box::new(RealTimeClass::new()), Box::new(FairClass::new())],
+        current,
+        idle: idle_id,
+        need_resched: false,
+    });
+    CURRENT.store(current.0 + 1, Ordering::Relaxed);
+}
+
+/// Cria uma tarefa do kernel que executa `f` e termina quando ela retornar
+pub fn spawn<F>(name: &str, policy: SchedPolicy, f: F) -> Option<TaskId>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let mut task = Task::new(name, Box::new(f))?;
+    task.policy = policy;
+    let id = task.id;
+    interrupts::without_interrupts(|| {
+        let mut guard = SCHEDULER.lock();
+        let scheduler = guard.as_mut()?;
+        scheduler.classes[policy.class_index()].attach(&mut task);
+        scheduler.tasks.insert(id, Box::new(task));
+        scheduler.wake_up(id);
+        Some(id)
+    })
+}
+
+pub fn current_id() -> Option<TaskId> {
+    CURRENT.load(Ordering::Relaxed).checked_sub(1).map(TaskId)
+}
+
+/// Cede a CPU para a próxima tarefa pronta
+pub fn yield_now() {
+    interrupts::without_interrupts(schedule);
+}
+
+/// Dorme por pelo menos `ticks` ticks do timer
+pub fn sleep_ticks(ticks: u64) {
+    let until = timer::ticks() + ticks.max(1);
+    interrupts::without_interrupts(|| {
+        if let Some(scheduler) = SCHEDULER.lock().as_mut() {
+            let current = scheduler.current;
+            scheduler.task(current).state = TaskState::Sleeping(until);
+        }
+        schedule();
+    });
+}
+
+pub fn sleep_ms(ms: u64) {
+    let hz = timer::TARGET_HZ as u64;
+    sleep_ticks((ms * hz).div_ceil(1000));
+}
+
+/// Bloqueia a tarefa atual até alguém chamar `wake` para ela. Um `wake`
+/// que chegue antes fica pendente e faz o próximo `block` voltar na hora.
+pub fn block() {
+    interrupts::without_interrupts(|| {
+        if let Some(scheduler) = SCHEDULER.lock().as_mut() {
+            let current = scheduler.current;
+            let task = scheduler.task(current);
+            if core::mem::take(&mut task.wakeup_pending) {
+                return;
+            }
+            task.state = TaskState::Blocked;
+        }
+        schedule();
+    });
+}
+
+/// Acorda uma tarefa bloqueada ou dormindo; retorna `false` se ela não existe
+pub fn wake(id: TaskId) -> bool {
+    interrupts::without_interrupts(|| {
+        let mut guard = SCHEDULER.lock();
+        let Some(scheduler) = guard.as_mut() else { return false };
+        let Some(state) = scheduler.tasks.get(&id).map(|t| t.state) else { return false };
+        match state {
+            TaskState::Blocked | TaskState::Sleeping(_) => scheduler.wake_up(id),
+            TaskState::Running | TaskState::Ready => scheduler.task(id).wakeup_pending = true,
+            TaskState::Dead => return false,
+        }
+        true
+    })
+}
+
+/// Encerra a tarefa atual; a pilha é liberada depois, pela tarefa ociosa
+pub fn exit() -> ! {
+    interrupts::disable();
+    if let Some(scheduler) = SCHEDULER.lock().as_mut() {
+        let current = scheduler.current;
+        scheduler.task(current).state = TaskState::Dead;
+    }
+    schedule();
+    unreachable!("tarefa morta voltou a executar");
+}
+
+/// Muda a política de uma tarefa, movendo-a de classe se estiver pronta
+pub fn set_policy(id: TaskId, policy: SchedPolicy) -> Result<(), &'static str> {
+    match policy {
+        SchedPolicy::Fair { nice } if !(SchedPolicy::NICE_MIN..=SchedPolicy::NICE_MAX).contains(&nice) => {
+            return Err("nice fora do intervalo -20..19");
+        }
+        SchedPolicy::RealTime { priority } if !(1..=SchedPolicy::RT_PRIORITY_MAX).contains(&priority) => {
+            return Err("prioridade fora do intervalo 1..99");
+        }
+        _ => {}
+    }
+
+    interrupts::without_interrupts(|| {
+        let mut guard = SCHEDULER.lock();
+        let scheduler = guard.as_mut().ok_or("escalonador não inicializado")?;
+        if id == scheduler.idle {
+            return Err("a tarefa ociosa não muda de política");
+        }
+        let task = scheduler.tasks.get_mut(&id).ok_or("tarefa inexistente")?;
+
+        let ready = task.state == TaskState::Ready;
+        let (old_class, new_class) = (task.policy.class_index(), policy.class_index());
+        if ready {
+            scheduler.classes[old_class].dequeue(task);
+        }
+        task.policy = policy;
+        if old_class != new_class {
+            scheduler.classes[new_class].attach(task);
+        }
+        if ready {
+            scheduler.classes[new_class].enqueue(task, false);
+        }
+        scheduler.need_resched = true;
+        Ok(())
+    })
+}
+
+/// Troca a pilha de entrada no kernel (RSP0) da tarefa atual; retorna a anterior
+pub fn set_kernel_stack(top: VirtAddr) -> VirtAddr {
+    interrupts::without_interrupts(|| {
+        let previous = gdt::kernel_stack();
+        if let Some(scheduler) = SCHEDULER.lock().as_mut() {
+            let current = scheduler.current;
+            scheduler.task(current).kernel_stack_top = top;
+        }
+        gdt::set_kernel_stack(top);
+        previous
+    })
+}
+
+/// Chamado pela IRQ0 (depois do EOI): contabiliza o tick, acorda quem
+/// terminou de dormir e preempta quando a fatia acaba ou alguém mais
+/// prioritário está pronto
+pub fn tick() {
+    let now = timer::ticks();
+    let preempt = {
+        let mut guard = SCHEDULER.lock();
+        let Some(scheduler) = guard.as_mut() else { return };
+
+        let sleepers: Vec<TaskId> = scheduler
+            .tasks
+            .values()
+            .filter(|task| matches!(task.state, TaskState::Sleeping(until) if until <= now))
+            .map(|task| task.id)
+            .collect();
+        for id in sleepers {
+            scheduler.wake_up(id);
+        }
+
+        let current = scheduler.current;
+        if current == scheduler.idle {
+            scheduler.task(current).stats.runtime += 1;
+            scheduler.has_ready()
+        } else {
+            let task = scheduler.tasks.get_mut(&current).expect("tarefa inexistente");
+            task.stats.runtime += 1;
+            scheduler.classes[task.policy.class_index()].tick(task);
+            task.slice = task.slice.saturating_sub(1);
+            task.slice == 0 || scheduler.need_resched
+        }
+    };
+    if preempt {
+        schedule();
+    }
+}
+
+/// Remove as tarefas mortas, uma a uma, liberando as pilhas fora da seção
+/// crítica
+fn reap() {
+    loop {
+        let dead = interrupts::without_interrupts(|| {
+            let mut guard = SCHEDULER.lock();
+            let scheduler = guard.as_mut()?;
+            let current = scheduler.current;
+            let id = scheduler
+                .tasks
+                .iter()
+                .find(|(&id, task)| task.state == TaskState::Dead && id != current)
+                .map(|(&id, _)| id)?;
+            scheduler.tasks.remove(&id)
+        });
+        match dead {
+            Some(task) => drop(task),
+            None => break,
+        }
+    }
+}
+
+fn idle_loop() {
+    loop {
+        reap();
+
+        // Desliga as interrupções antes de olhar as filas: `enable_and_hlt`
+        // não perde uma IRQ que chegue entre a verificação e o hlt
+        interrupts::disable();
+        let ready = SCHEDULER.lock().as_ref().is_some_and(|s| s.has_ready());
+        if ready {
+            schedule();
+            interrupts::enable();
+        } else {
+            interrupts::enable_and_hlt();
+        }
+    }
+}
+
+/// Retrato de uma tarefa para `ps` e `top`
+#[derive(Clone)]
+pub struct TaskInfo {
+    pub id: TaskId,
+    pub name: String,
+    pub state: TaskState,
+    pub policy: SchedPolicy,
+    pub stats: TaskStats,
+}
+
+pub fn tasks() -> Vec<TaskInfo> {
+    interrupts::without_interrupts(|| {
+        let guard = SCHEDULER.lock();
+        let Some(scheduler) = guard.as_ref() else { return Vec::new() };
+        scheduler
+            .tasks
+            .values()
+            .map(|t| TaskInfo { id: t.id, name: t.name.clone(), state: t.state, policy: t.policy, stats: t.stats })
+            .collect()
+    })
+}
+
+fn ticks_to_ms(ticks: u64) -> u64 {
+    ticks * 1000 / timer::TARGET_HZ as u64
+}
+
+fn priority_column(policy: SchedPolicy) -> String {
+    match policy {
+        SchedPolicy::Fair { nice } => format!("{}", nice),
+        SchedPolicy::RealTime { priority } => format!("rt{}", priority),
+    }
+}
+
+fn parse_pid(arg: &str) -> Result<TaskId, String> {
+    arg.parse().map(TaskId).map_err(|_| format!("{}: PID inválido", arg))
+}
+
+fn cmd_ps(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    let tasks = tasks();
+    if tasks.is_empty() {
+        return Err("escalonador não inicializado".into());
+    }
+
+    sh_println!(
+        ctx,
+        "{:>5} {:<4} {:>4} {:<11} {:>9} {:>9} {:>7} {:>7}  {}",
+        "PID", "CLS", "PRI", "ESTADO", "CPU(ms)", "FILA(ms)", "VOL", "INVOL", "NOME"
+    );
+    for t in tasks {
+        sh_println!(
+            ctx,
+            "{:>5} {:<4} {:>4} {:<11} {:>9} {:>9} {:>7} {:>7}  {}",
+            t.id,
+            t.policy.class_name(),
+            priority_column(t.policy),
+            t.state.name(),
+            ticks_to_ms(t.stats.runtime),
+            ticks_to_ms(t.stats.wait),
+            t.stats.voluntary_switches,
+            t.stats.involuntary_switches,
+            t.name
+        );
+    }
+    Ok(())
+}
+
+/// Uso de CPU por intervalo de um segundo: amostra, dorme e compara
+fn cmd_top(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let iterations = match args {
+        [] => 1,
+        ["-n", n] => n.parse::<u32>().map_err(|_| format!("{}: número inválido", n))?,
+        _ => return Err("uso: top [-n iterações]".into()),
+    };
+
+    let mut before: BTreeMap<TaskId, u64> = tasks().iter().map(|t| (t.id, t.stats.runtime)).collect();
+    let mut start = timer::ticks();
+    for _ in 0..iterations {
+        sleep_ms(1000);
+        let now = timer::ticks();
+        let elapsed = (now - start).max(1);
+
+        let mut rows: Vec<(u64, TaskInfo)> = tasks()
+            .into_iter()
+            .map(|t| (t.stats.runtime - before.get(&t.id).copied().unwrap_or(0), t))
+            .collect();
+        rows.sort_by(|a, b| b.0.cmp(&a.0));
+
+        sh_println!(ctx, "{:>5} {:<4} {:>4} {:<11} {:>6} {:>9}  {}", "PID", "CLS", "PRI", "ESTADO", "%CPU", "CPU(ms)", "NOME");
+        for (delta, t) in &rows {
+            sh_println!(
+                ctx,
+                "{:>5} {:<4} {:>4} {:<11} {:>5}% {:>9}  {}",
+                t.id,
+                t.policy.class_name(),
+                priority_column(t.policy),
+                t.state.name(),
+                delta * 100 / elapsed,
+                ticks_to_ms(t.stats.runtime),
+                t.name
+            );
+        }
+        sh_println!(ctx, "");
+
+        before = rows.iter().map(|(_, t)| (t.id, t.stats.runtime)).collect();
+        start = now;
+    }
+    Ok(())
+}
+
+fn cmd_renice(_ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let nice: i8 = args[0].parse().map_err(|_| format!("{}: nice inválido", args[0]))?;
+    for arg in &args[1..] {
+        set_policy(parse_pid(arg)?, SchedPolicy::Fair { nice }).map_err(|e| format!("{}: {}", arg, e))?;
+    }
+    Ok(())
+}
+
+fn cmd_chrt(_ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let priority: u8 = args[0].parse().map_err(|_| format!("{}: prioridade inválida", args[0]))?;
+    // Prioridade 0 devolve a tarefa à classe justa
+    let policy = match priority {
+        0 => SchedPolicy::default(),
+        _ => SchedPolicy::RealTime { priority },
+    };
+    for arg in &args[1..] {
+        set_policy(parse_pid(arg)?, policy).map_err(|e| format!("{}: {}", arg, e))?;
+    }
+    Ok(())
+}
+
+pub fn register_commands() {
+    let commands = [
+        Command { name: "ps", usage: "", help: "lista as tarefas com classe, estado e estatísticas", min_args: 0, max_args: Some(0), handler: cmd_ps },
+        Command { name: "top", usage: "[-n iterações]", help: "mostra o uso de CPU por tarefa a cada segundo", min_args: 0, max_args: Some(2), handler: cmd_top },
+        Command { name: "renice", usage: "<nice> <pid>...", help: "põe tarefas na classe justa com o nice dado (-20..19)", min_args: 2, max_args: None, handler: cmd_renice },
+        Command { name: "chrt", usage: "<prioridade> <pid>...", help: "põe tarefas na classe de tempo real (1..99; 0 volta à justa)", min_args: 2, max_args: None, handler: cmd_chrt },
+    ];
+    for command in commands {
+        shell::register(command);
+    }
+}
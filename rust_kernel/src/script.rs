@@ -0,0 +1,557 @@
+use alloc::{collections::BTreeMap, format, string::{String, ToString}, sync::Arc, vec::Vec};
+use core::{fmt, mem};
+
+use crate::shell::{self, Command, CommandResult, Context, Shell, EXIT_FAILURE, EXIT_SUCCESS, EXIT_USAGE};
+use crate::shell_parser::{self, Environment, Word};
+use crate::vfs::{FileType, VfsError, VFS_INSTANCE};
+use crate::vga_println;
+
+/// Arquivo executado pela shell interativa ao iniciar
+pub const RC_FILE: &str = "/etc/nnshrc";
+
+/// Sequência de comandos de um script, corpo de laço ou função
+pub type Block = Vec<Node>;
+
+/// Funções definidas na shell, compartilhadas com os blocos que as chamam
+pub type Functions = BTreeMap<String, Arc<Block>>;
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    /// Linha comum, executada pelo parser da shell (pipes, `&&`, ...)
+    Line(String),
+    /// `if`/`elif` com seus corpos, e o `else`
+    If { branches: Vec<(Block, Block)>, otherwise: Block },
+    /// `while` (ou `until`, com `until = true`)
+    While { condition: Block, body: Block, until: bool },
+    For { var: String, items: Vec<Word>, body: Block },
+    /// `nome() { ... }`
+    Function { name: String, body: Arc<Block> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// Falta fechar um bloco ou aspas; a shell interativa pede mais linhas
+    Incomplete,
+    Syntax(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Incomplete => write!(f, "fim inesperado do script"),
+            ParseError::Syntax(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// Como a execução de um bloco terminou
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return,
+    Exit,
+}
+
+/// Separa o texto em comandos por quebras de linha e `;` fora de aspas,
+/// removendo comentários
+fn split_statements(text: &str) -> Result<Vec<String>, ParseError> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = text.chars().peekable();
+
+    let mut flush = |current: &mut String| {
+        let statement = current.trim();
+        if !statement.is_empty() {
+            statements.push(statement.into());
+        }
+        current.clear();
+    };
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => {
+                quote = None;
+                current.push(c);
+            }
+            (Some('"'), '\\') | (None, '\\') => match chars.next() {
+                // Barra no fim da linha continua o comando na próxima
+                Some('\n') => {}
+                Some(next) => {
+                    current.push(c);
+                    current.push(next);
+                }
+                None => current.push(c),
+            },
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (None, '#') if current.is_empty() || current.ends_with(char::is_whitespace) => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            (None, '\n' | ';') => flush(&mut current),
+            (None, c) => current.push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(ParseError::Incomplete);
+    }
+    flush(&mut current);
+    Ok(statements)
+}
+
+/// Primeira palavra (sem aspas) e o resto do comando
+fn split_word(statement: &str) -> (&str, &str) {
+    match statement.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (statement, ""),
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Reconhece `nome() ...`; retorna o nome e o que vem depois dos parênteses
+fn function_header(statement: &str) -> Option<(&str, &str)> {
+    let (name, rest) = statement.split_once("()")?;
+    let name = name.trim();
+    is_name(name).then(|| (name, rest.trim()))
+}
+
+struct Parser {
+    /// Comandos ainda não lidos, do último para o primeiro
+    pending: Vec<String>,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<String> {
+        self.pending.pop()
+    }
+
+    /// Devolve um comando para ser o próximo lido (ex: "echo" em "then echo")
+    fn push_back(&mut self, statement: &str) {
+        if !statement.trim().is_empty() {
+            self.pending.push(statement.trim().into());
+        }
+    }
+
+    /// Lê nós até um comando que comece com uma das palavras `terminators`;
+    /// retorna o bloco e a palavra que o encerrou. Sem terminadores, lê até o fim.
+    fn block(&mut self, terminators: &[&'static str]) -> Result<(Block, &'static str), ParseError> {
+        let mut block = Vec::new();
+        loop {
+            let Some(statement) = self.next() else {
+                return if terminators.is_empty() { Ok((block, "")) } else { Err(ParseError::Incomplete) };
+            };
+            let (word, rest) = split_word(&statement);
+
+            if let Some(&terminator) = terminators.iter().find(|t| **t == word) {
+                self.push_back(rest);
+                return Ok((block, terminator));
+            }
+
+            let node = match word {
+                "if" => {
+                    self.push_back(rest);
+                    self.if_node()?
+                }
+                "while" | "until" => {
+                    self.push_back(rest);
+                    let (condition, _) = self.block(&["do"])?;
+                    let (body, _) = self.block(&["done"])?;
+                    Node::While { condition, body, until: word == "until" }
+                }
+                "for" => self.for_node(rest)?,
+                "function" => {
+                    let (name, rest) = split_word(rest);
+                    let name = name.strip_suffix("()").unwrap_or(name);
+                    if !is_name(name) {
+                        return Err(ParseError::Syntax(format!("nome de função inválido: {}", name)));
+                    }
+                    self.function_node(name, rest)?
+                }
+                "then" | "elif" | "else" | "fi" | "do" | "done" | "{" | "}" => {
+                    return Err(ParseError::Syntax(format!("'{}' inesperado", word)));
+                }
+                _ => match function_header(&statement) {
+                    Some((name, rest)) => self.function_node(name, rest)?,
+                    None => Node::Line(statement.clone()),
+                },
+            };
+            block.push(node);
+        }
+    }
+
+    fn if_node(&mut self) -> Result<Node, ParseError> {
+        let mut branches = Vec::new();
+        loop {
+            let (condition, _) = self.block(&["then"])?;
+            if condition.is_empty() {
+                return Err(ParseError::Syntax("'if' sem condição".into()));
+            }
+            let (body, end) = self.block(&["elif", "else", "fi"])?;
+            branches.push((condition, body));
+
+            match end {
+                "elif" => continue,
+                "else" => {
+                    let (otherwise, _) = self.block(&["fi"])?;
+                    return Ok(Node::If { branches, otherwise });
+                }
+                _ => return Ok(Node::If { branches, otherwise: Vec::new() }),
+            }
+        }
+    }
+
+    /// `for VAR in PALAVRAS` (já sem o "for"), seguido de `do ... done`
+    fn for_node(&mut self, header: &str) -> Result<Node, ParseError> {
+        let (var, rest) = split_word(header);
+        if !is_name(var) {
+            return Err(ParseError::Syntax(format!("nome de variável inválido: {}", var)));
+        }
+        let items = match split_word(rest) {
+            ("in", items) => shell_parser::words(items).map_err(|e| ParseError::Syntax(e.into()))?,
+            _ => return Err(ParseError::Syntax("esperado 'in' depois de 'for'".into())),
+        };
+
+        let (before_do, _) = self.block(&["do"])?;
+        if !before_do.is_empty() {
+            return Err(ParseError::Syntax("esperado 'do'".into()));
+        }
+        let (body, _) = self.block(&["done"])?;
+        Ok(Node::For { var: var.into(), items, body })
+    }
+
+    /// Corpo `{ ... }` de uma função; `rest` é o que sobrou da linha do nome
+    fn function_node(&mut self, name: &str, rest: &str) -> Result<Node, ParseError> {
+        let rest = match rest {
+            "" => self.next().ok_or(ParseError::Incomplete)?,
+            rest => rest.into(),
+        };
+        let Some(body_start) = rest.strip_prefix('{') else {
+            return Err(ParseError::Syntax("esperado '{' depois do nome da função".into()));
+        };
+        self.push_back(body_start);
+
+        let (body, _) = self.block(&["}"])?;
+        Ok(Node::Function { name: name.into(), body: Arc::new(body) })
+    }
+}
+
+/// Analisa um script inteiro
+pub fn parse(text: &str) -> Result<Block, ParseError> {
+    let mut pending = split_statements(text)?;
+    pending.reverse();
+    let (block, _) = Parser { pending }.block(&[])?;
+    Ok(block)
+}
+
+pub fn run_block(shell: &mut Shell, block: &[Node]) -> Flow {
+    for node in block {
+        let flow = run_node(shell, node);
+        if flow != Flow::Normal {
+            return flow;
+        }
+    }
+    Flow::Normal
+}
+
+/// Executa a condição de um `if`/`while`; `Err` se ela interrompeu o fluxo
+fn run_condition(shell: &mut Shell, condition: &[Node]) -> Result<bool, Flow> {
+    match run_block(shell, condition) {
+        Flow::Normal => Ok(shell.last_status == EXIT_SUCCESS),
+        flow => Err(flow),
+    }
+}
+
+fn run_node(shell: &mut Shell, node: &Node) -> Flow {
+    match node {
+        Node::Line(line) => run_statement(shell, line),
+        Node::If { branches, otherwise } => {
+            for (condition, body) in branches {
+                match run_condition(shell, condition) {
+                    Ok(true) => return run_block(shell, body),
+                    Ok(false) => {}
+                    Err(flow) => return flow,
+                }
+            }
+            // Como no sh, um if sem ramo executado termina com sucesso
+            shell.last_status = EXIT_SUCCESS;
+            run_block(shell, otherwise)
+        }
+        Node::While { condition, body, until } => {
+            loop {
+                match run_condition(shell, condition) {
+                    Ok(result) if result != *until => {}
+                    Ok(_) => break,
+                    Err(flow) => return flow,
+                }
+                match run_block(shell, body) {
+                    Flow::Break => break,
+                    Flow::Normal | Flow::Continue => {}
+                    flow => return flow,
+                }
+            }
+            shell.last_status = EXIT_SUCCESS;
+            Flow::Normal
+        }
+        Node::For { var, items, body } => {
+            let values: Vec<String> = items
+                .iter()
+                .map(|w| w.expand(&shell.env, shell.last_status))
+                .collect();
+            for value in values {
+                shell.env.insert(var.clone(), value);
+                match run_block(shell, body) {
+                    Flow::Break => break,
+                    Flow::Normal | Flow::Continue => {}
+                    flow => return flow,
+                }
+            }
+            Flow::Normal
+        }
+        Node::Function { name, body } => {
+            shell.functions.insert(name.clone(), body.clone());
+            shell.last_status = EXIT_SUCCESS;
+            Flow::Normal
+        }
+    }
+}
+
+/// Executa uma linha; `break`, `continue`, `return [n]` e `exit [n]` são
+/// tratados aqui porque mudam o fluxo do script
+fn run_statement(shell: &mut Shell, line: &str) -> Flow {
+    let (word, rest) = split_word(line);
+    let flow = match word {
+        "break" => return Flow::Break,
+        "continue" => return Flow::Continue,
+        "return" => Flow::Return,
+        "exit" => Flow::Exit,
+        _ => {
+            shell.run_line(line);
+            // `exit` dentro de uma função chamada por esta linha
+            return if shell.exit_status.is_some() { Flow::Exit } else { Flow::Normal };
+        }
+    };
+
+    let status = shell_parser::words(rest).map(|words| {
+        words
+            .first()
+            .map(|w| w.expand(&shell.env, shell.last_status))
+    });
+    match status {
+        Ok(None) => {}
+        Ok(Some(arg)) => match arg.parse::<i32>() {
+            Ok(status) => shell.last_status = status,
+            Err(_) => {
+                vga_println!("nnsh: {}: {}: número esperado", word, arg);
+                shell.last_status = EXIT_USAGE;
+            }
+        },
+        Err(e) => {
+            vga_println!("nnsh: {}: {}", word, e);
+            shell.last_status = EXIT_USAGE;
+        }
+    }
+
+    if flow == Flow::Exit {
+        shell.exit_status = Some(shell.last_status);
+    }
+    flow
+}
+
+/// Troca os parâmetros posicionais (`$0`, `$1`, ..., `$#`); retorna os antigos
+fn set_positional(env: &mut Environment, name: Option<&str>, args: &[&str]) -> Vec<(String, String)> {
+    let is_positional = |key: &str| key == "#" || key.chars().all(|c| c.is_ascii_digit());
+    let keys: Vec<String> = env
+        .keys()
+        .filter(|k| is_positional(k) && (name.is_some() || k.as_str() != "0"))
+        .cloned()
+        .collect();
+    let saved = keys
+        .into_iter()
+        .filter_map(|k| env.remove(&k).map(|v| (k, v)))
+        .collect();
+
+    if let Some(name) = name {
+        env.insert("0".into(), name.into());
+    }
+    for (i, arg) in args.iter().enumerate() {
+        env.insert(format!("{}", i + 1), (*arg).into());
+    }
+    env.insert("#".into(), format!("{}", args.len()));
+    saved
+}
+
+fn restore_positional(env: &mut Environment, saved: Vec<(String, String)>) {
+    env.retain(|k, _| k != "#" && !k.chars().all(|c| c.is_ascii_digit()));
+    env.extend(saved);
+}
+
+/// Máximo de funções e scripts aninhados; recursão sem fim esgotaria a pilha
+/// do kernel
+pub const MAX_DEPTH: usize = 64;
+
+/// Chama uma função com `args` em `$1`, `$2`, ...; retorna o status final.
+/// A função usa a entrada e a saída da shell, não as do pipeline.
+pub fn call_function(shell: &mut Shell, body: &[Node], args: &[&str]) -> i32 {
+    if shell.depth >= MAX_DEPTH {
+        vga_println!("nnsh: limite de {} níveis de aninhamento excedido", MAX_DEPTH);
+        shell.last_status = EXIT_FAILURE;
+        return EXIT_FAILURE;
+    }
+    let saved = set_positional(&mut shell.env, None, args);
+    shell.depth += 1;
+    run_block(shell, body);
+    shell.depth -= 1;
+    // `$0` fica com o valor de quem chamou
+    let script_name = shell.env.remove("0");
+    restore_positional(&mut shell.env, saved);
+    if let Some(name) = script_name {
+        shell.env.insert("0".into(), name);
+    }
+    shell.last_status
+}
+
+/// Lê e executa o script `path` na shell; `exit` encerra só o script
+pub fn run_file(shell: &mut Shell, path: &str, args: &[&str]) -> Result<i32, String> {
+    if shell.depth >= MAX_DEPTH {
+        return Err(format!("{}: limite de {} níveis de aninhamento excedido", path, MAX_DEPTH));
+    }
+    // O lock do VFS não pode ficar preso durante a leitura (o /proc o pega)
+    let opened = VFS_INSTANCE.lock().open(path);
+    let data = opened
+        .and_then(|mut file| file.read_to_end())
+        .map_err(|e| format!("{}: {}", path, e))?;
+    let text = String::from_utf8_lossy(&data);
+    let block = parse(&text).map_err(|e| format!("{}: erro de sintaxe: {}", path, e))?;
+
+    let saved = set_positional(&mut shell.env, Some(path), args);
+    shell.depth += 1;
+    run_block(shell, &block);
+    shell.depth -= 1;
+    restore_positional(&mut shell.env, saved);
+
+    Ok(shell.exit_status.take().unwrap_or(shell.last_status))
+}
+
+/// Executa o arquivo de inicialização, se existir
+pub fn run_rc(shell: &mut Shell) {
+    match run_file(shell, RC_FILE, &[]) {
+        Ok(_) => {}
+        Err(_) if matches!(VFS_INSTANCE.lock().stat(RC_FILE), Err(VfsError::NotFound)) => {}
+        Err(e) => vga_println!("nnsh: {}", e),
+    }
+}
+
+/// Registra `sh`, `source`, `true`, `false` e `test`
+pub fn register_commands() {
+    let commands = [
+        Command { name: "sh", usage: "<script> [argumento]...", help: "executa um script numa shell nova", min_args: 1, max_args: None, handler: cmd_sh },
+        Command { name: "source", usage: "<script> [argumento]...", help: "executa um script na shell atual", min_args: 1, max_args: None, handler: cmd_source },
+        Command { name: ".", usage: "<script> [argumento]...", help: "o mesmo que source", min_args: 1, max_args: None, handler: cmd_source },
+        Command { name: "true", usage: "", help: "termina com sucesso", min_args: 0, max_args: None, handler: cmd_true },
+        Command { name: "false", usage: "", help: "termina com falha", min_args: 0, max_args: None, handler: cmd_false },
+        Command { name: "test", usage: "<expressão>", help: "avalia arquivos (-e -f -d), textos (-z -n = !=) e números (-eq -lt ...)", min_args: 0, max_args: None, handler: cmd_test },
+        Command { name: "[", usage: "<expressão> ]", help: "o mesmo que test", min_args: 1, max_args: None, handler: cmd_bracket },
+    ];
+    for command in commands {
+        shell::register(command);
+    }
+}
+
+/// Converte o status de um script no resultado de um comando
+fn status_result(status: i32) -> CommandResult {
+    if status == EXIT_SUCCESS { Ok(()) } else { Err(String::new()) }
+}
+
+fn cmd_sh(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let path = ctx.resolve(args[0]).map_err(|e| format!("{}: {}", args[0], e))?;
+
+    // Shell nova: herda diretório e variáveis, mas as mudanças não voltam
+    let mut child = Shell::new();
+    child.cwd = ctx.cwd.clone();
+    child.env = ctx.env.clone();
+    child.depth = ctx.depth;
+    status_result(run_file(&mut child, &path, &args[1..])?)
+}
+
+fn cmd_source(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let path = ctx.resolve(args[0]).map_err(|e| format!("{}: {}", args[0], e))?;
+
+    // Empresta o estado da shell atual para o script e o devolve no fim
+    let mut shell = Shell::new();
+    shell.cwd = mem::take(ctx.cwd);
+    shell.env = mem::take(ctx.env);
+    shell.functions = mem::take(ctx.functions);
+    shell.depth = ctx.depth;
+
+    let result = run_file(&mut shell, &path, &args[1..]);
+
+    *ctx.cwd = shell.cwd;
+    *ctx.env = shell.env;
+    *ctx.functions = shell.functions;
+    status_result(result?)
+}
+
+fn cmd_true(_ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    Ok(())
+}
+
+fn cmd_false(_ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    Err(String::new())
+}
+
+fn evaluate(ctx: &Context, args: &[&str]) -> Result<bool, String> {
+    match args {
+        [] => Ok(false),
+        ["!", rest @ ..] => evaluate(ctx, rest).map(|result| !result),
+        [s] => Ok(!s.is_empty()),
+        ["-n", s] => Ok(!s.is_empty()),
+        ["-z", s] => Ok(s.is_empty()),
+        [op @ ("-e" | "-f" | "-d"), path] => {
+            let metadata = ctx.resolve(path).and_then(|p| VFS_INSTANCE.lock().stat(&p));
+            Ok(match (*op, metadata) {
+                (_, Err(_)) => false,
+                ("-e", Ok(_)) => true,
+                ("-d", Ok(m)) => m.is_dir(),
+                (_, Ok(m)) => m.file_type == FileType::File,
+            })
+        }
+        [a, "=", b] => Ok(a == b),
+        [a, "!=", b] => Ok(a != b),
+        [a, op, b] => {
+            let number = |s: &str| s.parse::<i64>().map_err(|_| format!("{}: número esperado", s));
+            let (a, b) = (number(a)?, number(b)?);
+            match *op {
+                "-eq" => Ok(a == b),
+                "-ne" => Ok(a != b),
+                "-lt" => Ok(a < b),
+                "-le" => Ok(a <= b),
+                "-gt" => Ok(a > b),
+                "-ge" => Ok(a >= b),
+                _ => Err(format!("{}: operador desconhecido", op)),
+            }
+        }
+        _ => Err("expressão inválida".to_string()),
+    }
+}
+
+fn cmd_test(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    status_result(if evaluate(ctx, args)? { EXIT_SUCCESS } else { EXIT_FAILURE })
+}
+
+fn cmd_bracket(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    match args.split_last() {
+        Some((&"]", expression)) => cmd_test(ctx, expression),
+        _ => Err("falta ']'".into()),
+    }
+}
@@ -0,0 +1,70 @@
+use x86_64::instructions::port::Port;
+
+use crate::sync::IrqSpinLock;
+
+/// Porta base da COM1
+const COM1: u16 = 0x3F8;
+
+// Registradores relativos à porta base
+const REG_DATA: u16 = 0;
+const REG_INT_ENABLE: u16 = 1;
+const REG_FIFO_CTRL: u16 = 2;
+const REG_LINE_CTRL: u16 = 3;
+const REG_MODEM_CTRL: u16 = 4;
+const REG_LINE_STATUS: u16 = 5;
+
+const LSR_DATA_READY: u8 = 0x01;
+const LSR_TX_EMPTY: u8 = 0x20;
+
+/// UART 16550 (usada como /dev/ttyS0)
+pub struct SerialPort {
+    base: u16,
+}
+
+impl SerialPort {
+    pub const fn new(base: u16) -> Self {
+        Self { base }
+    }
+
+    fn port(&self, reg: u16) -> Port<u8> {
+        Port::new(self.base + reg)
+    }
+
+    /// Configura 38400 baud, 8N1, FIFO habilitada
+    pub fn init(&mut self) {
+        unsafe {
+            self.port(REG_INT_ENABLE).write(0x00);
+            self.port(REG_LINE_CTRL).write(0x80); // DLAB
+            self.port(REG_DATA).write(0x03); // divisor 3 → 38400 baud
+            self.port(REG_INT_ENABLE).write(0x00);
+            self.port(REG_LINE_CTRL).write(0x03); // 8 bits, sem paridade, 1 stop
+            self.port(REG_FIFO_CTRL).write(0xC7);
+            self.port(REG_MODEM_CTRL).write(0x0B);
+        }
+    }
+
+    fn line_status(&self) -> u8 {
+        unsafe { self.port(REG_LINE_STATUS).read() }
+    }
+
+    pub fn write_byte(&mut self, byte: u8) {
+        while self.line_status() & LSR_TX_EMPTY == 0 {
+            core::hint::spin_loop();
+        }
+        unsafe { self.port(REG_DATA).write(byte) }
+    }
+
+    /// Retorna um byte recebido, se houver
+    pub fn try_read_byte(&mut self) -> Option<u8> {
+        if self.line_status() & LSR_DATA_READY == 0 {
+            return None;
+        }
+        Some(unsafe { self.port(REG_DATA).read() })
+    }
+}
+
+pub static SERIAL1: IrqSpinLock<SerialPort> = IrqSpinLock::named("serial", SerialPort::new(COM1));
+
+pub fn init() {
+    SERIAL1.lock().init();
+}
@@ -1,180 +1,791 @@
-use crate::vga_buffer::{vga_print, vga_println};
-use crate::vfs::{VFS_INSTANCE};
-use alloc::{string::String, vec::Vec};
+use crate::{vga_print, vga_println};
+use crate::rtc::DateTime;
+use crate::block;
+use crate::vfs::{self, File, FileType, Metadata, SeekFrom, VfsError, VFS_INSTANCE};
+use crate::fd::{Descriptor, MemoryStream, KERNEL_FDS};
+use crate::elf;
+use crate::keyboard::{self, Key};
+use crate::line_editor::{Completer, LineEditor};
+use crate::pipe::{self, PipeReader};
+use crate::sched_class::SchedPolicy;
+use crate::scheduler;
+use crate::script::{self, Functions, ParseError};
+use crate::sync::Semaphore;
+use crate::shell_parser::{self, Connector, Environment, Pipeline, Redirect, SimpleCommand};
+use crate::{sh_print, sh_println};
+use alloc::{collections::BTreeMap, format, string::{String, ToString}, sync::Arc, vec::Vec};
+use core::fmt;
+use spin::Mutex;
 
-pub fn run_shell() {
-    let mut cwd = String::from("/");
+/// Nome do arquivo do histórico de comandos (cabe no 8.3 da FAT)
+const HISTORY_FILE: &str = "history";
 
-    loop {
-        vga_print!("[{}]$ ", cwd);
-        let line = read_line();
+/// Caminho do histórico: no primeiro volume montado de um disco que não
+/// perde o conteúdo ao desligar (a raiz ou /mnt); sem disco, na raiz, e
+/// ele dura até desligar
+fn history_path() -> String {
+    let mounts = VFS_INSTANCE.lock().mounts();
+    let dir = mounts
+        .iter()
+        .find(|(_, _, source)| block::find(source).is_some_and(|device| !device.volatile()))
+        .map_or("/", |(path, _, _)| path.as_str());
+    format!("{}/{}", dir.trim_end_matches('/'), HISTORY_FILE)
+}
 
-        let mut parts = line.trim().split_whitespace();
-        let cmd = match parts.next() {
-            Some(c) => c,
-            None => continue,
-        };
+/// Escreve na saída padrão de um comando (`ctx.stdout`)
+#[macro_export]
+macro_rules! sh_print {
+    ($ctx:expr, $($arg:tt)*) => {{
+        let _ = core::fmt::Write::write_fmt(&mut *$ctx.stdout, format_args!($($arg)*));
+    }};
+}
+
+#[macro_export]
+macro_rules! sh_println {
+    ($ctx:expr) => ($crate::sh_print!($ctx, "\n"));
+    ($ctx:expr, $fmt:expr) => ($crate::sh_print!($ctx, concat!($fmt, "\n")));
+    ($ctx:expr, $fmt:expr, $($arg:tt)*) => ($crate::sh_print!($ctx, concat!($fmt, "\n"), $($arg)*));
+}
+
+/// Saída padrão ligada à tela VGA
+pub struct Console;
+
+impl fmt::Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        crate::vga_buffer::_print(format_args!("{}", s));
+        Ok(())
+    }
+}
+
+/// Saída redirecionada para um arquivo (`>` e `>>`)
+struct FileOutput(File);
+
+impl fmt::Write for FileOutput {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes()).map(|_| ()).map_err(|_| fmt::Error)
+    }
+}
+
+/// Entrada padrão de um comando
+pub trait Input {
+    /// Retorna 0 no fim da entrada
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, VfsError>;
+}
+
+impl Input for File {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, VfsError> {
+        File::read(self, buf)
+    }
+}
+
+impl Input for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, VfsError> {
+        PipeReader::read(self, buf)
+    }
+}
+
+/// Entrada padrão ligada ao teclado, com eco; Ctrl+D encerra
+pub struct ConsoleInput;
 
-        match cmd {
-            "help" => {
-                vga_println!("Comandos disponíveis:");
-                vga_println!("  help          - mostra esta ajuda");
-                vga_println!("  ls            - lista arquivos");
-                vga_println!("  cat <arquivo> - mostra conteúdo");
-                vga_println!("  cd <dir>      - muda de diretório");
-                vga_println!("  clear         - limpa a tela");
+impl Input for ConsoleInput {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, VfsError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let key = keyboard::read_key();
+            if key == Key::Ctrl('d') {
+                return Ok(0);
+            }
+            if let Some(byte) = key.as_ascii() {
+                vga_print!("{}", byte as char);
+                buf[0] = byte;
+                return Ok(1);
             }
+        }
+    }
+}
 
-            "ls" => {
-                let dir = VFS_INSTANCE.lock().list_dir(&cwd);
-                match dir {
-                    Some(d) => {
-                        for e in d.entries {
-                            if e.is_dir {
-                                vga_println!("<DIR> {}", e.name);
-                            } else {
-                                vga_println!("     {} ({} bytes)", e.name, e.size);
-                            }
-                        }
-                    }
-                    None => vga_println!("Diretório inválido"),
-                }
+/// Estado da shell visível para os comandos
+pub struct Context<'a> {
+    pub cwd: &'a mut String,
+    pub env: &'a mut Environment,
+    pub functions: &'a mut Functions,
+    /// Funções e scripts em execução por baixo deste comando
+    pub depth: usize,
+    pub stdin: &'a mut dyn Input,
+    pub stdout: &'a mut dyn fmt::Write,
+    /// Entrada e saída padrão são o console, sem pipe nem redirecionamento
+    pub stdin_is_console: bool,
+    pub stdout_is_console: bool,
+    pub history: &'a [String],
+}
+
+impl Context<'_> {
+    /// Caminho absoluto de `path` relativo ao diretório atual
+    pub fn resolve(&self, path: &str) -> Result<String, VfsError> {
+        vfs::resolve(self.cwd, path)
+    }
+
+    /// Lê toda a entrada padrão
+    pub fn read_stdin(&mut self) -> Result<Vec<u8>, VfsError> {
+        let mut data = Vec::new();
+        let mut chunk = [0u8; 512];
+        loop {
+            let n = self.stdin.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(data);
             }
+            data.extend_from_slice(&chunk[..n]);
+        }
+    }
+}
 
-            "cd" => {
-                let arg = parts.next();
-                if let Some(target) = arg {
-                    if target == ".." && cwd != "/" {
-                        if let Some(pos) = cwd.rfind('/') {
-                            cwd.truncate(pos);
-                            if cwd.is_empty() {
-                                cwd = "/".into();
-                            }
-                        }
-                    } else {
-                        let mut new_path = cwd.clone();
-                        if !cwd.ends_with('/') {
-                            new_path.push('/');
-                        }
-                        new_path.push_str(target);
-
-                        if VFS_INSTANCE.lock().list_dir(&new_path).is_some() {
-                            cwd = new_path;
-                        } else {
-                            vga_println!("Diretório não encontrado");
-                        }
-                    }
-                } else {
-                    vga_println!("Uso: cd <diretório>");
+/// Separa as opções de uma letra (`-la` = `-l -a`) dos operandos; `--`
+/// encerra as opções e letras fora de `allowed` são erro
+pub fn parse_flags<'a>(args: &[&'a str], allowed: &str) -> Result<(String, Vec<&'a str>), String> {
+    let mut flags = String::new();
+    let mut operands = Vec::new();
+    let mut only_operands = false;
+
+    for &arg in args {
+        if only_operands || arg == "-" || !arg.starts_with('-') {
+            operands.push(arg);
+        } else if arg == "--" {
+            only_operands = true;
+        } else {
+            for c in arg[1..].chars() {
+                if !allowed.contains(c) {
+                    return Err(format!("opção inválida: -{}", c));
                 }
+                flags.push(c);
             }
+        }
+    }
+    Ok((flags, operands))
+}
 
-            "cat" => {
-                let filename = parts.next();
-                if let Some(f) = filename {
-                    let mut full_path = cwd.clone();
-                    if !cwd.ends_with('/') {
-                        full_path.push('/');
-                    }
-                    full_path.push_str(f);
-
-                    if let Some(mut file) = VFS_INSTANCE.lock().open(&full_path) {
-                        while let Some(b) = file.read_byte() {
-                            if b == b'\n' {
-                                vga_println!();
-                            } else {
-                                vga_print!("{}", b as char);
-                            }
-                        }
-                        vga_println!();
-                    } else {
-                        vga_println!("Arquivo não encontrado");
-                    }
-                } else {
-                    vga_println!("Uso: cat <arquivo>");
-                }
+/// Erros viram a mensagem "comando: erro" e status 1; uma mensagem vazia
+/// falha sem imprimir nada (ex: `false`, `test`)
+pub type CommandResult = Result<(), String>;
+
+/// Um comando da shell: nome, ajuda, aridade e a função que o executa
+#[derive(Clone, Copy)]
+pub struct Command {
+    pub name: &'static str,
+    /// Sinopse dos argumentos (ex: "<dispositivo> <diretório>")
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub min_args: usize,
+    /// `None` = sem limite
+    pub max_args: Option<usize>,
+    /// Recebe só os argumentos, sem o nome do comando
+    pub handler: fn(&mut Context, &[&str]) -> CommandResult,
+}
+
+/// Status de saída de um comando bem-sucedido, com erro, com uso incorreto
+/// ou inexistente (como no sh)
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NOT_FOUND: i32 = 127;
+
+static COMMANDS: Mutex<BTreeMap<&'static str, Command>> = Mutex::new(BTreeMap::new());
+
+/// Registra (ou substitui) um comando; drivers chamam isso no próprio init
+pub fn register(command: Command) {
+    COMMANDS.lock().insert(command.name, command);
+}
+
+pub fn find(name: &str) -> Option<Command> {
+    COMMANDS.lock().get(name).copied()
+}
+
+pub fn command_names() -> Vec<&'static str> {
+    COMMANDS.lock().keys().copied().collect()
+}
+
+/// Executa `argv[0]` com o resto como argumentos; retorna o status de saída
+pub fn execute(ctx: &mut Context, argv: &[&str]) -> i32 {
+    let Some((&name, args)) = argv.split_first() else {
+        return EXIT_SUCCESS;
+    };
+    let Some(command) = find(name) else {
+        vga_println!("{}: comando não encontrado", name);
+        return EXIT_NOT_FOUND;
+    };
+
+    let too_many = command.max_args.is_some_and(|max| args.len() > max);
+    if args.len() < command.min_args || too_many {
+        vga_println!("Uso: {} {}", command.name, command.usage);
+        return EXIT_USAGE;
+    }
+
+    match (command.handler)(ctx, args) {
+        Ok(()) => EXIT_SUCCESS,
+        Err(msg) => {
+            if !msg.is_empty() {
+                vga_println!("{}: {}", name, msg);
+            }
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Completa nomes de comandos e caminhos relativos ao diretório atual
+struct ShellCompleter<'a> {
+    cwd: &'a str,
+    functions: &'a Functions,
+}
+
+impl Completer for ShellCompleter<'_> {
+    fn complete(&self, word: &str, command: bool) -> Vec<String> {
+        if command && !word.contains('/') {
+            return command_names()
+                .into_iter()
+                .chain(self.functions.keys().map(String::as_str))
+                .filter(|c| c.starts_with(word))
+                .map(String::from)
+                .collect();
+        }
+
+        // "dir/pre" → lista "dir/" e filtra pelo prefixo "pre"
+        let (dir, prefix) = match word.rfind('/') {
+            Some(i) => (&word[..=i], &word[i + 1..]),
+            None => ("", word),
+        };
+        let Ok(path) = vfs::resolve(self.cwd, if dir.is_empty() { "." } else { dir }) else {
+            return Vec::new();
+        };
+        let Ok(listing) = VFS_INSTANCE.lock().list_dir(&path) else {
+            return Vec::new();
+        };
+
+        let prefix_upper = prefix.to_ascii_uppercase();
+        listing
+            .entries
+            .iter()
+            .filter(|e| e.name != "." && e.name != "..")
+            // Sem distinção de maiúsculas, já que nomes FAT são sempre maiúsculos
+            .filter(|e| e.name.to_ascii_uppercase().starts_with(&prefix_upper))
+            .map(|e| format!("{}{}{}", dir, e.name, if e.is_dir() { "/" } else { "" }))
+            .collect()
+    }
+}
+
+/// Estado que persiste entre as linhas: diretório, variáveis, funções,
+/// último status
+pub struct Shell {
+    pub cwd: String,
+    pub env: Environment,
+    pub functions: Functions,
+    pub last_status: i32,
+    /// Status pedido por `exit`, até o script que o executou terminar
+    pub exit_status: Option<i32>,
+    /// Funções e scripts aninhados em execução (limitado por `MAX_DEPTH`)
+    pub depth: usize,
+    /// Só a shell interativa tem editor (e histórico)
+    editor: Option<LineEditor>,
+}
+
+impl Shell {
+    pub fn new() -> Self {
+        let mut env = Environment::new();
+        env.insert("HOME".into(), "/".into());
+        env.insert("PWD".into(), "/".into());
+
+        Self {
+            cwd: String::from("/"),
+            env,
+            functions: Functions::new(),
+            last_status: EXIT_SUCCESS,
+            exit_status: None,
+            depth: 0,
+            editor: None,
+        }
+    }
+
+    /// Analisa e executa uma linha; retorna (e guarda em `$?`) o status
+    pub fn run_line(&mut self, line: &str) -> i32 {
+        let list = match shell_parser::parse(line) {
+            Ok(list) => list,
+            Err(e) => {
+                vga_println!("nnsh: erro de sintaxe: {}", e);
+                self.last_status = EXIT_USAGE;
+                return self.last_status;
             }
+        };
 
-            "clear" => {
-                crate::vga_buffer::clear_screen();
+        for (connector, pipeline) in &list.items {
+            let run = match connector {
+                Connector::Always => true,
+                Connector::And => self.last_status == EXIT_SUCCESS,
+                Connector::Or => self.last_status != EXIT_SUCCESS,
+            };
+            if run {
+                self.last_status = self.run_pipeline(pipeline);
             }
+        }
+        self.last_status
+    }
+
+    /// Liga a saída de cada estágio à entrada do próximo por um pipe; o
+    /// status é o do último estágio. Os estágios anteriores rodam cada um na
+    /// sua tarefa, numa cópia da shell; o último roda nesta, que espera os
+    /// demais terminarem
+    fn run_pipeline(&mut self, pipeline: &Pipeline) -> i32 {
+        let Some((last, stages)) = pipeline.commands.split_last() else {
+            return EXIT_SUCCESS;
+        };
+        let finished = Arc::new(Semaphore::new(0));
+        let mut input: Option<PipeReader> = None;
+        let mut running = 0;
 
-            _ => {
-                vga_println!("Comando não reconhecido: '{}'", cmd);
+        for command in stages {
+            let (reader, mut writer) = pipe::pipe();
+            let mut stage = self.pipeline_stage();
+            let command = command.clone();
+            let mut previous = input.take();
+            let done = finished.clone();
+            let spawned = scheduler::spawn("pipeline", SchedPolicy::default(), move || {
+                let console = (previous.is_none(), false);
+                let stdin: &mut dyn Input = match &mut previous {
+                    Some(reader) => reader,
+                    None => &mut ConsoleInput,
+                };
+                stage.run_command(&command, stdin, &mut writer, console);
+                // Fecha as pontas antes de avisar, para o próximo estágio ver o fim
+                drop(writer);
+                drop(previous);
+                done.release();
+            });
+            if spawned.is_none() {
+                vga_println!("nnsh: não foi possível criar a tarefa de um estágio do pipeline");
+                // Sem leitor, os estágios já criados recebem `BrokenPipe` e terminam
+                drop(reader);
+                (0..running).for_each(|_| finished.acquire());
+                return EXIT_FAILURE;
             }
+            running += 1;
+            input = Some(reader);
+        }
+
+        let status = match &mut input {
+            Some(reader) => self.run_command(last, reader, &mut Console, (false, true)),
+            None => self.run_command(last, &mut ConsoleInput, &mut Console, (true, true)),
+        };
+        drop(input);
+        (0..running).for_each(|_| finished.acquire());
+        status
+    }
+
+    /// Cópia da shell para um estágio de pipeline: herda diretório,
+    /// variáveis, funções e histórico, mas as mudanças não voltam
+    fn pipeline_stage(&self) -> Shell {
+        let mut stage = Shell::new();
+        stage.cwd = self.cwd.clone();
+        stage.env = self.env.clone();
+        stage.functions = self.functions.clone();
+        stage.last_status = self.last_status;
+        stage.depth = self.depth;
+        stage.editor = self.editor.as_ref().map(LineEditor::detached);
+        stage
+    }
+
+    /// `console` diz se a entrada e a saída recebidas são o console
+    fn run_command(
+        &mut self,
+        command: &SimpleCommand,
+        stdin: &mut dyn Input,
+        stdout: &mut dyn fmt::Write,
+        console: (bool, bool),
+    ) -> i32 {
+        // Sem processos filhos, atribuições antes de um comando também ficam na shell
+        for (name, value) in &command.assignments {
+            let value = value.expand(&self.env, self.last_status);
+            self.env.insert(name.clone(), value);
+        }
 
-"exec" => {
-    if let Some(f) = parts.next() {
-        let mut full_path = cwd.clone();
-        if !cwd.ends_with('/') {
-            full_path.push('/');
+        let argv: Vec<String> = command
+            .argv
+            .iter()
+            .map(|w| w.expand(&self.env, self.last_status))
+            .collect();
+        let argv: Vec<&str> = argv.iter().map(String::as_str).collect();
+
+        if let Some(body) = argv.first().and_then(|name| self.functions.get(*name)).cloned() {
+            return script::call_function(self, &body, &argv[1..]);
+        }
+
+        let mut input_file: Option<File> = None;
+        let mut output_file: Option<FileOutput> = None;
+        for redirect in &command.redirects {
+            let (Redirect::Input(word) | Redirect::Output(word) | Redirect::Append(word)) = redirect;
+            let target = word.expand(&self.env, self.last_status);
+            let opened = vfs::resolve(&self.cwd, &target).and_then(|path| {
+                let vfs = VFS_INSTANCE.lock();
+                match redirect {
+                    Redirect::Input(_) => vfs.open(&path).map(|f| input_file = Some(f)),
+                    Redirect::Output(_) => vfs.create(&path).map(|f| output_file = Some(FileOutput(f))),
+                    Redirect::Append(_) => {
+                        let mut file = match vfs.open(&path) {
+                            Err(VfsError::NotFound) => vfs.create(&path)?,
+                            other => other?,
+                        };
+                        file.seek(SeekFrom::End(0))?;
+                        output_file = Some(FileOutput(file));
+                        Ok(())
+                    }
+                }
+            });
+            if let Err(e) = opened {
+                vga_println!("nnsh: {}: {}", target, e);
+                return EXIT_FAILURE;
+            }
         }
-        full_path.push_str(f);
 
-        if let Some(mut file) = VFS_INSTANCE.lock().open(&full_path) {
-            let data = file.data;
+        let stdin_is_console = console.0 && input_file.is_none();
+        let stdout_is_console = console.1 && output_file.is_none();
+        let mut ctx = Context {
+            cwd: &mut self.cwd,
+            env: &mut self.env,
+            functions: &mut self.functions,
+            depth: self.depth,
+            stdin: match &mut input_file {
+                Some(file) => file,
+                None => stdin,
+            },
+            stdout: match &mut output_file {
+                Some(file) => file,
+                None => stdout,
+            },
+            stdin_is_console,
+            stdout_is_console,
+            history: self.editor.as_ref().map_or(&[], |e| e.history()),
+        };
+        execute(&mut ctx, &argv)
+    }
+
+    /// Laço interativo: executa o rc, depois lê comandos com o editor. Blocos
+    /// abertos (`if` sem `fi`, ...) continuam nas linhas seguintes.
+    pub fn run_interactive(&mut self) -> ! {
+        script::run_rc(self);
 
-            const LOAD_ADDR: usize = 0x50000;
-            let exec_mem = LOAD_ADDR as *mut u8;
+        loop {
+            let mut text = String::new();
+            let mut prompt = format!("[{}]$ ", self.cwd);
+            let block = loop {
+                let editor = self.editor.get_or_insert_with(|| LineEditor::with_history_file(&history_path()));
+                let completer = ShellCompleter { cwd: &self.cwd, functions: &self.functions };
+                let line = editor.read_line_with(&prompt, Some(&completer));
+                text.push_str(&line);
+                text.push('\n');
 
-            unsafe {
-                for (i, byte) in data.iter().enumerate() {
-                    core::ptr::write_volatile(exec_mem.add(i), *byte);
+                match script::parse(&text) {
+                    Ok(block) => break Some(block),
+                    Err(ParseError::Incomplete) => prompt = String::from("> "),
+                    Err(e) => {
+                        vga_println!("nnsh: erro de sintaxe: {}", e);
+                        self.last_status = EXIT_USAGE;
+                        break None;
+                    }
                 }
+            };
+
+            if let Some(block) = block {
+                script::run_block(self, &block);
+            }
+            if self.exit_status.take().is_some() {
+                vga_println!("nnsh: a shell do kernel não pode ser encerrada");
+            }
+        }
+    }
+}
 
-                vga_println!("Executando '{}'", f);
+pub fn run_shell() -> ! {
+    Shell::new().run_interactive()
+}
+
+/// Registra os comandos internos da shell
+pub fn init() {
+    let builtins = [
+        Command { name: "help", usage: "[comando]", help: "mostra os comandos ou a ajuda de um deles", min_args: 0, max_args: Some(1), handler: cmd_help },
+        Command { name: "ls", usage: "[-la] [diretório]", help: "lista arquivos (-l: detalhes, -a: inclui ocultos)", min_args: 0, max_args: None, handler: cmd_ls },
+        Command { name: "stat", usage: "<caminho>...", help: "mostra metadados", min_args: 1, max_args: None, handler: cmd_stat },
+        Command { name: "ln", usage: "[-s] <alvo> <nome>", help: "cria um link (-s: simbólico)", min_args: 2, max_args: Some(3), handler: cmd_ln },
+        Command { name: "cd", usage: "<diretório>", help: "muda de diretório", min_args: 1, max_args: Some(1), handler: cmd_cd },
+        Command { name: "cat", usage: "[arquivo]...", help: "mostra arquivos (ou a entrada padrão)", min_args: 0, max_args: None, handler: cmd_cat },
+        Command { name: "clear", usage: "", help: "limpa a tela", min_args: 0, max_args: Some(0), handler: cmd_clear },
+        Command { name: "history", usage: "", help: "lista os comandos anteriores", min_args: 0, max_args: Some(0), handler: cmd_history },
+        Command { name: "mount", usage: "<dispositivo> <diretório>", help: "monta um dispositivo", min_args: 2, max_args: Some(2), handler: cmd_mount },
+        Command { name: "umount", usage: "<diretório>", help: "desmonta um diretório", min_args: 1, max_args: Some(1), handler: cmd_umount },
+        Command { name: "mounts", usage: "", help: "lista as montagens", min_args: 0, max_args: Some(0), handler: cmd_mounts },
+        Command { name: "set", usage: "", help: "lista as variáveis da shell", min_args: 0, max_args: Some(0), handler: cmd_set },
+        Command { name: "export", usage: "<NOME=valor>...", help: "define variáveis", min_args: 1, max_args: None, handler: cmd_export },
+        Command { name: "unset", usage: "<NOME>...", help: "remove variáveis", min_args: 1, max_args: None, handler: cmd_unset },
+        Command { name: "exec", usage: "<arquivo> [argumento]...", help: "carrega e executa um programa ELF64", min_args: 1, max_args: None, handler: cmd_exec },
+    ];
+    for command in builtins {
+        register(command);
+    }
+}
+
+fn cmd_help(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    if let Some(name) = args.first() {
+        let command = find(name).ok_or_else(|| format!("{}: comando não encontrado", name))?;
+        sh_println!(ctx, "Uso: {} {}", command.name, command.usage);
+        sh_println!(ctx, "  {}", command.help);
+        return Ok(());
+    }
+
+    let commands: Vec<Command> = COMMANDS.lock().values().copied().collect();
+    let width = commands
+        .iter()
+        .map(|c| c.name.len() + c.usage.len() + 1)
+        .max()
+        .unwrap_or(0);
+
+    sh_println!(ctx, "Comandos disponíveis:");
+    for command in commands {
+        let synopsis = format!("{} {}", command.name, command.usage);
+        sh_println!(ctx, "  {:<width$} - {}", synopsis, command.help, width = width);
+    }
+    Ok(())
+}
+
+fn cmd_ls(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (flags, targets) = parse_flags(args, "la")?;
+    let (long, all) = (flags.contains('l'), flags.contains('a'));
+    if targets.len() > 1 {
+        return Err(String::from("só um diretório por vez"));
+    }
+    let target = targets.first().copied().unwrap_or(".");
 
-                let entry: extern "C" fn() = core::mem::transmute(LOAD_ADDR);
-                entry(); // executa o binário
+    let path = ctx.resolve(target).map_err(|e| e.to_string())?;
+    let dir = VFS_INSTANCE.lock().list_dir(&path).map_err(|e| format!("{}: {}", target, e))?;
 
-                vga_println!("\nFim da execução de '{}'", f);
+    // Nomes começando com '.' ficam ocultos sem -a
+    for e in dir.entries.into_iter().filter(|e| all || !e.name.starts_with('.')) {
+        let m = &e.metadata;
+        if long {
+            let mut name = e.name.clone();
+            if m.file_type == FileType::Symlink {
+                let link = vfs::resolve(&path, &e.name).and_then(|p| VFS_INSTANCE.lock().readlink(&p));
+                if let Ok(link) = link {
+                    name = format!("{} -> {}", name, link);
+                }
             }
+            sh_println!(
+                ctx,
+                "{} {:>2} {:>4} {:>4} {:>8} {} {}",
+                m.mode_string(),
+                m.nlink,
+                m.uid,
+                m.gid,
+                m.size,
+                DateTime::from_unix(m.modified),
+                name
+            );
+        } else if e.is_dir() {
+            sh_println!(ctx, "<DIR> {}", e.name);
         } else {
-            vga_println!("Binário '{}' não encontrado", f);
+            sh_println!(ctx, "     {} ({} bytes)", e.name, m.size);
         }
+    }
+    Ok(())
+}
+
+fn cmd_stat(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    for &target in args {
+        let metadata = ctx
+            .resolve(target)
+            .and_then(|path| VFS_INSTANCE.lock().lstat(&path))
+            .map_err(|e| format!("{}: {}", target, e))?;
+        print_metadata(ctx, target, &metadata);
+    }
+    Ok(())
+}
+
+fn print_metadata(ctx: &mut Context, name: &str, m: &Metadata) {
+    sh_println!(ctx, "  Arquivo: {}", name);
+    sh_println!(ctx, "  Tamanho: {:<10} Tipo: {}", m.size, m.file_type);
+    sh_println!(ctx, "    Inode: {:<10} Links: {}", m.inode, m.nlink);
+    sh_println!(ctx, "   Acesso: ({:04o}/{})  Uid: {}  Gid: {}", m.mode, m.mode_string(), m.uid, m.gid);
+
+    let mut attrs = Vec::new();
+    if m.attributes.readonly {
+        attrs.push("somente leitura");
+    }
+    if m.attributes.hidden {
+        attrs.push("oculto");
+    }
+    if m.attributes.system {
+        attrs.push("sistema");
+    }
+    if !attrs.is_empty() {
+        sh_println!(ctx, "Atributos: {}", attrs.join(", "));
+    }
+
+    sh_println!(ctx, "   Acesso: {}", DateTime::from_unix(m.accessed));
+    sh_println!(ctx, "Modificação: {}", DateTime::from_unix(m.modified));
+    sh_println!(ctx, "  Criação: {}", DateTime::from_unix(m.created));
+}
+
+fn cmd_ln(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (symbolic, target, name) = match args {
+        ["-s", target, name] => (true, *target, *name),
+        [target, name] => (false, *target, *name),
+        _ => return Err(String::from("Uso: ln [-s] <alvo> <nome>")),
+    };
+
+    let path = ctx.resolve(name).map_err(|e| e.to_string())?;
+    let result = if symbolic {
+        // O alvo de um link simbólico é gravado como foi digitado
+        VFS_INSTANCE.lock().symlink(target, &path)
     } else {
-        vga_println!("Uso: exec <arquivo>");
+        let existing = ctx.resolve(target).map_err(|e| e.to_string())?;
+        VFS_INSTANCE.lock().link(&existing, &path)
+    };
+    result.map_err(|e| format!("{}: {}", name, e))
+}
+
+fn cmd_cd(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let target = args[0];
+    let path = ctx.resolve(target).map_err(|e| e.to_string())?;
+    let dir = VFS_INSTANCE.lock().list_dir(&path).map_err(|e| format!("{}: {}", target, e))?;
+    // list_dir devolve o caminho já com os links resolvidos
+    ctx.env.insert("PWD".into(), dir.name.clone());
+    *ctx.cwd = dir.name;
+    Ok(())
+}
+
+fn cmd_set(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    let vars: Vec<(String, String)> = ctx.env.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    for (name, value) in vars {
+        sh_println!(ctx, "{}={}", name, value);
+    }
+    Ok(())
+}
+
+fn cmd_export(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    for arg in args {
+        let (name, value) = arg.split_once('=').ok_or_else(|| format!("{}: esperado NOME=valor", arg))?;
+        ctx.env.insert(name.into(), value.into());
     }
+    Ok(())
+}
 
-        }
+fn cmd_unset(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    for name in args {
+        ctx.env.remove(*name);
     }
+    Ok(())
 }
 
-fn read_line() -> String {
-    use x86_64::instructions::interrupts;
-    use spin::Mutex;
-    use pc_keyboard::{layouts, DecodedKey, HandleControl, Keyboard, ScancodeSet1};
-    use crate::keyboard::KEYBOARD;
-    use crate::vga_buffer::vga_print;
-
-    let mut buf = String::new();
-
-    loop {
-        interrupts::disable();
-        let mut keyboard = KEYBOARD.lock();
-        if let Some(key_event) = keyboard.next_key() {
-            if let Some(key) = key_event {
-                match key {
-                    DecodedKey::Unicode(c) => {
-                        if c == '\n' || c == '\r' {
-                            vga_println!();
-                            break;
-                        } else if c == '\x08' || c == '\x7F' { // backspace
-                            if !buf.is_empty() {
-                                buf.pop();
-                                vga_print!("\x08 \x08");
-                            }
-                        } else {
-                            buf.push(c);
-                            vga_print!("{}", c);
-                        }
+fn cmd_cat(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    if args.is_empty() {
+        let data = ctx.read_stdin().map_err(|e| e.to_string())?;
+        for &b in &data {
+            sh_print!(ctx, "{}", b as char);
+        }
+        return Ok(());
+    }
+
+    for &name in args {
+        let fd = ctx
+            .resolve(name)
+            .and_then(|path| KERNEL_FDS.lock().open(&path))
+            .map_err(|e| format!("{}: {}", name, e))?;
+
+        let mut buf = [0u8; 512];
+        let result = loop {
+            match KERNEL_FDS.lock().read(fd, &mut buf) {
+                Ok(0) => break Ok(()),
+                Ok(n) => {
+                    for &b in &buf[..n] {
+                        sh_print!(ctx, "{}", b as char);
                     }
-                    _ => {}
                 }
+                Err(e) => break Err(format!("{}: {}", name, e)),
             }
-        }
-        interrupts::enable();
+        };
+        let _ = KERNEL_FDS.lock().close(fd);
+        result?;
+    }
+    Ok(())
+}
+
+fn cmd_clear(_ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    crate::vga_buffer::clear_screen();
+    Ok(())
+}
+
+fn cmd_history(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    for (i, entry) in ctx.history.iter().enumerate() {
+        sh_println!(ctx, "{:>4}  {}", i + 1, entry);
+    }
+    Ok(())
+}
+
+fn cmd_mount(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (device, dir) = (args[0], args[1]);
+    ctx.resolve(dir)
+        .and_then(|path| VFS_INSTANCE.lock().mount_device(device, &path))
+        .map_err(|e| format!("{}: {}", device, e))
+}
+
+fn cmd_umount(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let dir = args[0];
+    ctx.resolve(dir)
+        .and_then(|path| VFS_INSTANCE.lock().umount(&path))
+        .map_err(|e| format!("{}: {}", dir, e))
+}
+
+fn cmd_mounts(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    for (path, fs, source) in VFS_INSTANCE.lock().mounts() {
+        sh_println!(ctx, "{} em {} tipo {}", source, path, fs);
+    }
+    Ok(())
+}
+
+fn cmd_exec(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let name = args[0];
+    let data = ctx
+        .resolve(name)
+        .and_then(|path| VFS_INSTANCE.lock().open(&path))
+        .and_then(|mut file| file.read_to_end())
+        .map_err(|e| format!("{}: {}", name, e))?;
+
+    let envp: Vec<String> = ctx.env.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    let envp: Vec<&str> = envp.iter().map(String::as_str).collect();
+    // Só um programa roda por vez: a entrada que não é o console é lida
+    // inteira antes de carregá-lo e a saída é repassada no fim, para um
+    // estágio nunca esperar pelo pipe segurando a vez dos outros
+    let stdin = match ctx.stdin_is_console {
+        true => None,
+        false => Some(MemoryStream::new(ctx.read_stdin().map_err(|e| format!("{}: {}", name, e))?)),
+    };
+    let stdout = (!ctx.stdout_is_console).then(MemoryStream::default);
+
+    let mut program = elf::load(&data, args, &envp).map_err(|e| format!("{}: {}", name, e))?;
+    program.cwd = ctx.cwd.clone();
+    if let Some(stream) = &stdin {
+        program.fds.replace(0, Descriptor::Memory(stream.clone()));
+    }
+    if let Some(stream) = &stdout {
+        program.fds.replace(1, Descriptor::Memory(stream.clone()));
     }
 
-    buf
+    let status = program.run();
+    program.unload();
+
+    if let Some(stream) = stdout {
+        sh_print!(ctx, "{}", String::from_utf8_lossy(&stream.take()));
+    }
+
+    if status == EXIT_SUCCESS {
+        Ok(())
+    } else {
+        Err(format!("{}: terminou com status {}", name, status))
+    }
 }
@@ -0,0 +1,319 @@
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+
+/// Variáveis da shell (`NOME=valor`, expandidas com `$NOME`)
+pub type Environment = BTreeMap<String, String>;
+
+/// Pedaço de uma palavra: texto literal ou uma variável a expandir
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordPart {
+    Literal(String),
+    /// `$NOME` ou `${NOME}`; `$?` e `$#` usam os nomes "?" e "#"
+    Var(String),
+}
+
+/// Uma palavra da linha de comando; as aspas já foram removidas, mas as
+/// variáveis só são expandidas na execução (para `$?` refletir o comando anterior)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Word(pub Vec<WordPart>);
+
+impl Word {
+    fn push_char(&mut self, c: char) {
+        match self.0.last_mut() {
+            Some(WordPart::Literal(s)) => s.push(c),
+            _ => self.0.push(WordPart::Literal(c.into())),
+        }
+    }
+
+    /// Texto literal, se a palavra não tem variáveis
+    pub fn as_literal(&self) -> Option<String> {
+        let mut out = String::new();
+        for part in &self.0 {
+            match part {
+                WordPart::Literal(s) => out.push_str(s),
+                WordPart::Var(_) => return None,
+            }
+        }
+        Some(out)
+    }
+
+    pub fn expand(&self, env: &Environment, last_status: i32) -> String {
+        let mut out = String::new();
+        for part in &self.0 {
+            match part {
+                WordPart::Literal(s) => out.push_str(s),
+                WordPart::Var(name) if name == "?" => out.push_str(&format!("{}", last_status)),
+                WordPart::Var(name) => out.push_str(env.get(name).map_or("", String::as_str)),
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Redirect {
+    /// `< arquivo`
+    Input(Word),
+    /// `> arquivo` (trunca)
+    Output(Word),
+    /// `>> arquivo` (acrescenta)
+    Append(Word),
+}
+
+/// Um comando simples: atribuições, argumentos e redirecionamentos
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimpleCommand {
+    /// `NOME=valor` antes do comando
+    pub assignments: Vec<(String, Word)>,
+    pub argv: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+}
+
+/// Comandos ligados por `|`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pipeline {
+    pub commands: Vec<SimpleCommand>,
+}
+
+/// Como um pipeline se liga ao anterior na lista
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connector {
+    /// `;` (ou início da linha): sempre executa
+    Always,
+    /// `&&`: só se o anterior teve sucesso
+    And,
+    /// `||`: só se o anterior falhou
+    Or,
+}
+
+/// Linha inteira: pipelines separados por `;`, `&&` e `||`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandList {
+    pub items: Vec<(Connector, Pipeline)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(Word),
+    Semicolon,
+    And,
+    Or,
+    Pipe,
+    RedirectIn,
+    RedirectOut,
+    RedirectAppend,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Lê o nome depois de um `$` (já consumido) e o adiciona à palavra
+fn read_variable(chars: &mut core::iter::Peekable<core::str::Chars>, word: &mut Word) -> Result<(), &'static str> {
+    match chars.peek() {
+        Some(&c @ ('?' | '#')) => {
+            chars.next();
+            word.0.push(WordPart::Var(c.into()));
+        }
+        Some('{') => {
+            chars.next();
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(c) if is_name_char(c) => name.push(c),
+                    Some(_) => return Err("nome de variável inválido"),
+                    None => return Err("'}' não fechado"),
+                }
+            }
+            word.0.push(WordPart::Var(name));
+        }
+        Some(&c) if is_name_char(c) => {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if !is_name_char(c) {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+            word.0.push(WordPart::Var(name));
+        }
+        // "$" sozinho é literal
+        _ => word.push_char('$'),
+    }
+    Ok(())
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    let mut word: Option<Word> = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' | '\r' | ';' | '|' | '&' | '<' | '>' => {
+                if let Some(w) = word.take() {
+                    tokens.push(Token::Word(w));
+                }
+                let token = match c {
+                    ';' => Token::Semicolon,
+                    '|' if chars.peek() == Some(&'|') => {
+                        chars.next();
+                        Token::Or
+                    }
+                    '|' => Token::Pipe,
+                    '&' if chars.peek() == Some(&'&') => {
+                        chars.next();
+                        Token::And
+                    }
+                    '&' => return Err("execução em segundo plano não suportada"),
+                    '<' => Token::RedirectIn,
+                    '>' if chars.peek() == Some(&'>') => {
+                        chars.next();
+                        Token::RedirectAppend
+                    }
+                    '>' => Token::RedirectOut,
+                    _ => continue,
+                };
+                tokens.push(token);
+            }
+            // Comentário só no começo de uma palavra
+            '#' if word.is_none() => break,
+            '\'' => {
+                let w = word.get_or_insert_with(Word::default);
+                // Garante uma palavra mesmo para '' vazio
+                w.0.push(WordPart::Literal(String::new()));
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => w.push_char(c),
+                        None => return Err("aspas simples não fechadas"),
+                    }
+                }
+            }
+            '"' => {
+                let w = word.get_or_insert_with(Word::default);
+                w.0.push(WordPart::Literal(String::new()));
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$')) => w.push_char(c),
+                            Some(c) => {
+                                w.push_char('\\');
+                                w.push_char(c);
+                            }
+                            None => return Err("aspas duplas não fechadas"),
+                        },
+                        Some('$') => read_variable(&mut chars, w)?,
+                        Some(c) => w.push_char(c),
+                        None => return Err("aspas duplas não fechadas"),
+                    }
+                }
+            }
+            '\\' => {
+                let escaped = chars.next().ok_or("'\\' no fim da linha")?;
+                word.get_or_insert_with(Word::default).push_char(escaped);
+            }
+            '$' => read_variable(&mut chars, word.get_or_insert_with(Word::default))?,
+            c => word.get_or_insert_with(Word::default).push_char(c),
+        }
+    }
+
+    if let Some(w) = word {
+        tokens.push(Token::Word(w));
+    }
+    Ok(tokens)
+}
+
+/// Se a palavra é `NOME=valor` (nome literal), separa as duas partes
+fn split_assignment(word: &Word) -> Option<(String, Word)> {
+    let Some(WordPart::Literal(first)) = word.0.first() else { return None };
+    let (name, rest) = first.split_once('=')?;
+    if name.is_empty() || !name.chars().all(is_name_char) || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut value = Word::default();
+    if !rest.is_empty() {
+        value.0.push(WordPart::Literal(rest.into()));
+    }
+    value.0.extend(word.0[1..].iter().cloned());
+    Some((name.into(), value))
+}
+
+/// Separa `text` em palavras (ex: a lista de um `for`); operadores não são aceitos
+pub fn words(text: &str) -> Result<Vec<Word>, &'static str> {
+    tokenize(text)?
+        .into_iter()
+        .map(|token| match token {
+            Token::Word(word) => Ok(word),
+            _ => Err("operador inesperado"),
+        })
+        .collect()
+}
+
+/// Analisa uma linha da shell
+pub fn parse(line: &str) -> Result<CommandList, &'static str> {
+    let mut list = CommandList::default();
+    let mut pipeline = Pipeline::default();
+    let mut command = SimpleCommand::default();
+    let mut connector = Connector::Always;
+
+    let mut tokens = tokenize(line)?.into_iter();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Word(word) => {
+                if command.argv.is_empty() {
+                    if let Some(assignment) = split_assignment(&word) {
+                        command.assignments.push(assignment);
+                        continue;
+                    }
+                }
+                command.argv.push(word);
+            }
+            Token::RedirectIn | Token::RedirectOut | Token::RedirectAppend => {
+                let Some(Token::Word(target)) = tokens.next() else {
+                    return Err("redirecionamento sem arquivo");
+                };
+                command.redirects.push(match token {
+                    Token::RedirectIn => Redirect::Input(target),
+                    Token::RedirectOut => Redirect::Output(target),
+                    _ => Redirect::Append(target),
+                });
+            }
+            Token::Pipe => {
+                if command == SimpleCommand::default() {
+                    return Err("'|' sem comando");
+                }
+                pipeline.commands.push(core::mem::take(&mut command));
+            }
+            Token::Semicolon | Token::And | Token::Or => {
+                if command == SimpleCommand::default() {
+                    // `;` no fim ou linhas vazias entre `;` são aceitos
+                    if token != Token::Semicolon || !pipeline.commands.is_empty() {
+                        return Err("operador sem comando");
+                    }
+                    continue;
+                }
+                pipeline.commands.push(core::mem::take(&mut command));
+                list.items.push((connector, core::mem::take(&mut pipeline)));
+                connector = match token {
+                    Token::And => Connector::And,
+                    Token::Or => Connector::Or,
+                    _ => Connector::Always,
+                };
+            }
+        }
+    }
+
+    if command != SimpleCommand::default() {
+        pipeline.commands.push(command);
+    } else if !pipeline.commands.is_empty() || connector != Connector::Always {
+        return Err("comando incompleto no fim da linha");
+    }
+    if !pipeline.commands.is_empty() {
+        list.items.push((connector, pipeline));
+    }
+    Ok(list)
+}
@@ -0,0 +1,532 @@
+use alloc::collections::VecDeque;
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use x86_64::instructions::interrupts;
+
+use crate::scheduler;
+use crate::task::TaskId;
+
+/// Verificação de locks (feature `lockdep`): pegar de novo um lock que a
+/// tarefa já tem, ou dois locks nomeados na ordem inversa de uma vez
+/// anterior, gera panic com os nomes envolvidos
+#[cfg(feature = "lockdep")]
+mod lockdep {
+    use alloc::collections::{BTreeMap, BTreeSet};
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use x86_64::instructions::interrupts;
+
+    use crate::scheduler;
+
+    struct State {
+        /// Locks presos por tarefa (0 antes do escalonador): endereço e nome
+        held: BTreeMap<u64, Vec<(usize, &'static str)>>,
+        /// Pares (antes, depois) já observados entre locks nomeados
+        order: BTreeSet<(&'static str, &'static str)>,
+    }
+
+    static STATE: spin::Mutex<State> = spin::Mutex::new(State { held: BTreeMap::new(), order: BTreeSet::new() });
+
+    /// Desligado no primeiro erro, para o panic conseguir imprimir
+    static ENABLED: AtomicBool = AtomicBool::new(true);
+
+    enum Violation {
+        Recursion,
+        Order(&'static str),
+    }
+
+    fn context() -> u64 {
+        scheduler::current_id().map_or(0, |id| id.0 + 1)
+    }
+
+    pub fn acquire(addr: usize, name: &'static str) {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return;
+        }
+        let context = context();
+        let violation = interrupts::without_interrupts(|| {
+            let mut state = STATE.lock();
+            let State { held, order } = &mut *state;
+            let held = held.entry(context).or_default();
+
+            if held.iter().any(|&(a, _)| a == addr) {
+                return Some(Violation::Recursion);
+            }
+            if !name.is_empty() {
+                for &(_, before) in held.iter() {
+                    if before.is_empty() || before == name {
+                        continue;
+                    }
+                    if order.contains(&(name, before)) {
+                        return Some(Violation::Order(before));
+                    }
+                    order.insert((before, name));
+                }
+            }
+            held.push((addr, name));
+            None
+        });
+
+        if let Some(violation) = violation {
+            ENABLED.store(false, Ordering::Relaxed);
+            match violation {
+                Violation::Recursion => panic!("lockdep: recursão no lock '{}'", name),
+                Violation::Order(before) => panic!(
+                    "lockdep: '{}' pego depois de '{}', mas já foi visto '{}' depois de '{}'",
+                    name, before, before, name
+                ),
+            }
+        }
+    }
+
+    pub fn release(addr: usize) {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return;
+        }
+        let context = context();
+        interrupts::without_interrupts(|| {
+            if let Some(held) = STATE.lock().held.get_mut(&context) {
+                if let Some(at) = held.iter().rposition(|&(a, _)| a == addr) {
+                    held.remove(at);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(not(feature = "lockdep"))]
+mod lockdep {
+    #[inline(always)]
+    pub fn acquire(_addr: usize, _name: &'static str) {}
+
+    #[inline(always)]
+    pub fn release(_addr: usize) {}
+}
+
+/// Spinlock que desliga as interrupções enquanto está preso: para estado
+/// tocado por handlers de IRQ, que nunca o encontram ocupado pelo código que
+/// interromperam. Os outros locks deste módulo bloqueiam a tarefa e só podem
+/// ser usados fora de interrupções.
+pub struct IrqSpinLock<T> {
+    locked: AtomicBool,
+    name: &'static str,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for IrqSpinLock<T> {}
+unsafe impl<T: Send> Sync for IrqSpinLock<T> {}
+
+impl<T> IrqSpinLock<T> {
+    pub const fn new(data: T) -> Self {
+        Self::named("", data)
+    }
+
+    /// O nome identifica o lock nas mensagens e na ordem do lockdep
+    pub const fn named(name: &'static str, data: T) -> Self {
+        Self { locked: AtomicBool::new(false), name, data: UnsafeCell::new(data) }
+    }
+
+    pub fn lock(&self) -> IrqSpinLockGuard<'_, T> {
+        let irq_enabled = interrupts::are_enabled();
+        interrupts::disable();
+        lockdep::acquire(self as *const _ as usize, self.name);
+        while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            core::hint::spin_loop();
+        }
+        IrqSpinLockGuard { lock: self, irq_enabled }
+    }
+
+    pub fn try_lock(&self) -> Option<IrqSpinLockGuard<'_, T>> {
+        let irq_enabled = interrupts::are_enabled();
+        interrupts::disable();
+        if self.locked.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            if irq_enabled {
+                interrupts::enable();
+            }
+            return None;
+        }
+        lockdep::acquire(self as *const _ as usize, self.name);
+        Some(IrqSpinLockGuard { lock: self, irq_enabled })
+    }
+}
+
+pub struct IrqSpinLockGuard<'a, T> {
+    lock: &'a IrqSpinLock<T>,
+    /// Estado das interrupções antes do `lock`, restaurado no drop
+    irq_enabled: bool,
+}
+
+impl<T> Deref for IrqSpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for IrqSpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for IrqSpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+        lockdep::release(self.lock as *const _ as usize);
+        if self.irq_enabled {
+            interrupts::enable();
+        }
+    }
+}
+
+/// Fila de tarefas esperando um evento. Quem acorda sempre confere de novo
+/// a condição: `wake_*` só diz que vale a pena olhar.
+pub struct WaitQueue {
+    waiters: IrqSpinLock<VecDeque<TaskId>>,
+}
+
+impl WaitQueue {
+    pub const fn new() -> Self {
+        Self { waiters: IrqSpinLock::new(VecDeque::new()) }
+    }
+
+    fn enqueue(&self, id: TaskId) {
+        let mut waiters = self.waiters.lock();
+        if !waiters.contains(&id) {
+            waiters.push_back(id);
+        }
+    }
+
+    fn remove(&self, id: TaskId) {
+        self.waiters.lock().retain(|&waiter| waiter != id);
+    }
+
+    /// Bloqueia até `condition` devolver `Some`. A condição é conferida de
+    /// novo depois de entrar na fila, então um `wake` entre a verificação e
+    /// o bloqueio não se perde. Antes do escalonador, apenas gira.
+    pub fn wait_until<R>(&self, mut condition: impl FnMut() -> Option<R>) -> R {
+        loop {
+            if let Some(result) = condition() {
+                return result;
+            }
+            let Some(me) = scheduler::current_id() else {
+                core::hint::spin_loop();
+                continue;
+            };
+
+            self.enqueue(me);
+            if let Some(result) = condition() {
+                self.remove(me);
+                return result;
+            }
+            scheduler::block();
+        }
+    }
+
+    /// Entra na fila e bloqueia uma vez; pode voltar sem um `wake` ter sido
+    /// feito para este evento, então chame dentro de um laço
+    pub fn wait(&self) {
+        match scheduler::current_id() {
+            Some(me) => {
+                self.enqueue(me);
+                scheduler::block();
+            }
+            None => core::hint::spin_loop(),
+        }
+    }
+
+    /// Acorda a primeira tarefa da fila; pode ser chamada de uma IRQ
+    pub fn wake_one(&self) -> bool {
+        let waiter = self.waiters.lock().pop_front();
+        waiter.is_some_and(scheduler::wake)
+    }
+
+    /// Acorda todas as tarefas da fila; retorna quantas
+    pub fn wake_all(&self) -> usize {
+        let waiters = core::mem::take(&mut *self.waiters.lock());
+        waiters.into_iter().filter(|&id| scheduler::wake(id)).count()
+    }
+}
+
+impl Default for WaitQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Identificador da tarefa atual para os donos de locks (0 antes do escalonador)
+fn owner_id() -> u64 {
+    scheduler::current_id().map_or(0, |id| id.0 + 1)
+}
+
+/// Mutex que bloqueia a tarefa enquanto outra segura o lock
+pub struct Mutex<T> {
+    locked: AtomicBool,
+    owner: AtomicU64,
+    name: &'static str,
+    waiters: WaitQueue,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for Mutex<T> {}
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    pub const fn new(data: T) -> Self {
+        Self::named("", data)
+    }
+
+    pub const fn named(name: &'static str, data: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            owner: AtomicU64::new(0),
+            name,
+            waiters: WaitQueue::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        self.owner.store(owner_id(), Ordering::Relaxed);
+        lockdep::acquire(self as *const _ as usize, self.name);
+        Some(MutexGuard { mutex: self })
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        let me = owner_id();
+        // Com o escalonador ativo, pegar de novo o próprio lock travaria a
+        // tarefa para sempre
+        if me != 0 && self.locked.load(Ordering::Relaxed) && self.owner.load(Ordering::Relaxed) == me {
+            panic!("mutex '{}': recursão na tarefa {}", self.name, me - 1);
+        }
+        self.waiters.wait_until(|| self.try_lock())
+    }
+}
+
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        lockdep::release(self.mutex as *const _ as usize);
+        self.mutex.owner.store(0, Ordering::Relaxed);
+        self.mutex.locked.store(false, Ordering::Release);
+        self.mutex.waiters.wake_one();
+    }
+}
+
+/// Semáforo contador
+pub struct Semaphore {
+    count: AtomicUsize,
+    waiters: WaitQueue,
+}
+
+impl Semaphore {
+    pub const fn new(count: usize) -> Self {
+        Self { count: AtomicUsize::new(count), waiters: WaitQueue::new() }
+    }
+
+    pub fn try_acquire(&self) -> bool {
+        self.count
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    /// Espera uma unidade ficar disponível (P)
+    pub fn acquire(&self) {
+        self.waiters.wait_until(|| self.try_acquire().then_some(()));
+    }
+
+    /// Devolve uma unidade (V); pode ser chamada de uma IRQ
+    pub fn release(&self) {
+        self.count.fetch_add(1, Ordering::Release);
+        self.waiters.wake_one();
+    }
+
+    pub fn available(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+/// Variável de condição associada a um `Mutex`
+pub struct Condvar {
+    waiters: WaitQueue,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self { waiters: WaitQueue::new() }
+    }
+
+    /// Solta o mutex, espera um `notify` e o pega de novo. Pode acordar sem
+    /// notificação: confira a condição num laço.
+    pub fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let mutex = guard.mutex;
+        match scheduler::current_id() {
+            // Entra na fila antes de soltar o mutex: um `notify` logo depois
+            // fica pendente e o `block` volta na hora
+            Some(me) => {
+                self.waiters.enqueue(me);
+                drop(guard);
+                scheduler::block();
+            }
+            None => {
+                drop(guard);
+                core::hint::spin_loop();
+            }
+        }
+        mutex.lock()
+    }
+
+    /// Espera até `condition` ser falsa sobre o dado protegido
+    pub fn wait_while<'a, T>(&self, mut guard: MutexGuard<'a, T>, mut condition: impl FnMut(&mut T) -> bool) -> MutexGuard<'a, T> {
+        while condition(&mut guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    pub fn notify_one(&self) {
+        self.waiters.wake_one();
+    }
+
+    pub fn notify_all(&self) {
+        self.waiters.wake_all();
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Valor de `RwLock::state` quando há um escritor
+const WRITE_LOCKED: usize = usize::MAX;
+
+/// Vários leitores ou um escritor. Escritores esperando barram leitores
+/// novos, para não esperarem para sempre.
+pub struct RwLock<T> {
+    /// Número de leitores, ou `WRITE_LOCKED`
+    state: AtomicUsize,
+    writers_waiting: AtomicUsize,
+    name: &'static str,
+    waiters: WaitQueue,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for RwLock<T> {}
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    pub const fn new(data: T) -> Self {
+        Self::named("", data)
+    }
+
+    pub const fn named(name: &'static str, data: T) -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            writers_waiting: AtomicUsize::new(0),
+            name,
+            waiters: WaitQueue::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        if self.writers_waiting.load(Ordering::Relaxed) > 0 {
+            return None;
+        }
+        self.state
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |n| (n < WRITE_LOCKED - 1).then_some(n + 1))
+            .ok()?;
+        lockdep::acquire(self as *const _ as usize, self.name);
+        Some(RwLockReadGuard { lock: self })
+    }
+
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        self.state
+            .compare_exchange(0, WRITE_LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        lockdep::acquire(self as *const _ as usize, self.name);
+        Some(RwLockWriteGuard { lock: self })
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.waiters.wait_until(|| self.try_read())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.writers_waiting.fetch_add(1, Ordering::Relaxed);
+        let guard = self.waiters.wait_until(|| self.try_write());
+        self.writers_waiting.fetch_sub(1, Ordering::Relaxed);
+        guard
+    }
+}
+
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        lockdep::release(self.lock as *const _ as usize);
+        if self.lock.state.fetch_sub(1, Ordering::Release) == 1 {
+            self.lock.waiters.wake_all();
+        }
+    }
+}
+
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        lockdep::release(self.lock as *const _ as usize);
+        self.lock.state.store(0, Ordering::Release);
+        self.lock.waiters.wake_all();
+    }
+}
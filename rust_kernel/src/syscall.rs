@@ -0,0 +1,664 @@
+use alloc::{string::String, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+use x86_64::registers::model_specific::{Efer, EferFlags, LStar, SFMask, Star};
+use x86_64::registers::rflags::RFlags;
+use x86_64::structures::paging::PageTableFlags;
+use x86_64::VirtAddr;
+
+use crate::address_space::{AddressSpace, AddressSpaceError, Vma, USER_SPACE_END};
+use crate::elf::{self, LoadedProgram};
+use crate::sched_class::TICK_NS;
+use crate::vfs::{self, FileType, Metadata, SeekFrom, VfsError, VFS_INSTANCE};
+use crate::{gdt, rtc, scheduler, timer};
+
+const PAGE_SIZE: u64 = 4096;
+
+/// Números das chamadas, os mesmos do Linux x86_64. Argumentos em rdi,
+/// rsi, rdx, r10, r8 e r9; o retorno volta em rax, com `-errno` em caso de
+/// erro (valores entre -4095 e -1).
+///
+/// | nº  | chamada                                  | retorno            |
+/// |-----|------------------------------------------|--------------------|
+/// | 0   | read(fd, buf, len)                       | bytes lidos        |
+/// | 1   | write(fd, buf, len)                      | bytes escritos     |
+/// | 2   | open(path, flags, mode)                  | descritor          |
+/// | 3   | close(fd)                                | 0                  |
+/// | 4   | stat(path, statbuf)                      | 0                  |
+/// | 5   | fstat(fd, statbuf)                       | 0                  |
+/// | 8   | lseek(fd, offset, whence)                | nova posição       |
+/// | 9   | mmap(addr, len, prot, flags, fd, offset) | endereço           |
+/// | 11  | munmap(addr, len)                        | 0                  |
+/// | 12  | brk(addr)                                | break atual        |
+/// | 35  | nanosleep(req, rem)                      | 0                  |
+/// | 39  | getpid()                                 | id da tarefa       |
+/// | 60  | exit(status)                             | não retorna        |
+/// | 217 | getdents64(fd, dirp, len)                | bytes escritos     |
+/// | 228 | clock_gettime(clock, tp)                 | 0                  |
+/// | 231 | exit_group(status)                       | não retorna        |
+///
+/// Todo ponteiro é conferido contra as VMAs do programa antes do uso
+/// (`EFAULT` se cair fora delas, ou numa região só de leitura quando o
+/// kernel precisa escrever).
+pub const SYS_READ: u64 = 0;
+pub const SYS_WRITE: u64 = 1;
+pub const SYS_OPEN: u64 = 2;
+pub const SYS_CLOSE: u64 = 3;
+pub const SYS_STAT: u64 = 4;
+pub const SYS_FSTAT: u64 = 5;
+pub const SYS_LSEEK: u64 = 8;
+pub const SYS_MMAP: u64 = 9;
+pub const SYS_MUNMAP: u64 = 11;
+pub const SYS_BRK: u64 = 12;
+pub const SYS_NANOSLEEP: u64 = 35;
+pub const SYS_GETPID: u64 = 39;
+pub const SYS_EXIT: u64 = 60;
+pub const SYS_GETDENTS64: u64 = 217;
+pub const SYS_CLOCK_GETTIME: u64 = 228;
+pub const SYS_EXIT_GROUP: u64 = 231;
+
+/// Código de erro (errno), com a numeração do Linux
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Errno(pub i64);
+
+impl Errno {
+    pub const ENOENT: Errno = Errno(2);
+    pub const ESRCH: Errno = Errno(3);
+    pub const EIO: Errno = Errno(5);
+    pub const EBADF: Errno = Errno(9);
+    pub const ENOMEM: Errno = Errno(12);
+    pub const EFAULT: Errno = Errno(14);
+    pub const EBUSY: Errno = Errno(16);
+    pub const EEXIST: Errno = Errno(17);
+    pub const EXDEV: Errno = Errno(18);
+    pub const ENODEV: Errno = Errno(19);
+    pub const ENOTDIR: Errno = Errno(20);
+    pub const EISDIR: Errno = Errno(21);
+    pub const EINVAL: Errno = Errno(22);
+    pub const EMFILE: Errno = Errno(24);
+    pub const EFBIG: Errno = Errno(27);
+    pub const ENOSPC: Errno = Errno(28);
+    pub const EROFS: Errno = Errno(30);
+    pub const EPIPE: Errno = Errno(32);
+    pub const ENAMETOOLONG: Errno = Errno(36);
+    pub const ENOSYS: Errno = Errno(38);
+    pub const ENOTEMPTY: Errno = Errno(39);
+    pub const ELOOP: Errno = Errno(40);
+    pub const EOPNOTSUPP: Errno = Errno(95);
+}
+
+impl From<VfsError> for Errno {
+    fn from(error: VfsError) -> Self {
+        match error {
+            VfsError::NotFound | VfsError::NotMounted => Errno::ENOENT,
+            VfsError::NotADirectory => Errno::ENOTDIR,
+            VfsError::IsADirectory => Errno::EISDIR,
+            VfsError::InvalidPath | VfsError::InvalidSeek => Errno::EINVAL,
+            VfsError::AlreadyMounted | VfsError::Busy => Errno::EBUSY,
+            VfsError::NoDevice => Errno::ENODEV,
+            VfsError::ReadOnly => Errno::EROFS,
+            VfsError::BadDescriptor => Errno::EBADF,
+            VfsError::TooManyOpenFiles => Errno::EMFILE,
+            VfsError::AlreadyExists => Errno::EEXIST,
+            VfsError::NotEmpty => Errno::ENOTEMPTY,
+            VfsError::NoSpace => Errno::ENOSPC,
+            VfsError::FileTooLarge => Errno::EFBIG,
+            VfsError::Unsupported => Errno::EOPNOTSUPP,
+            VfsError::SymlinkLoop => Errno::ELOOP,
+            VfsError::CrossDevice => Errno::EXDEV,
+            VfsError::BrokenPipe => Errno::EPIPE,
+            VfsError::Io => Errno::EIO,
+        }
+    }
+}
+
+impl From<AddressSpaceError> for Errno {
+    fn from(error: AddressSpaceError) -> Self {
+        match error {
+            AddressSpaceError::BadRange => Errno::EINVAL,
+            _ => Errno::ENOMEM,
+        }
+    }
+}
+
+/// Registradores salvos na entrada (`syscall` ou `int 0x80`), na ordem
+/// inversa em que são empilhados. Número em rax; argumentos em rdi, rsi,
+/// rdx, r10, r8 e r9; o retorno volta em rax.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SyscallFrame {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    /// RFLAGS do usuário (salvo pelo `syscall`)
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rbp: u64,
+    pub rdi: u64,
+    pub rsi: u64,
+    pub rdx: u64,
+    /// RIP do usuário (salvo pelo `syscall`)
+    pub rcx: u64,
+    pub rbx: u64,
+    pub rax: u64,
+}
+
+impl SyscallFrame {
+    pub fn args(&self) -> [u64; 6] {
+        [self.rdi, self.rsi, self.rdx, self.r10, self.r8, self.r9]
+    }
+}
+
+type SyscallResult = Result<u64, Errno>;
+type SyscallHandler = fn(&mut LoadedProgram, &[u64; 6]) -> SyscallResult;
+
+/// Tabela de chamadas: número, nome (para depuração) e handler
+static SYSCALLS: &[(u64, &str, SyscallHandler)] = &[
+    (SYS_READ, "read", sys_read),
+    (SYS_WRITE, "write", sys_write),
+    (SYS_OPEN, "open", sys_open),
+    (SYS_CLOSE, "close", sys_close),
+    (SYS_STAT, "stat", sys_stat),
+    (SYS_FSTAT, "fstat", sys_fstat),
+    (SYS_LSEEK, "lseek", sys_lseek),
+    (SYS_MMAP, "mmap", sys_mmap),
+    (SYS_MUNMAP, "munmap", sys_munmap),
+    (SYS_BRK, "brk", sys_brk),
+    (SYS_NANOSLEEP, "nanosleep", sys_nanosleep),
+    (SYS_GETPID, "getpid", sys_getpid),
+    (SYS_EXIT, "exit", sys_exit),
+    (SYS_GETDENTS64, "getdents64", sys_getdents64),
+    (SYS_CLOCK_GETTIME, "clock_gettime", sys_clock_gettime),
+    (SYS_EXIT_GROUP, "exit_group", sys_exit),
+];
+
+/// Pilha do kernel para o `syscall` (que, ao contrário das interrupções, não
+/// troca de pilha sozinho) e RSP do usuário durante a troca
+#[no_mangle]
+static SYSCALL_KERNEL_RSP: AtomicU64 = AtomicU64::new(0);
+#[no_mangle]
+static SYSCALL_USER_RSP: AtomicU64 = AtomicU64::new(0);
+
+pub fn set_kernel_stack(top: VirtAddr) {
+    SYSCALL_KERNEL_RSP.store(top.as_u64(), Ordering::Relaxed);
+}
+
+core::arch::global_asm!(
+    ".global syscall_entry",
+    "syscall_entry:",
+    // Interrupções estão mascaradas (SFMASK) até a pilha do kernel estar ativa
+    "mov [rip + SYSCALL_USER_RSP], rsp",
+    "mov rsp, [rip + SYSCALL_KERNEL_RSP]",
+    "push qword ptr [rip + SYSCALL_USER_RSP]",
+    "push rax", "push rbx", "push rcx", "push rdx", "push rsi", "push rdi", "push rbp",
+    "push r8", "push r9", "push r10", "push r11", "push r12", "push r13", "push r14", "push r15",
+    "mov rdi, rsp",
+    "sti",
+    "call syscall_dispatch",
+    "cli",
+    "pop r15", "pop r14", "pop r13", "pop r12", "pop r11", "pop r10", "pop r9", "pop r8",
+    "pop rbp", "pop rdi", "pop rsi", "pop rdx", "pop rcx", "pop rbx", "pop rax",
+    "pop rsp",
+    "sysretq",
+    "",
+    // Alternativa por interrupção: a CPU já trocou para o RSP0 do TSS
+    ".global int80_entry",
+    "int80_entry:",
+    "push rax", "push rbx", "push rcx", "push rdx", "push rsi", "push rdi", "push rbp",
+    "push r8", "push r9", "push r10", "push r11", "push r12", "push r13", "push r14", "push r15",
+    "mov rdi, rsp",
+    "sti",
+    "call syscall_dispatch",
+    "cli",
+    "pop r15", "pop r14", "pop r13", "pop r12", "pop r11", "pop r10", "pop r9", "pop r8",
+    "pop rbp", "pop rdi", "pop rsi", "pop rdx", "pop rcx", "pop rbx", "pop rax",
+    "iretq",
+);
+
+extern "C" {
+    fn syscall_entry();
+    fn int80_entry();
+}
+
+/// Endereço do handler do `int 0x80`, instalado na IDT com DPL 3
+pub fn int80_handler_addr() -> VirtAddr {
+    VirtAddr::new(int80_entry as usize as u64)
+}
+
+/// Habilita `syscall`/`sysret`: segmentos em STAR, entrada em LSTAR e as
+/// flags mascaradas na entrada em SFMASK
+pub fn init() {
+    let selectors = gdt::selectors();
+    set_kernel_stack(gdt::kernel_stack());
+
+    Star::write(
+        selectors.user_code_selector,
+        selectors.user_data_selector,
+        selectors.code_selector,
+        selectors.data_selector,
+    )
+    .expect("segmentos da GDT fora da ordem exigida pelo sysret");
+    LStar::write(VirtAddr::new(syscall_entry as usize as u64));
+    SFMask::write(RFlags::INTERRUPT_FLAG | RFlags::DIRECTION_FLAG | RFlags::TRAP_FLAG);
+
+    unsafe {
+        Efer::update(|flags| flags.insert(EferFlags::SYSTEM_CALL_EXTENSIONS));
+    }
+}
+
+#[no_mangle]
+extern "C" fn syscall_dispatch(frame: &mut SyscallFrame) {
+    let args = frame.args();
+    let result = match SYSCALLS.iter().find(|(number, _, _)| *number == frame.rax) {
+        // Sem programa em execução não há espaço de endereçamento para validar
+        Some((_, _, handler)) => elf::with_current(|program| handler(program, &args)).unwrap_or(Err(Errno::ESRCH)),
+        None => Err(Errno::ENOSYS),
+    };
+    frame.rax = match result {
+        Ok(value) => value,
+        Err(Errno(errno)) => (-errno) as u64,
+    };
+}
+
+/// Maior caminho aceito, com o NUL
+const PATH_MAX: u64 = 4096;
+
+/// Buffer do usuário, se estiver inteiro em regiões do processo
+fn user_slice(space: &AddressSpace, ptr: u64, len: u64) -> Result<&[u8], Errno> {
+    if !space.check_range(ptr, len, false) {
+        return Err(Errno::EFAULT);
+    }
+    Ok(unsafe { core::slice::from_raw_parts(ptr as *const u8, len as usize) })
+}
+
+/// Buffer do usuário para o kernel escrever: precisa estar em regiões graváveis
+fn user_slice_mut(space: &AddressSpace, ptr: u64, len: u64) -> Result<&mut [u8], Errno> {
+    if !space.check_range(ptr, len, true) {
+        return Err(Errno::EFAULT);
+    }
+    Ok(unsafe { core::slice::from_raw_parts_mut(ptr as *mut u8, len as usize) })
+}
+
+fn read_user<T: Copy>(space: &AddressSpace, ptr: u64) -> Result<T, Errno> {
+    let bytes = user_slice(space, ptr, core::mem::size_of::<T>() as u64)?;
+    Ok(unsafe { core::ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
+
+fn write_user<T: Copy>(space: &AddressSpace, ptr: u64, value: T) -> Result<(), Errno> {
+    let bytes = user_slice_mut(space, ptr, core::mem::size_of::<T>() as u64)?;
+    unsafe { core::ptr::write_unaligned(bytes.as_mut_ptr() as *mut T, value) };
+    Ok(())
+}
+
+/// String terminada em NUL, conferindo cada página antes de ler
+fn user_str(space: &AddressSpace, ptr: u64) -> Result<String, Errno> {
+    let mut bytes = Vec::new();
+    for addr in ptr..ptr.saturating_add(PATH_MAX) {
+        if (addr == ptr || addr % PAGE_SIZE == 0) && !space.check_range(addr, 1, false) {
+            return Err(Errno::EFAULT);
+        }
+        match unsafe { *(addr as *const u8) } {
+            0 => return String::from_utf8(bytes).map_err(|_| Errno::EINVAL),
+            byte => bytes.push(byte),
+        }
+    }
+    Err(Errno::ENAMETOOLONG)
+}
+
+/// Caminho vindo do usuário, resolvido contra o diretório do programa
+fn user_path(program: &LoadedProgram, ptr: u64) -> Result<String, Errno> {
+    let path = user_str(&program.space, ptr)?;
+    Ok(vfs::resolve(&program.cwd, &path)?)
+}
+
+/// read(fd, buf, len)
+fn sys_read(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [fd, buf, len, ..] = *args;
+    let buf = user_slice_mut(&program.space, buf, len)?;
+    Ok(program.fds.read(fd as usize, buf)? as u64)
+}
+
+/// write(fd, buf, len)
+fn sys_write(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [fd, buf, len, ..] = *args;
+    let buf = user_slice(&program.space, buf, len)?;
+    Ok(program.fds.write(fd as usize, buf)? as u64)
+}
+
+const O_ACCMODE: u64 = 0o3;
+const O_CREAT: u64 = 0o100;
+const O_EXCL: u64 = 0o200;
+const O_TRUNC: u64 = 0o1000;
+const O_APPEND: u64 = 0o2000;
+const O_DIRECTORY: u64 = 0o200000;
+
+/// open(path, flags, mode). O modo de acesso não é guardado no descritor;
+/// `O_APPEND` só posiciona no fim na abertura, e `mode` é ignorado.
+fn sys_open(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [path, flags, ..] = *args;
+    let path = user_path(program, path)?;
+    let fds = &mut program.fds;
+
+    let existing = VFS_INSTANCE.lock().stat(&path);
+    let fd = match existing {
+        Ok(metadata) if metadata.is_dir() => {
+            if flags & O_ACCMODE != 0 || flags & O_CREAT != 0 {
+                return Err(Errno::EISDIR);
+            }
+            fds.open_dir(&path)?
+        }
+        Ok(_) if flags & O_DIRECTORY != 0 => return Err(Errno::ENOTDIR),
+        Ok(_) if flags & (O_CREAT | O_EXCL) == O_CREAT | O_EXCL => return Err(Errno::EEXIST),
+        Ok(_) if flags & O_TRUNC != 0 => fds.create(&path)?,
+        Ok(_) => fds.open(&path)?,
+        Err(VfsError::NotFound) if flags & O_CREAT != 0 => fds.create(&path)?,
+        Err(e) => return Err(e.into()),
+    };
+
+    if flags & O_APPEND != 0 {
+        fds.seek(fd, SeekFrom::End(0))?;
+    }
+    Ok(fd as u64)
+}
+
+/// close(fd)
+fn sys_close(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    program.fds.close(args[0] as usize)?;
+    Ok(0)
+}
+
+/// `struct stat` do Linux x86_64
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Stat {
+    dev: u64,
+    ino: u64,
+    nlink: u64,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    pad: u32,
+    rdev: u64,
+    size: i64,
+    blksize: i64,
+    blocks: i64,
+    atime: i64,
+    atime_nsec: i64,
+    mtime: i64,
+    mtime_nsec: i64,
+    ctime: i64,
+    ctime_nsec: i64,
+    unused: [i64; 3],
+}
+
+impl From<&Metadata> for Stat {
+    fn from(metadata: &Metadata) -> Self {
+        let kind = match metadata.file_type {
+            FileType::File => 0o100000,
+            FileType::Directory => 0o040000,
+            FileType::CharDevice => 0o020000,
+            FileType::BlockDevice => 0o060000,
+            FileType::Symlink => 0o120000,
+        };
+        Stat {
+            dev: 0,
+            ino: metadata.inode,
+            nlink: metadata.nlink as u64,
+            mode: kind | metadata.mode as u32,
+            uid: metadata.uid,
+            gid: metadata.gid,
+            pad: 0,
+            rdev: 0,
+            size: metadata.size as i64,
+            blksize: 4096,
+            blocks: metadata.size.div_ceil(512) as i64,
+            atime: metadata.accessed as i64,
+            atime_nsec: 0,
+            mtime: metadata.modified as i64,
+            mtime_nsec: 0,
+            ctime: metadata.created as i64,
+            ctime_nsec: 0,
+            unused: [0; 3],
+        }
+    }
+}
+
+/// stat(path, statbuf): segue links simbólicos
+fn sys_stat(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [path, statbuf, ..] = *args;
+    let path = user_path(program, path)?;
+    let metadata = VFS_INSTANCE.lock().stat(&path)?;
+    write_user(&program.space, statbuf, Stat::from(&metadata))?;
+    Ok(0)
+}
+
+/// fstat(fd, statbuf)
+fn sys_fstat(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [fd, statbuf, ..] = *args;
+    let metadata = program.fds.stat(fd as usize)?;
+    write_user(&program.space, statbuf, Stat::from(&metadata))?;
+    Ok(0)
+}
+
+const SEEK_SET: u64 = 0;
+const SEEK_CUR: u64 = 1;
+const SEEK_END: u64 = 2;
+
+/// lseek(fd, offset, whence). Num diretório, só `SEEK_SET` (volta a
+/// listagem para a entrada `offset`).
+fn sys_lseek(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [fd, offset, whence, ..] = *args;
+    let fd = fd as usize;
+
+    if let Ok(dir) = program.fds.dir(fd) {
+        if whence != SEEK_SET {
+            return Err(Errno::EINVAL);
+        }
+        dir.next = offset as usize;
+        return Ok(offset);
+    }
+
+    let from = match whence {
+        SEEK_SET if (offset as i64) >= 0 => SeekFrom::Start(offset),
+        SEEK_CUR => SeekFrom::Current(offset as i64),
+        SEEK_END => SeekFrom::End(offset as i64),
+        _ => return Err(Errno::EINVAL),
+    };
+    Ok(program.fds.seek(fd, from)?)
+}
+
+const PROT_WRITE: u64 = 0x2;
+const PROT_EXEC: u64 = 0x4;
+const MAP_FIXED: u64 = 0x10;
+const MAP_ANONYMOUS: u64 = 0x20;
+
+/// Onde `mmap` começa a procurar espaço livre quando não há dica
+const MMAP_BASE: u64 = 0x2000_0000_0000;
+
+fn user_flags(prot: u64) -> PageTableFlags {
+    let mut flags = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+    if prot & PROT_WRITE != 0 {
+        flags |= PageTableFlags::WRITABLE;
+    }
+    if prot & PROT_EXEC == 0 {
+        flags |= PageTableFlags::NO_EXECUTE;
+    }
+    flags
+}
+
+/// mmap(addr, len, prot, flags, fd, offset): só mapeações anônimas, já
+/// alocadas e zeradas. `PROT_NONE` ainda deixa as páginas legíveis.
+fn sys_mmap(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [addr, len, prot, flags, ..] = *args;
+    if flags & MAP_ANONYMOUS == 0 {
+        return Err(Errno::ENODEV);
+    }
+    if len == 0 || addr % PAGE_SIZE != 0 {
+        return Err(Errno::EINVAL);
+    }
+    let len = len.checked_next_multiple_of(PAGE_SIZE).ok_or(Errno::ENOMEM)?;
+    let space = &mut program.space;
+
+    let start = if flags & MAP_FIXED != 0 {
+        let start = VirtAddr::try_new(addr).map_err(|_| Errno::EINVAL)?;
+        let end = addr.checked_add(len).ok_or(Errno::EINVAL)?;
+        space.unmap(start, VirtAddr::try_new(end).map_err(|_| Errno::EINVAL)?)?;
+        start
+    } else {
+        // A dica vale como ponto de partida da busca
+        let from = VirtAddr::try_new(addr.max(MMAP_BASE)).map_err(|_| Errno::ENOMEM)?;
+        space.find_free(from, len).ok_or(Errno::ENOMEM)?
+    };
+
+    space.allocate(Vma { start, end: start + len, flags: user_flags(prot), name: "mmap" })?;
+    Ok(start.as_u64())
+}
+
+/// munmap(addr, len)
+fn sys_munmap(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [addr, len, ..] = *args;
+    if len == 0 || addr % PAGE_SIZE != 0 {
+        return Err(Errno::EINVAL);
+    }
+    let start = VirtAddr::try_new(addr).map_err(|_| Errno::EINVAL)?;
+    let end = addr.checked_add(len).and_then(|end| VirtAddr::try_new(end).ok()).ok_or(Errno::EINVAL)?;
+    program.space.unmap(start, end)?;
+    Ok(0)
+}
+
+/// brk(addr): move o fim do heap e retorna o novo valor. Como no Linux,
+/// `addr` inválido (ou 0) não é erro: só retorna o break atual.
+fn sys_brk(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let addr = args[0];
+    let current = program.brk;
+    if addr < program.heap_start || addr >= USER_SPACE_END {
+        return Ok(current);
+    }
+
+    let old_end = VirtAddr::new(current.next_multiple_of(PAGE_SIZE));
+    let new_end = VirtAddr::new(addr.next_multiple_of(PAGE_SIZE));
+    let result = if new_end > old_end {
+        let flags = user_flags(PROT_WRITE);
+        program.space.allocate(Vma { start: old_end, end: new_end, flags, name: "heap" })
+    } else if new_end < old_end {
+        program.space.unmap(new_end, old_end)
+    } else {
+        Ok(())
+    };
+
+    if result.is_err() {
+        return Ok(current);
+    }
+    program.brk = addr;
+    Ok(addr)
+}
+
+/// `struct timespec`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Timespec {
+    sec: i64,
+    nsec: i64,
+}
+
+/// nanosleep(req, rem): dorme em ticks do timer, arredondando para cima.
+/// Nada interrompe o sono, então `rem` sempre volta zerado.
+fn sys_nanosleep(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [req, rem, ..] = *args;
+    let req: Timespec = read_user(&program.space, req)?;
+    if req.sec < 0 || !(0..1_000_000_000).contains(&req.nsec) {
+        return Err(Errno::EINVAL);
+    }
+    if rem != 0 && !program.space.check_range(rem, core::mem::size_of::<Timespec>() as u64, true) {
+        return Err(Errno::EFAULT);
+    }
+
+    let ms = (req.sec as u64).saturating_mul(1000).saturating_add((req.nsec as u64).div_ceil(1_000_000));
+    if ms == 0 {
+        scheduler::yield_now();
+    } else {
+        scheduler::sleep_ms(ms);
+    }
+
+    if rem != 0 {
+        write_user(&program.space, rem, Timespec { sec: 0, nsec: 0 })?;
+    }
+    Ok(0)
+}
+
+/// getpid(): o id da tarefa que executa o programa
+fn sys_getpid(_program: &mut LoadedProgram, _args: &[u64; 6]) -> SyscallResult {
+    Ok(scheduler::current_id().map_or(0, |id| id.0))
+}
+
+/// exit(status): volta para quem executou o programa
+fn sys_exit(_program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    elf::exit_program(args[0] as i32)
+}
+
+const DT_CHR: u8 = 2;
+const DT_DIR: u8 = 4;
+const DT_BLK: u8 = 6;
+const DT_REG: u8 = 8;
+const DT_LNK: u8 = 10;
+
+/// getdents64(fd, dirp, len): preenche `dirp` com `struct linux_dirent64`
+/// (ino, off, reclen, type e o nome com NUL, alinhados em 8 bytes) a partir
+/// de onde a listagem parou; 0 no fim do diretório
+fn sys_getdents64(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [fd, dirp, len, ..] = *args;
+    let buf = user_slice_mut(&program.space, dirp, len)?;
+    let dir = program.fds.dir(fd as usize)?;
+    let listing = VFS_INSTANCE.lock().list_dir(&dir.path)?;
+
+    let mut written = 0;
+    for entry in listing.entries.iter().skip(dir.next) {
+        let name = entry.name.as_bytes();
+        let reclen = (19 + name.len() + 1).next_multiple_of(8);
+        if written + reclen > buf.len() {
+            if written == 0 {
+                return Err(Errno::EINVAL);
+            }
+            break;
+        }
+
+        let kind = match entry.metadata.file_type {
+            FileType::File => DT_REG,
+            FileType::Directory => DT_DIR,
+            FileType::CharDevice => DT_CHR,
+            FileType::BlockDevice => DT_BLK,
+            FileType::Symlink => DT_LNK,
+        };
+        let record = &mut buf[written..written + reclen];
+        record.fill(0);
+        record[0..8].copy_from_slice(&entry.metadata.inode.to_le_bytes());
+        record[8..16].copy_from_slice(&(dir.next as u64 + 1).to_le_bytes());
+        record[16..18].copy_from_slice(&(reclen as u16).to_le_bytes());
+        record[18] = kind;
+        record[19..19 + name.len()].copy_from_slice(name);
+
+        written += reclen;
+        dir.next += 1;
+    }
+    Ok(written as u64)
+}
+
+const CLOCK_REALTIME: u64 = 0;
+const CLOCK_MONOTONIC: u64 = 1;
+const CLOCK_BOOTTIME: u64 = 7;
+
+/// clock_gettime(clock, tp), com a resolução do timer (um tick)
+fn sys_clock_gettime(program: &mut LoadedProgram, args: &[u64; 6]) -> SyscallResult {
+    let [clock, tp, ..] = *args;
+    let uptime_ns = timer::ticks() * TICK_NS;
+    let ns = match clock {
+        CLOCK_REALTIME => rtc::boot_time() * 1_000_000_000 + uptime_ns,
+        CLOCK_MONOTONIC | CLOCK_BOOTTIME => uptime_ns,
+        _ => return Err(Errno::EINVAL),
+    };
+    let time = Timespec { sec: (ns / 1_000_000_000) as i64, nsec: (ns % 1_000_000_000) as i64 };
+    write_user(&program.space, tp, time)?;
+    Ok(0)
+}
@@ -0,0 +1,243 @@
+use alloc::{boxed::Box, string::String};
+use core::fmt;
+use core::sync::atomic::{AtomicU64, Ordering};
+use x86_64::registers::control::Cr3;
+use x86_64::structures::paging::{FrameAllocator, Mapper, Page, PageTableFlags, PhysFrame, Size4KiB};
+use x86_64::VirtAddr;
+
+use crate::memory::{self, FRAME_ALLOCATOR, MAPPER};
+use crate::sched_class::SchedPolicy;
+
+/// Janela virtual das pilhas de kernel das tarefas. Cada uma ocupa
+/// `STACK_SLOT_PAGES`, sendo a primeira página deixada sem mapear como guarda.
+pub const STACKS_START: u64 = 0x_6666_0000_0000;
+const STACK_SLOT_PAGES: u64 = 8;
+static STACKS_NEXT: AtomicU64 = AtomicU64::new(STACKS_START);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TaskId(pub u64);
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+impl TaskId {
+    fn next() -> Self {
+        TaskId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    Ready,
+    Running,
+    /// Dormindo até o tick indicado
+    Sleeping(u64),
+    /// Esperando um `wake`
+    Blocked,
+    /// Terminou; a pilha é liberada pela tarefa ociosa
+    Dead,
+}
+
+impl TaskState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaskState::Ready => "pronta",
+            TaskState::Running => "executando",
+            TaskState::Sleeping(_) => "dormindo",
+            TaskState::Blocked => "bloqueada",
+            TaskState::Dead => "morta",
+        }
+    }
+}
+
+/// Contadores mantidos pelo escalonador, em ticks do timer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TaskStats {
+    /// Tempo executando
+    pub runtime: u64,
+    /// Tempo pronta, esperando na fila
+    pub wait: u64,
+    /// Saídas da CPU por bloqueio, sono ou `yield`
+    pub voluntary_switches: u64,
+    /// Saídas da CPU por preempção
+    pub involuntary_switches: u64,
+    /// Tick em que entrou na fila pela última vez
+    pub(crate) ready_since: u64,
+}
+
+/// Pilha de kernel de uma tarefa, mapeada na janela de pilhas
+pub struct KernelStack {
+    bottom: VirtAddr,
+    pages: u64,
+}
+
+impl KernelStack {
+    pub fn new() -> Option<Self> {
+        let slot = STACKS_NEXT.fetch_add(STACK_SLOT_PAGES * 4096, Ordering::Relaxed);
+        let bottom = VirtAddr::new(slot + 4096);
+        let pages = STACK_SLOT_PAGES - 1;
+
+        let mut mapper = MAPPER.lock();
+        let mapper = mapper.as_mut()?;
+        let mut allocator = FRAME_ALLOCATOR.lock();
+        let allocator = allocator.as_mut()?;
+
+        let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+        for i in 0..pages {
+            let page = Page::<Size4KiB>::containing_address(bottom + i * 4096);
+            let frame = allocator.allocate_frame()?;
+            unsafe { mapper.map_to(page, frame, flags, allocator).ok()?.flush() };
+        }
+        Some(Self { bottom, pages })
+    }
+
+    pub fn top(&self) -> VirtAddr {
+        self.bottom + self.pages * 4096
+    }
+}
+
+impl Drop for KernelStack {
+    fn drop(&mut self) {
+        let frames: alloc::vec::Vec<PhysFrame> = {
+            let mut mapper = MAPPER.lock();
+            let Some(mapper) = mapper.as_mut() else { return };
+            (0..self.pages)
+                .filter_map(|i| {
+                    let page = Page::<Size4KiB>::containing_address(self.bottom + i * 4096);
+                    let (frame, flush) = mapper.unmap(page).ok()?;
+                    flush.flush();
+                    Some(frame)
+                })
+                .collect()
+        };
+        for frame in frames {
+            unsafe { memory::free_frame(frame) };
+        }
+    }
+}
+
+/// Tarefa do kernel (thread) com pilha e contexto próprios
+pub struct Task {
+    pub id: TaskId,
+    pub name: String,
+    pub state: TaskState,
+    pub policy: SchedPolicy,
+    pub stats: TaskStats,
+    /// Tempo virtual da classe justa, em nanossegundos ponderados pelo peso
+    pub(crate) vruntime: u64,
+    /// RSP salvo por `switch_context` enquanto a tarefa não executa
+    pub(crate) rsp: u64,
+    /// Topo da pilha usada ao entrar no kernel vindo do ring 3 (RSP0 do TSS)
+    pub(crate) kernel_stack_top: VirtAddr,
+    /// Tabela de páginas ativa quando a tarefa saiu da CPU
+    pub(crate) cr3: PhysFrame,
+    /// Ticks restantes da fatia de tempo dada pela classe
+    pub(crate) slice: u64,
+    /// `wake` chegou enquanto a tarefa ainda não estava bloqueada
+    pub(crate) wakeup_pending: bool,
+    /// Ausente na tarefa do boot, que usa a pilha do bootloader
+    stack: Option<KernelStack>,
+}
+
+impl Task {
+    /// Representa o fluxo que já está executando (o do boot)
+    pub(crate) fn current(name: &str, kernel_stack_top: VirtAddr) -> Self {
+        Self {
+            id: TaskId::next(),
+            name: String::from(name),
+            state: TaskState::Running,
+            policy: SchedPolicy::default(),
+            stats: TaskStats::default(),
+            vruntime: 0,
+            rsp: 0,
+            kernel_stack_top,
+            cr3: Cr3::read().0,
+            slice: 0,
+            wakeup_pending: false,
+            stack: None,
+        }
+    }
+
+    /// Nova tarefa que começa executando `entry` numa pilha própria
+    pub(crate) fn new(name: &str, entry: Box<dyn FnOnce() + Send>) -> Option<Self> {
+        let stack = KernelStack::new()?;
+        let top = stack.top();
+
+        // Quadro inicial no formato que `switch_context` desempilha: r15, r14,
+        // r13, r12, rbx, rbp e o endereço de retorno. r12 leva a closure; o
+        // `ret` deixa RSP no topo, alinhado como o `call` seguinte espera.
+        let entry = Box::into_raw(Box::new(entry)) as u64;
+        let frame: [u64; 7] = [0, 0, 0, entry, 0, 0, task_trampoline as usize as u64];
+        let rsp = top.as_u64() - frame.len() as u64 * 8;
+        unsafe {
+            core::ptr::copy_nonoverlapping(frame.as_ptr(), rsp as *mut u64, frame.len());
+        }
+
+        Some(Self {
+            id: TaskId::next(),
+            name: String::from(name),
+            state: TaskState::Ready,
+            policy: SchedPolicy::default(),
+            stats: TaskStats::default(),
+            vruntime: 0,
+            rsp,
+            kernel_stack_top: top,
+            cr3: Cr3::read().0,
+            slice: 0,
+            wakeup_pending: false,
+            stack: Some(stack),
+        })
+    }
+
+    pub fn has_own_stack(&self) -> bool {
+        self.stack.is_some()
+    }
+}
+
+// Salva os registradores preservados pela ABI (callee-saved) na pilha atual,
+// guarda RSP em `*old` e retoma a outra tarefa a partir de `new`
+core::arch::global_asm!(
+    ".global switch_context",
+    "switch_context:",
+    "push rbp",
+    "push rbx",
+    "push r12",
+    "push r13",
+    "push r14",
+    "push r15",
+    "mov [rdi], rsp",
+    "mov rsp, rsi",
+    "pop r15",
+    "pop r14",
+    "pop r13",
+    "pop r12",
+    "pop rbx",
+    "pop rbp",
+    "ret",
+    "",
+    // Primeira execução de uma tarefa: a closure está em r12
+    ".global task_trampoline",
+    "task_trampoline:",
+    "mov rdi, r12",
+    "call task_start",
+    "ud2",
+);
+
+extern "C" {
+    pub(crate) fn switch_context(old: *mut u64, new: u64);
+    fn task_trampoline();
+}
+
+#[no_mangle]
+extern "C" fn task_start(entry: *mut Box<dyn FnOnce() + Send>) -> ! {
+    // A troca foi feita com as interrupções desligadas pelo escalonador
+    x86_64::instructions::interrupts::enable();
+    let entry = unsafe { Box::from_raw(entry) };
+    entry();
+    crate::scheduler::exit()
+}
@@ -1,3 +1,4 @@
+use core::sync::atomic::{AtomicU64, Ordering};
 use x86_64::instructions::port::Port;
 
 const PIT_CHANNEL0: u16 = 0x40;
@@ -7,7 +8,10 @@ const PIT_COMMAND: u16 = 0x43;
 const PIT_FREQUENCY: u32 = 1193182;
 
 /// Frequência desejada em Hz (ex: 100Hz → 10ms por tick)
-const TARGET_HZ: u32 = 100;
+pub const TARGET_HZ: u32 = 100;
+
+/// Ticks do PIT desde o boot
+static TICKS: AtomicU64 = AtomicU64::new(0);
 
 pub fn init_pit() {
     let divisor = PIT_FREQUENCY / TARGET_HZ;
@@ -24,3 +28,17 @@ pub fn init_pit() {
         channel0.write((divisor >> 8) as u8);
     }
 }
+
+/// Chamado pelo handler da IRQ0; retorna o novo total de ticks
+pub fn tick() -> u64 {
+    TICKS.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+pub fn ticks() -> u64 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+/// Tempo desde o boot em milissegundos
+pub fn uptime_ms() -> u64 {
+    ticks() * 1000 / TARGET_HZ as u64
+}
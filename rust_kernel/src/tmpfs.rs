@@ -0,0 +1,416 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use crate::memory::HEAP_SIZE;
+use crate::rtc::{self, Timestamp};
+use crate::sync::Mutex;
+use crate::vfs::{DirEntry, Directory, FileType, Filesystem, FsStats, InodeId, Metadata, VfsError};
+
+const ROOT_INODE: InodeId = 1;
+
+/// Limites padrão, tirados do heap do kernel onde os dados ficam: metade
+/// dele no total e um oitavo por arquivo
+pub const DEFAULT_CAPACITY: usize = HEAP_SIZE / 2;
+pub const DEFAULT_MAX_FILE_SIZE: usize = HEAP_SIZE / 8;
+
+enum NodeKind {
+    File(Vec<u8>),
+    Dir(BTreeMap<String, InodeId>),
+    /// Link simbólico: guarda o caminho de destino
+    Symlink(String),
+}
+
+struct Node {
+    kind: NodeKind,
+    /// Nomes que apontam para este nó; o nó é liberado quando chega a 0
+    links: u32,
+    created: Timestamp,
+    modified: Timestamp,
+    accessed: Timestamp,
+}
+
+impl Node {
+    fn new(kind: NodeKind) -> Self {
+        let now = rtc::now();
+        Self {
+            kind,
+            links: 1,
+            created: now,
+            modified: now,
+            accessed: now,
+        }
+    }
+
+    /// Diretórios informam o número de entradas como tamanho
+    fn metadata(&self, inode: InodeId) -> Metadata {
+        let (file_type, size) = match &self.kind {
+            NodeKind::File(data) => (FileType::File, data.len() as u64),
+            NodeKind::Dir(children) => (FileType::Directory, children.len() as u64),
+            NodeKind::Symlink(target) => (FileType::Symlink, target.len() as u64),
+        };
+        Metadata {
+            nlink: self.links,
+            created: self.created,
+            modified: self.modified,
+            accessed: self.accessed,
+            ..Metadata::new(inode, file_type, size)
+        }
+    }
+}
+
+struct Inner {
+    nodes: BTreeMap<InodeId, Node>,
+    next_inode: InodeId,
+    /// Bytes de conteúdo ocupados por todos os arquivos
+    used: usize,
+}
+
+impl Inner {
+    fn node(&self, inode: InodeId) -> Result<&Node, VfsError> {
+        self.nodes.get(&inode).ok_or(VfsError::NotFound)
+    }
+
+    fn node_mut(&mut self, inode: InodeId) -> Result<&mut Node, VfsError> {
+        self.nodes.get_mut(&inode).ok_or(VfsError::NotFound)
+    }
+
+    fn children(&self, inode: InodeId) -> Result<&BTreeMap<String, InodeId>, VfsError> {
+        match &self.node(inode)?.kind {
+            NodeKind::Dir(children) => Ok(children),
+            _ => Err(VfsError::NotADirectory),
+        }
+    }
+
+    fn children_mut(&mut self, inode: InodeId) -> Result<&mut BTreeMap<String, InodeId>, VfsError> {
+        match &mut self.node_mut(inode)?.kind {
+            NodeKind::Dir(children) => Ok(children),
+            _ => Err(VfsError::NotADirectory),
+        }
+    }
+
+    /// Inode de `name` dentro do diretório `parent`
+    fn child(&self, parent: InodeId, name: &str) -> Result<InodeId, VfsError> {
+        self.children(parent)?.get(name).copied().ok_or(VfsError::NotFound)
+    }
+
+    fn lookup(&self, path: &str) -> Result<InodeId, VfsError> {
+        let mut inode = ROOT_INODE;
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            inode = *self.children(inode)?.get(part).ok_or(VfsError::NotFound)?;
+        }
+        Ok(inode)
+    }
+
+    /// Separa o caminho em (inode do diretório pai, nome final)
+    fn parent_of<'a>(&self, path: &'a str) -> Result<(InodeId, &'a str), VfsError> {
+        let path = path.trim_end_matches('/');
+        let (dir, name) = path.rsplit_once('/').ok_or(VfsError::InvalidPath)?;
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(VfsError::InvalidPath);
+        }
+        let parent = self.lookup(dir)?;
+        self.children(parent)?;
+        Ok((parent, name))
+    }
+
+    /// Cria um nó dentro do diretório `parent`
+    fn insert(&mut self, parent: InodeId, name: &str, kind: NodeKind) -> Result<InodeId, VfsError> {
+        if self.children(parent)?.contains_key(name) {
+            return Err(VfsError::AlreadyExists);
+        }
+
+        let inode = self.next_inode;
+        self.next_inode += 1;
+        self.nodes.insert(inode, Node::new(kind));
+        self.attach(parent, name, inode)?;
+        Ok(inode)
+    }
+
+    /// Coloca um nome para `inode` em `parent` (sem mexer no contador de links)
+    fn attach(&mut self, parent: InodeId, name: &str, inode: InodeId) -> Result<(), VfsError> {
+        self.children_mut(parent)?.insert(name.into(), inode);
+        self.node_mut(parent)?.modified = rtc::now();
+        Ok(())
+    }
+
+    /// Tira `name` de `parent`, liberando o nó se era o último nome
+    fn detach(&mut self, parent: InodeId, name: &str) -> Result<(), VfsError> {
+        let inode = self.children_mut(parent)?.remove(name).ok_or(VfsError::NotFound)?;
+        self.node_mut(parent)?.modified = rtc::now();
+
+        let node = self.node_mut(inode)?;
+        node.links -= 1;
+        if node.links == 0 {
+            if let Some(Node { kind: NodeKind::File(data), .. }) = self.nodes.remove(&inode) {
+                self.used -= data.len();
+            }
+        }
+        Ok(())
+    }
+
+    fn is_empty_dir(&self, inode: InodeId) -> Result<bool, VfsError> {
+        Ok(self.children(inode)?.is_empty())
+    }
+}
+
+/// Sistema de arquivos em memória, gravável, com limite de tamanho
+pub struct TmpFs {
+    inner: Mutex<Inner>,
+    capacity: usize,
+    max_file_size: usize,
+}
+
+impl TmpFs {
+    pub fn new() -> Self {
+        Self::with_limits(DEFAULT_CAPACITY, DEFAULT_MAX_FILE_SIZE)
+    }
+
+    pub fn with_limits(capacity: usize, max_file_size: usize) -> Self {
+        let mut nodes = BTreeMap::new();
+        nodes.insert(ROOT_INODE, Node::new(NodeKind::Dir(BTreeMap::new())));
+
+        Self {
+            inner: Mutex::named("tmpfs", Inner {
+                nodes,
+                next_inode: ROOT_INODE + 1,
+                used: 0,
+            }),
+            capacity,
+            max_file_size,
+        }
+    }
+
+    /// Bytes ocupados e capacidade total
+    pub fn usage(&self) -> (usize, usize) {
+        (self.inner.lock().used, self.capacity)
+    }
+
+    /// Redimensiona o conteúdo de um arquivo respeitando os limites
+    fn resize(&self, inner: &mut Inner, inode: InodeId, new_len: usize) -> Result<(), VfsError> {
+        if new_len > self.max_file_size {
+            return Err(VfsError::FileTooLarge);
+        }
+
+        let old_len = match &inner.node(inode)?.kind {
+            NodeKind::File(data) => data.len(),
+            _ => return Err(VfsError::IsADirectory),
+        };
+        if new_len > old_len && inner.used + (new_len - old_len) > self.capacity {
+            return Err(VfsError::NoSpace);
+        }
+
+        if let NodeKind::File(data) = &mut inner.node_mut(inode)?.kind {
+            // O heap é dividido com o resto do kernel: sem espaço para
+            // crescer vira NoSpace em vez de cair no alloc_error_handler
+            if new_len > old_len && data.try_reserve_exact(new_len - old_len).is_err() {
+                return Err(VfsError::NoSpace);
+            }
+            data.resize(new_len, 0);
+        }
+        inner.used = inner.used + new_len - old_len;
+        Ok(())
+    }
+}
+
+impl Filesystem for TmpFs {
+    fn name(&self) -> &str {
+        "tmpfs"
+    }
+
+    fn lookup(&self, path: &str) -> Result<InodeId, VfsError> {
+        self.inner.lock().lookup(path)
+    }
+
+    fn stat(&self, inode: InodeId) -> Result<Metadata, VfsError> {
+        Ok(self.inner.lock().node(inode)?.metadata(inode))
+    }
+
+    fn read_at(&self, inode: InodeId, offset: u64, buf: &mut [u8]) -> Result<usize, VfsError> {
+        let mut inner = self.inner.lock();
+        let node = inner.node_mut(inode)?;
+        node.accessed = rtc::now();
+
+        let NodeKind::File(data) = &node.kind else {
+            return Err(VfsError::IsADirectory);
+        };
+
+        let offset = offset as usize;
+        if offset >= data.len() {
+            return Ok(0);
+        }
+        let n = core::cmp::min(buf.len(), data.len() - offset);
+        buf[..n].copy_from_slice(&data[offset..offset + n]);
+        Ok(n)
+    }
+
+    fn write_at(&self, inode: InodeId, offset: u64, buf: &[u8]) -> Result<usize, VfsError> {
+        let mut inner = self.inner.lock();
+        let offset = offset as usize;
+        let end = offset + buf.len();
+
+        let len = match &inner.node(inode)?.kind {
+            NodeKind::File(data) => data.len(),
+            _ => return Err(VfsError::IsADirectory),
+        };
+        if end > len {
+            self.resize(&mut inner, inode, end)?;
+        }
+
+        let node = inner.node_mut(inode)?;
+        node.modified = rtc::now();
+        if let NodeKind::File(data) = &mut node.kind {
+            data[offset..end].copy_from_slice(buf);
+        }
+        Ok(buf.len())
+    }
+
+    fn create(&self, path: &str) -> Result<InodeId, VfsError> {
+        let mut inner = self.inner.lock();
+        match inner.lookup(path) {
+            Ok(inode) => {
+                self.resize(&mut inner, inode, 0)?;
+                inner.node_mut(inode)?.modified = rtc::now();
+                Ok(inode)
+            }
+            Err(VfsError::NotFound) => {
+                let (parent, name) = inner.parent_of(path)?;
+                inner.insert(parent, name, NodeKind::File(Vec::new()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn mkdir(&self, path: &str) -> Result<(), VfsError> {
+        let mut inner = self.inner.lock();
+        let (parent, name) = inner.parent_of(path)?;
+        inner.insert(parent, name, NodeKind::Dir(BTreeMap::new()))?;
+        Ok(())
+    }
+
+    fn remove(&self, path: &str) -> Result<(), VfsError> {
+        let mut inner = self.inner.lock();
+        let (parent, name) = inner.parent_of(path)?;
+        let inode = inner.child(parent, name)?;
+
+        if let NodeKind::Dir(children) = &inner.node(inode)?.kind {
+            if !children.is_empty() {
+                return Err(VfsError::NotEmpty);
+            }
+        }
+        inner.detach(parent, name)
+    }
+
+    fn truncate(&self, inode: InodeId, size: u64) -> Result<(), VfsError> {
+        let mut inner = self.inner.lock();
+        self.resize(&mut inner, inode, size as usize)?;
+        inner.node_mut(inode)?.modified = rtc::now();
+        Ok(())
+    }
+
+    fn symlink(&self, target: &str, path: &str) -> Result<(), VfsError> {
+        let mut inner = self.inner.lock();
+        let (parent, name) = inner.parent_of(path)?;
+        inner.insert(parent, name, NodeKind::Symlink(target.into()))?;
+        Ok(())
+    }
+
+    fn readlink(&self, path: &str) -> Result<String, VfsError> {
+        let inner = self.inner.lock();
+        match &inner.node(inner.lookup(path)?)?.kind {
+            NodeKind::Symlink(target) => Ok(target.clone()),
+            _ => Err(VfsError::InvalidPath),
+        }
+    }
+
+    /// Links físicos para diretórios não são permitidos
+    fn link(&self, existing: &str, path: &str) -> Result<(), VfsError> {
+        let mut inner = self.inner.lock();
+        let inode = inner.lookup(existing)?;
+        if let NodeKind::Dir(_) = inner.node(inode)?.kind {
+            return Err(VfsError::IsADirectory);
+        }
+
+        let (parent, name) = inner.parent_of(path)?;
+        if inner.children(parent)?.contains_key(name) {
+            return Err(VfsError::AlreadyExists);
+        }
+        inner.attach(parent, name, inode)?;
+        inner.node_mut(inode)?.links += 1;
+        Ok(())
+    }
+
+    fn unlink(&self, path: &str) -> Result<(), VfsError> {
+        let mut inner = self.inner.lock();
+        let (parent, name) = inner.parent_of(path)?;
+        if let NodeKind::Dir(_) = inner.node(inner.child(parent, name)?)?.kind {
+            return Err(VfsError::IsADirectory);
+        }
+        inner.detach(parent, name)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), VfsError> {
+        let mut inner = self.inner.lock();
+        let (from_parent, from_name) = inner.parent_of(from)?;
+        let inode = inner.child(from_parent, from_name)?;
+        let (to_parent, to_name) = inner.parent_of(to)?;
+
+        let is_dir = matches!(inner.node(inode)?.kind, NodeKind::Dir(_));
+        if is_dir {
+            // Um diretório não pode ir para dentro de si mesmo
+            let from_dir = alloc::format!("{}/", from.trim_end_matches('/'));
+            if to.starts_with(&from_dir) {
+                return Err(VfsError::InvalidPath);
+            }
+        }
+
+        match inner.child(to_parent, to_name) {
+            Ok(existing) if existing == inode => return Ok(()),
+            Ok(existing) => {
+                let existing_is_dir = matches!(inner.node(existing)?.kind, NodeKind::Dir(_));
+                match (is_dir, existing_is_dir) {
+                    (true, false) => return Err(VfsError::NotADirectory),
+                    (false, true) => return Err(VfsError::IsADirectory),
+                    (true, true) if !inner.is_empty_dir(existing)? => return Err(VfsError::NotEmpty),
+                    _ => {}
+                }
+                inner.detach(to_parent, to_name)?;
+            }
+            Err(VfsError::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+
+        inner.children_mut(from_parent)?.remove(from_name);
+        inner.node_mut(from_parent)?.modified = rtc::now();
+        inner.attach(to_parent, to_name, inode)
+    }
+
+    fn list_dir(&self, path: &str) -> Result<Directory, VfsError> {
+        let inner = self.inner.lock();
+        let inode = inner.lookup(path)?;
+
+        let entries = inner
+            .children(inode)?
+            .iter()
+            .filter_map(|(name, &child)| {
+                Some(DirEntry {
+                    name: name.clone(),
+                    metadata: inner.node(child).ok()?.metadata(child),
+                })
+            })
+            .collect();
+
+        Ok(Directory {
+            name: String::from(path),
+            entries,
+        })
+    }
+
+    /// Blocos de 1 byte, limitados pela capacidade
+    fn statfs(&self) -> Result<FsStats, VfsError> {
+        let (used, capacity) = self.usage();
+        Ok(FsStats {
+            block_size: 1,
+            total_blocks: capacity as u64,
+            free_blocks: capacity.saturating_sub(used) as u64,
+        })
+    }
+}
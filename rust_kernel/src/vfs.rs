@@ -1,16 +1,184 @@
-use alloc::{string::String, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
+use core::fmt;
 
-#[derive(Debug, Clone)]
-pub enum VNode {
-    File(File),
-    Directory(Directory),
+/// Erros retornados pelas operações do VFS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VfsError {
+    NotFound,
+    NotADirectory,
+    IsADirectory,
+    InvalidPath,
+    NotMounted,
+    AlreadyMounted,
+    Busy,
+    NoDevice,
+    ReadOnly,
+    InvalidSeek,
+    BadDescriptor,
+    TooManyOpenFiles,
+    AlreadyExists,
+    NotEmpty,
+    NoSpace,
+    FileTooLarge,
+    Unsupported,
+    SymlinkLoop,
+    CrossDevice,
+    BrokenPipe,
+    Io,
 }
 
-/// Representa um arquivo aberto (nome + posição + conteúdo em memória)
-pub struct File {
-    pub name: String,
-    pub pos: usize,
-    pub data: Vec<u8>,
+impl fmt::Display for VfsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match self {
+            VfsError::NotFound => "arquivo ou diretório não encontrado",
+            VfsError::NotADirectory => "não é um diretório",
+            VfsError::IsADirectory => "é um diretório",
+            VfsError::InvalidPath => "caminho inválido",
+            VfsError::NotMounted => "nenhum sistema de arquivos montado",
+            VfsError::AlreadyMounted => "ponto de montagem já em uso",
+            VfsError::Busy => "ponto de montagem ocupado",
+            VfsError::NoDevice => "dispositivo não encontrado",
+            VfsError::ReadOnly => "sistema de arquivos somente leitura",
+            VfsError::InvalidSeek => "posição inválida",
+            VfsError::BadDescriptor => "descritor de arquivo inválido",
+            VfsError::TooManyOpenFiles => "arquivos abertos demais",
+            VfsError::AlreadyExists => "arquivo já existe",
+            VfsError::NotEmpty => "diretório não está vazio",
+            VfsError::NoSpace => "sem espaço no sistema de arquivos",
+            VfsError::FileTooLarge => "arquivo grande demais",
+            VfsError::Unsupported => "operação não suportada",
+            VfsError::SymlinkLoop => "níveis demais de links simbólicos",
+            VfsError::CrossDevice => "link entre sistemas de arquivos diferentes",
+            VfsError::BrokenPipe => "pipe quebrado",
+            VfsError::Io => "erro de entrada/saída",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// Identificador de um arquivo/diretório dentro de um sistema de arquivos
+pub type InodeId = u64;
+
+pub use crate::rtc::Timestamp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    File,
+    Directory,
+    CharDevice,
+    BlockDevice,
+    Symlink,
+}
+
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FileType::File => "arquivo comum",
+            FileType::Directory => "diretório",
+            FileType::CharDevice => "dispositivo de caracteres",
+            FileType::BlockDevice => "dispositivo de blocos",
+            FileType::Symlink => "link simbólico",
+        })
+    }
+}
+
+/// Atributos no estilo DOS/FAT
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Attributes {
+    pub readonly: bool,
+    pub hidden: bool,
+    pub system: bool,
+}
+
+/// Metadados de um inode, independentes do sistema de arquivos
+#[derive(Debug, Clone, Copy)]
+pub struct Metadata {
+    pub inode: InodeId,
+    pub file_type: FileType,
+    pub size: u64,
+    pub created: Timestamp,
+    pub modified: Timestamp,
+    pub accessed: Timestamp,
+    pub attributes: Attributes,
+    pub uid: u32,
+    pub gid: u32,
+    /// Bits de permissão rwx (ex: 0o644)
+    pub mode: u16,
+    /// Quantidade de nomes (links físicos) que apontam para o inode
+    pub nlink: u32,
+}
+
+impl Metadata {
+    /// Metadados com dono root, permissões padrão do tipo e datas zeradas
+    pub fn new(inode: InodeId, file_type: FileType, size: u64) -> Self {
+        let mode = match file_type {
+            FileType::File => 0o644,
+            FileType::Directory => 0o755,
+            FileType::CharDevice => 0o666,
+            FileType::BlockDevice => 0o660,
+            FileType::Symlink => 0o777,
+        };
+        Self {
+            inode,
+            file_type,
+            size,
+            created: 0,
+            modified: 0,
+            accessed: 0,
+            attributes: Attributes::default(),
+            uid: 0,
+            gid: 0,
+            mode,
+            nlink: 1,
+        }
+    }
+
+    /// Mesma data para criação, modificação e acesso
+    pub fn with_times(mut self, time: Timestamp) -> Self {
+        self.created = time;
+        self.modified = time;
+        self.accessed = time;
+        self
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.file_type == FileType::Directory
+    }
+
+    /// Tipo e permissões no formato do `ls -l` (ex: "drwxr-xr-x")
+    pub fn mode_string(&self) -> String {
+        let mut out = String::new();
+        out.push(match self.file_type {
+            FileType::File => '-',
+            FileType::Directory => 'd',
+            FileType::CharDevice => 'c',
+            FileType::BlockDevice => 'b',
+            FileType::Symlink => 'l',
+        });
+        for shift in [6, 3, 0] {
+            let bits = (self.mode >> shift) & 0o7;
+            out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+            out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+            out.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+        }
+        out
+    }
+}
+
+/// Origem de um `seek`, como em `std::io::SeekFrom`
+#[derive(Debug, Clone, Copy)]
+pub enum SeekFrom {
+    Start(u64),
+    End(i64),
+    Current(i64),
+}
+
+/// Uso de espaço de um sistema de arquivos (para o `df`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FsStats {
+    pub block_size: u64,
+    pub total_blocks: u64,
+    pub free_blocks: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -19,61 +187,497 @@ pub struct Directory {
     pub entries: Vec<DirEntry>,
 }
 
+/// Sistema de arquivos montável; os caminhos recebidos são relativos
+/// ao ponto de montagem e sempre começam com '/'
+pub trait Filesystem: Send + Sync {
+    /// Nome do tipo de sistema de arquivos (ex: "fat12")
+    fn name(&self) -> &str;
+
+    /// Resolve um caminho para o inode correspondente
+    fn lookup(&self, path: &str) -> Result<InodeId, VfsError>;
+
+    fn stat(&self, inode: InodeId) -> Result<Metadata, VfsError>;
+
+    /// Lê a partir de `offset`; retorna 0 no fim do arquivo
+    fn read_at(&self, inode: InodeId, offset: u64, buf: &mut [u8]) -> Result<usize, VfsError>;
+
+    fn write_at(&self, _inode: InodeId, _offset: u64, _buf: &[u8]) -> Result<usize, VfsError> {
+        Err(VfsError::ReadOnly)
+    }
+
+    /// Cria um arquivo vazio (ou trunca um existente) e retorna seu inode
+    fn create(&self, _path: &str) -> Result<InodeId, VfsError> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn mkdir(&self, _path: &str) -> Result<(), VfsError> {
+        Err(VfsError::ReadOnly)
+    }
+
+    /// Remove um arquivo ou diretório vazio
+    fn remove(&self, _path: &str) -> Result<(), VfsError> {
+        Err(VfsError::ReadOnly)
+    }
+
+    fn truncate(&self, _inode: InodeId, _size: u64) -> Result<(), VfsError> {
+        Err(VfsError::ReadOnly)
+    }
+
+    /// Cria em `path` um link simbólico com o conteúdo `target`
+    fn symlink(&self, _target: &str, _path: &str) -> Result<(), VfsError> {
+        Err(VfsError::Unsupported)
+    }
+
+    /// Conteúdo de um link simbólico (não é seguido)
+    fn readlink(&self, _path: &str) -> Result<String, VfsError> {
+        Err(VfsError::Unsupported)
+    }
+
+    /// Cria um novo nome `path` para o inode de `existing`
+    fn link(&self, _existing: &str, _path: &str) -> Result<(), VfsError> {
+        Err(VfsError::Unsupported)
+    }
+
+    /// Remove um nome (arquivo ou link); o inode some junto com o último nome
+    fn unlink(&self, _path: &str) -> Result<(), VfsError> {
+        Err(VfsError::Unsupported)
+    }
+
+    /// Move/renomeia `from` para `to`, substituindo `to` se existir
+    fn rename(&self, _from: &str, _to: &str) -> Result<(), VfsError> {
+        Err(VfsError::Unsupported)
+    }
+
+    fn list_dir(&self, path: &str) -> Result<Directory, VfsError>;
+
+    /// Espaço total e livre; sistemas virtuais (devfs, procfs) não informam
+    fn statfs(&self) -> Result<FsStats, VfsError> {
+        Err(VfsError::Unsupported)
+    }
+}
+
+/// Handle de um arquivo aberto: lê o conteúdo sob demanda via `read_at`
+pub struct File {
+    pub name: String,
+    fs: &'static dyn Filesystem,
+    inode: InodeId,
+    pos: u64,
+}
+
 impl File {
+    pub fn new(name: &str, fs: &'static dyn Filesystem, inode: InodeId) -> Self {
+        Self {
+            name: name.into(),
+            fs,
+            inode,
+            pos: 0,
+        }
+    }
+
+    /// Lê até `buf.len()` bytes da posição atual
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, VfsError> {
+        let n = self.fs.read_at(self.inode, self.pos, buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    /// Escreve `buf` na posição atual
+    pub fn write(&mut self, buf: &[u8]) -> Result<usize, VfsError> {
+        let n = self.fs.write_at(self.inode, self.pos, buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    /// Move a posição de leitura/escrita; retorna a nova posição
+    pub fn seek(&mut self, from: SeekFrom) -> Result<u64, VfsError> {
+        let (base, delta) = match from {
+            SeekFrom::Start(offset) => (0, offset as i64),
+            SeekFrom::Current(delta) => (self.pos as i64, delta),
+            SeekFrom::End(delta) => (self.stat()?.size as i64, delta),
+        };
+
+        let pos = base.checked_add(delta).ok_or(VfsError::InvalidSeek)?;
+        if pos < 0 {
+            return Err(VfsError::InvalidSeek);
+        }
+        self.pos = pos as u64;
+        Ok(self.pos)
+    }
+
+    pub fn stat(&self) -> Result<Metadata, VfsError> {
+        self.fs.stat(self.inode)
+    }
+
+    /// Fecha o handle (equivalente a descartá-lo)
+    pub fn close(self) {}
+
     pub fn read_byte(&mut self) -> Option<u8> {
-        if self.pos >= self.data.len() {
-            None
-        } else {
-            let byte = self.data[self.pos];
-            self.pos += 1;
-            Some(byte)
+        let mut byte = [0u8; 1];
+        match self.read(&mut byte) {
+            Ok(1) => Some(byte[0]),
+            _ => None,
         }
     }
 
     pub fn rewind(&mut self) {
         self.pos = 0;
     }
-}
 
-pub trait Filesystem {
-    fn open(&self, name: &str) -> Option<File>;
-    fn list_dir(&self, path: &str) -> Option<Directory>;
+    /// Lê da posição atual até o fim do arquivo
+    pub fn read_to_end(&mut self) -> Result<Vec<u8>, VfsError> {
+        let mut data = Vec::new();
+        let mut chunk = [0u8; 512];
+        loop {
+            let n = self.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(data);
+            }
+            data.extend_from_slice(&chunk[..n]);
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct DirEntry {
     pub name: String,
-    pub is_dir: bool,
-    pub cluster: u16,
-    pub size: u32,
+    pub metadata: Metadata,
+}
+
+impl DirEntry {
+    pub fn is_dir(&self) -> bool {
+        self.metadata.is_dir()
+    }
 }
 
-/// Registro do VFS
+/// Normaliza um caminho absoluto, resolvendo `.`, `..` e barras repetidas
+pub fn normalize(path: &str) -> Result<String, VfsError> {
+    if !path.starts_with('/') {
+        return Err(VfsError::InvalidPath);
+    }
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            name => parts.push(name),
+        }
+    }
+
+    let mut out = String::new();
+    for part in parts {
+        out.push('/');
+        out.push_str(part);
+    }
+    if out.is_empty() {
+        out.push('/');
+    }
+    Ok(out)
+}
+
+/// Junta um caminho (absoluto ou relativo) ao diretório atual e normaliza
+pub fn resolve(cwd: &str, path: &str) -> Result<String, VfsError> {
+    if path.starts_with('/') {
+        return normalize(path);
+    }
+
+    let mut full = String::from(cwd);
+    if !full.ends_with('/') {
+        full.push('/');
+    }
+    full.push_str(path);
+    normalize(&full)
+}
+
+/// Quantos links simbólicos uma resolução pode seguir antes de desistir
+const MAX_SYMLINK_DEPTH: usize = 8;
+
+/// Uma entrada da tabela de montagem
+pub struct Mount {
+    pub fs: &'static dyn Filesystem,
+    /// Origem da montagem (dispositivo ou descrição)
+    pub source: String,
+}
+
+/// Registro do VFS: tabela de montagem indexada pelo ponto de montagem
 pub struct VFS {
-    fs: Option<&'static dyn Filesystem>,
+    mounts: BTreeMap<String, Mount>,
 }
 
 impl VFS {
     pub const fn new() -> Self {
-        Self { fs: None }
+        Self { mounts: BTreeMap::new() }
+    }
+
+    /// Monta `fs` no caminho `path` (que deve ser absoluto)
+    pub fn mount(&mut self, path: &str, fs: &'static dyn Filesystem, source: &str) -> Result<(), VfsError> {
+        let path = normalize(path)?;
+        if self.mounts.contains_key(&path) {
+            return Err(VfsError::AlreadyMounted);
+        }
+        self.mounts.insert(path, Mount { fs, source: source.into() });
+        Ok(())
+    }
+
+    /// Monta um volume FAT a partir de um dispositivo de blocos (ex: "hda1")
+    pub fn mount_device(&mut self, name: &str, path: &str) -> Result<(), VfsError> {
+        let device = crate::block::find(name).ok_or(VfsError::NoDevice)?;
+        let fat = crate::fat12::Fat12Volume::from_device(device).map_err(|_| VfsError::Io)?;
+        self.mount(path, Box::leak(Box::new(fat)), name)
     }
 
-    pub fn mount(&mut self, fs: &'static dyn Filesystem) {
-        self.fs = Some(fs);
+    /// Desmonta o sistema de arquivos em `path`; falha se houver montagens aninhadas
+    pub fn umount(&mut self, path: &str) -> Result<(), VfsError> {
+        let path = normalize(path)?;
+        if !self.mounts.contains_key(&path) {
+            return Err(VfsError::NotMounted);
+        }
+
+        let nested = self
+            .mounts
+            .keys()
+            .any(|other| *other != path && Self::strip_mount(other, &path).is_some());
+        if nested {
+            return Err(VfsError::Busy);
+        }
+
+        self.mounts.remove(&path);
+        Ok(())
     }
 
-    pub fn open(&self, name: &str) -> Option<File> {
-        self.fs?.open(name)
+    /// Lista (ponto de montagem, tipo, origem) de todas as montagens
+    pub fn mounts(&self) -> Vec<(String, String, String)> {
+        self.mounts
+            .iter()
+            .map(|(path, m)| (path.clone(), String::from(m.fs.name()), m.source.clone()))
+            .collect()
+    }
+
+    /// Se `path` está dentro de `mount_point`, retorna o restante (com '/' inicial)
+    fn strip_mount<'a>(path: &'a str, mount_point: &str) -> Option<&'a str> {
+        if mount_point == "/" {
+            return Some(path);
+        }
+        let rest = path.strip_prefix(mount_point)?;
+        if rest.is_empty() {
+            Some("/")
+        } else if rest.starts_with('/') {
+            Some(rest)
+        } else {
+            None
+        }
     }
 
-     pub fn list_dir(&self, path: &str) -> Option<Directory> {
-        self.fs?.list_dir(path)
+    /// Encontra a montagem com o maior prefixo e o caminho relativo a ela
+    fn resolve_mount(&self, path: &str) -> Result<(&'static dyn Filesystem, String), VfsError> {
+        let path = normalize(path)?;
+
+        let (fs, rest) = self
+            .mounts
+            .iter()
+            .filter_map(|(mount_point, m)| {
+                Self::strip_mount(&path, mount_point).map(|rest| (mount_point.len(), m.fs, rest))
+            })
+            .max_by_key(|(len, _, _)| *len)
+            .map(|(_, fs, rest)| (fs, String::from(rest)))
+            .ok_or(VfsError::NotMounted)?;
+
+        Ok((fs, rest))
+    }
+
+    /// Resolve os links simbólicos de um caminho absoluto, componente a
+    /// componente (o alvo de um link pode estar em outra montagem).
+    /// Com `follow_last` o último componente também é seguido; um último
+    /// componente inexistente é aceito, para permitir criá-lo.
+    pub fn canonicalize(&self, path: &str, follow_last: bool) -> Result<String, VfsError> {
+        if !path.starts_with('/') {
+            return Err(VfsError::InvalidPath);
+        }
+
+        // Componentes ainda não processados, em ordem inversa
+        let mut pending: Vec<String> = path.rsplit('/').filter(|p| !p.is_empty()).map(String::from).collect();
+        let mut resolved = String::from("/");
+        let mut followed = 0;
+
+        while let Some(part) = pending.pop() {
+            match part.as_str() {
+                "." => continue,
+                ".." => {
+                    let parent = resolved.rsplit_once('/').map_or("", |(dir, _)| dir);
+                    resolved = if parent.is_empty() { String::from("/") } else { String::from(parent) };
+                    continue;
+                }
+                _ => {}
+            }
+
+            let mut candidate = resolved.clone();
+            if !candidate.ends_with('/') {
+                candidate.push('/');
+            }
+            candidate.push_str(&part);
+
+            let is_last = pending.is_empty();
+            if is_last && !follow_last {
+                resolved = candidate;
+                break;
+            }
+
+            let (fs, rest) = self.resolve_mount(&candidate)?;
+            let inode = match fs.lookup(&rest) {
+                Ok(inode) => inode,
+                Err(VfsError::NotFound) if is_last => {
+                    resolved = candidate;
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+
+            if fs.stat(inode)?.file_type != FileType::Symlink {
+                resolved = candidate;
+                continue;
+            }
+
+            followed += 1;
+            if followed > MAX_SYMLINK_DEPTH {
+                return Err(VfsError::SymlinkLoop);
+            }
+            let target = fs.readlink(&rest)?;
+            if target.starts_with('/') {
+                resolved = String::from("/");
+            }
+            pending.extend(target.rsplit('/').filter(|p| !p.is_empty()).map(String::from));
+        }
+
+        Ok(resolved)
+    }
+
+    /// Montagem e caminho relativo após resolver os links simbólicos
+    fn resolve_links(&self, path: &str, follow_last: bool) -> Result<(&'static dyn Filesystem, String), VfsError> {
+        self.resolve_mount(&self.canonicalize(path, follow_last)?)
+    }
+
+    /// Como `resolve_links` para dois caminhos que precisam estar na mesma montagem
+    fn resolve_pair(&self, from: &str, to: &str) -> Result<(&'static dyn Filesystem, String, String), VfsError> {
+        let (fs_from, from) = self.resolve_links(from, false)?;
+        let (fs_to, to) = self.resolve_links(to, false)?;
+        if !core::ptr::addr_eq(fs_from, fs_to) {
+            return Err(VfsError::CrossDevice);
+        }
+        Ok((fs_from, from, to))
+    }
+
+    pub fn open(&self, path: &str) -> Result<File, VfsError> {
+        let (fs, rest) = self.resolve_links(path, true)?;
+        let inode = fs.lookup(&rest)?;
+        if fs.stat(inode)?.is_dir() {
+            return Err(VfsError::IsADirectory);
+        }
+        Ok(File::new(&normalize(path)?, fs, inode))
+    }
+
+    /// Cria (ou trunca) um arquivo e o abre para escrita
+    pub fn create(&self, path: &str) -> Result<File, VfsError> {
+        let (fs, rest) = self.resolve_links(path, true)?;
+        let inode = fs.create(&rest)?;
+        Ok(File::new(&normalize(path)?, fs, inode))
+    }
+
+    pub fn mkdir(&self, path: &str) -> Result<(), VfsError> {
+        let (fs, rest) = self.resolve_links(path, false)?;
+        fs.mkdir(&rest)
+    }
+
+    pub fn remove(&self, path: &str) -> Result<(), VfsError> {
+        let path = self.canonicalize(path, false)?;
+        if self.mounts.contains_key(&path) {
+            return Err(VfsError::Busy);
+        }
+        let (fs, rest) = self.resolve_mount(&path)?;
+        fs.remove(&rest)
+    }
+
+    pub fn symlink(&self, target: &str, path: &str) -> Result<(), VfsError> {
+        let (fs, rest) = self.resolve_links(path, false)?;
+        fs.symlink(target, &rest)
+    }
+
+    pub fn readlink(&self, path: &str) -> Result<String, VfsError> {
+        let (fs, rest) = self.resolve_links(path, false)?;
+        fs.readlink(&rest)
+    }
+
+    pub fn link(&self, existing: &str, path: &str) -> Result<(), VfsError> {
+        let (fs, existing, path) = self.resolve_pair(existing, path)?;
+        fs.link(&existing, &path)
+    }
+
+    pub fn unlink(&self, path: &str) -> Result<(), VfsError> {
+        let (fs, rest) = self.resolve_links(path, false)?;
+        fs.unlink(&rest)
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> Result<(), VfsError> {
+        for path in [from, to] {
+            if self.mounts.contains_key(&self.canonicalize(path, false)?) {
+                return Err(VfsError::Busy);
+            }
+        }
+        let (fs, from, to) = self.resolve_pair(from, to)?;
+        fs.rename(&from, &to)
+    }
+
+    /// Metadados do alvo, seguindo links simbólicos
+    pub fn stat(&self, path: &str) -> Result<Metadata, VfsError> {
+        let (fs, rest) = self.resolve_links(path, true)?;
+        fs.stat(fs.lookup(&rest)?)
+    }
+
+    /// Metadados do próprio link, sem segui-lo
+    pub fn lstat(&self, path: &str) -> Result<Metadata, VfsError> {
+        let (fs, rest) = self.resolve_links(path, false)?;
+        fs.stat(fs.lookup(&rest)?)
+    }
+
+    /// Uso de espaço do sistema de arquivos que contém `path`
+    pub fn statfs(&self, path: &str) -> Result<FsStats, VfsError> {
+        let (fs, _) = self.resolve_links(path, true)?;
+        fs.statfs()
+    }
+
+    pub fn list_dir(&self, path: &str) -> Result<Directory, VfsError> {
+        let path = self.canonicalize(&normalize(path)?, true)?;
+        let (fs, rest) = self.resolve_mount(&path)?;
+        let mut dir = fs.list_dir(&rest)?;
+
+        // Pontos de montagem filhos aparecem como diretórios
+        for (mount_point, mount) in &self.mounts {
+            if *mount_point == path {
+                continue;
+            }
+            let Some(child) = Self::strip_mount(mount_point, &path) else { continue };
+            let child = child.trim_start_matches('/');
+            if child.is_empty() || child.contains('/') {
+                continue;
+            }
+            if !dir.entries.iter().any(|e| e.name == child) {
+                let metadata = mount.fs.stat(mount.fs.lookup("/")?)?;
+                dir.entries.push(DirEntry {
+                    name: child.into(),
+                    metadata,
+                });
+            }
+        }
+
+        dir.name = path;
+        Ok(dir)
     }
 }
 
-use spin::Mutex;
 use lazy_static::lazy_static;
+use crate::sync::Mutex;
 
 lazy_static! {
-    pub static ref VFS_INSTANCE: Mutex<VFS> = Mutex::new(VFS::new());
+    pub static ref VFS_INSTANCE: Mutex<VFS> = Mutex::named("vfs", VFS::new());
 }
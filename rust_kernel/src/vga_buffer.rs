@@ -1,7 +1,15 @@
 use core::fmt;
 use lazy_static::lazy_static;
-use spin::Mutex;
 use volatile::Volatile;
+use x86_64::instructions::port::Port;
+
+use crate::sync::IrqSpinLock;
+
+// Registradores do controlador CRT usados para posicionar o cursor
+const CRTC_ADDRESS: u16 = 0x3D4;
+const CRTC_DATA: u16 = 0x3D5;
+const CRTC_CURSOR_HIGH: u8 = 0x0E;
+const CRTC_CURSOR_LOW: u8 = 0x0F;
 
 #[allow(dead_code)]
 #[derive(Clone, Copy)]
@@ -100,6 +108,48 @@ impl Writer {
         }
     }
 
+    /// Apaga a tela inteira e volta ao início da última linha
+    pub fn clear(&mut self) {
+        for row in 0..BUFFER_HEIGHT {
+            self.clear_row(row);
+        }
+        self.column_position = 0;
+    }
+
+    /// Move o cursor de hardware para uma coluna da última linha
+    fn move_cursor(&mut self, col: usize) {
+        let pos = ((BUFFER_HEIGHT - 1) * BUFFER_WIDTH + col.min(BUFFER_WIDTH - 1)) as u16;
+        unsafe {
+            let mut address = Port::<u8>::new(CRTC_ADDRESS);
+            let mut data = Port::<u8>::new(CRTC_DATA);
+            address.write(CRTC_CURSOR_HIGH);
+            data.write((pos >> 8) as u8);
+            address.write(CRTC_CURSOR_LOW);
+            data.write(pos as u8);
+        }
+    }
+
+    /// Reescreve a última linha com `text`, com o cursor no caractere
+    /// `cursor`; textos maiores que a tela rolam horizontalmente
+    pub fn redraw_row(&mut self, text: &str, cursor: usize) {
+        let row = BUFFER_HEIGHT - 1;
+        self.clear_row(row);
+
+        let skip = (cursor + 1).saturating_sub(BUFFER_WIDTH);
+        let mut col = 0;
+        for c in text.chars().skip(skip).take(BUFFER_WIDTH) {
+            let byte = if (' '..='~').contains(&c) { c as u8 } else { 0xfe };
+            self.buffer.chars[row][col].write(ScreenChar {
+                ascii_character: byte,
+                color_code: self.color_code,
+            });
+            col += 1;
+        }
+
+        self.column_position = col;
+        self.move_cursor(cursor - skip);
+    }
+
     pub fn write_string(&mut self, s: &str) {
         for byte in s.bytes() {
             match byte {
@@ -111,7 +161,8 @@ impl Writer {
 }
 
 lazy_static! {
-    pub static ref WRITER: Mutex<Writer> = Mutex::new(Writer {
+    /// Também usado por handlers de interrupção (panics, mensagens de IRQ)
+    pub static ref WRITER: IrqSpinLock<Writer> = IrqSpinLock::named("vga", Writer {
         column_position: 0,
         color_code: ColorCode::new(Color::LightGreen, Color::Black),
         buffer: unsafe { &mut *(0xb8000 as *mut Buffer) },
@@ -144,5 +195,14 @@ macro_rules! vga_println {
 #[doc(hidden)]
 pub fn _print(args: fmt::Arguments) {
     use core::fmt::Write;
-    WRITER.lock().write_fmt(args).unwrap();
+    let mut writer = WRITER.lock();
+    writer.write_fmt(args).unwrap();
+    let col = writer.column_position;
+    writer.move_cursor(col);
+}
+
+pub fn clear_screen() {
+    let mut writer = WRITER.lock();
+    writer.clear();
+    writer.move_cursor(0);
 }
@@ -0,0 +1,593 @@
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{fence, AtomicBool, Ordering};
+use spin::Mutex;
+use x86_64::instructions::port::Port;
+use x86_64::{PhysAddr, VirtAddr};
+
+use crate::block::{self, BlockDevice, SECTOR_SIZE};
+use crate::memory::{alloc_dma, map_mmio};
+use crate::pci::{self, Bar, PciDevice, CAP_VENDOR_SPECIFIC};
+use crate::sync::IrqSpinLock;
+use crate::vga_println;
+
+const VIRTIO_VENDOR: u16 = 0x1AF4;
+const DEVICE_LEGACY_BLK: u16 = 0x1001;
+const DEVICE_MODERN_BLK: u16 = 0x1042;
+
+// Bits de status do dispositivo
+const STATUS_ACKNOWLEDGE: u8 = 1;
+const STATUS_DRIVER: u8 = 2;
+const STATUS_DRIVER_OK: u8 = 4;
+const STATUS_FEATURES_OK: u8 = 8;
+const STATUS_FAILED: u8 = 128;
+
+// Features que o driver aceita
+const FEATURE_BLK_RO: u64 = 1 << 5;
+const FEATURE_BLK_FLUSH: u64 = 1 << 9;
+const FEATURE_VERSION_1: u64 = 1 << 32;
+
+// Tipos de requisição virtio-blk
+const REQ_IN: u32 = 0;
+const REQ_OUT: u32 = 1;
+const REQ_FLUSH: u32 = 4;
+
+const DESC_F_NEXT: u16 = 1;
+const DESC_F_WRITE: u16 = 2;
+
+/// Tamanho máximo da fila que usamos (limita memória de DMA)
+const MAX_QUEUE_SIZE: u16 = 128;
+
+/// Páginas do buffer intermediário de DMA (64 KiB por requisição)
+const BOUNCE_PAGES: usize = 16;
+
+/// Limite de iterações esperando o dispositivo responder
+const TIMEOUT: u32 = 10_000_000;
+
+// Tipos de capability virtio PCI (cfg_type)
+const CAP_COMMON_CFG: u8 = 1;
+const CAP_NOTIFY_CFG: u8 = 2;
+const CAP_ISR_CFG: u8 = 3;
+const CAP_DEVICE_CFG: u8 = 4;
+
+// Offsets do common cfg (transporte moderno)
+const COMMON_DFSELECT: usize = 0x00;
+const COMMON_DF: usize = 0x04;
+const COMMON_GFSELECT: usize = 0x08;
+const COMMON_GF: usize = 0x0C;
+const COMMON_STATUS: usize = 0x14;
+const COMMON_Q_SELECT: usize = 0x16;
+const COMMON_Q_SIZE: usize = 0x18;
+const COMMON_Q_ENABLE: usize = 0x1C;
+const COMMON_Q_NOFF: usize = 0x1E;
+const COMMON_Q_DESCLO: usize = 0x20;
+const COMMON_Q_AVAILLO: usize = 0x28;
+const COMMON_Q_USEDLO: usize = 0x30;
+
+// Registradores do transporte legado (BAR0 de I/O)
+const LEGACY_DEVICE_FEATURES: u16 = 0x00;
+const LEGACY_GUEST_FEATURES: u16 = 0x04;
+const LEGACY_QUEUE_PFN: u16 = 0x08;
+const LEGACY_QUEUE_SIZE: u16 = 0x0C;
+const LEGACY_QUEUE_SELECT: u16 = 0x0E;
+const LEGACY_QUEUE_NOTIFY: u16 = 0x10;
+const LEGACY_STATUS: u16 = 0x12;
+const LEGACY_ISR: u16 = 0x13;
+const LEGACY_CONFIG: u16 = 0x14;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Descriptor {
+    addr: u64,
+    len: u32,
+    flags: u16,
+    next: u16,
+}
+
+/// Cabeçalho de uma requisição virtio-blk
+#[repr(C)]
+struct RequestHeader {
+    kind: u32,
+    reserved: u32,
+    sector: u64,
+}
+
+unsafe fn mmio_read<T>(base: VirtAddr, offset: usize) -> T {
+    read_volatile((base.as_u64() as usize + offset) as *const T)
+}
+
+unsafe fn mmio_write<T>(base: VirtAddr, offset: usize, value: T) {
+    write_volatile((base.as_u64() as usize + offset) as *mut T, value)
+}
+
+/// Registradores do dispositivo: porta de I/O (legado) ou MMIO (moderno)
+enum Transport {
+    Legacy {
+        io_base: u16,
+    },
+    Modern {
+        common: VirtAddr,
+        notify: VirtAddr,
+        notify_multiplier: u32,
+        isr: VirtAddr,
+        device: VirtAddr,
+    },
+}
+
+impl Transport {
+    /// Detecta o transporte moderno pelas capabilities; senão usa o BAR0 legado
+    fn probe(dev: &PciDevice) -> Result<Self, &'static str> {
+        let mut common = None;
+        let mut notify = None;
+        let mut isr = None;
+        let mut device = None;
+
+        for cap in dev.capabilities() {
+            if dev.read8(cap) != CAP_VENDOR_SPECIFIC {
+                continue;
+            }
+
+            let cfg_type = dev.read8(cap + 3);
+            let bar = dev.read8(cap + 4);
+            let offset = dev.read32(cap + 8) as u64;
+            let length = dev.read32(cap + 12) as usize;
+
+            let Some(Bar::Memory(base)) = dev.bar(bar) else { continue };
+            let region = || map_mmio(PhysAddr::new(base + offset), length);
+
+            match cfg_type {
+                CAP_COMMON_CFG if common.is_none() => common = Some(region()?),
+                CAP_NOTIFY_CFG if notify.is_none() => {
+                    notify = Some((region()?, dev.read32(cap + 16)));
+                }
+                CAP_ISR_CFG if isr.is_none() => isr = Some(region()?),
+                CAP_DEVICE_CFG if device.is_none() => device = Some(region()?),
+                _ => {}
+            }
+        }
+
+        if let (Some(common), Some((notify, notify_multiplier)), Some(isr), Some(device)) =
+            (common, notify, isr, device)
+        {
+            return Ok(Transport::Modern { common, notify, notify_multiplier, isr, device });
+        }
+
+        match dev.bar(0) {
+            Some(Bar::Io(io_base)) => Ok(Transport::Legacy { io_base }),
+            _ => Err("virtio sem BAR de I/O legado nem capabilities modernas"),
+        }
+    }
+
+    fn is_modern(&self) -> bool {
+        matches!(self, Transport::Modern { .. })
+    }
+
+    fn status(&self) -> u8 {
+        match *self {
+            Transport::Legacy { io_base } => unsafe { Port::<u8>::new(io_base + LEGACY_STATUS).read() },
+            Transport::Modern { common, .. } => unsafe { mmio_read(common, COMMON_STATUS) },
+        }
+    }
+
+    fn set_status(&self, status: u8) {
+        match *self {
+            Transport::Legacy { io_base } => unsafe {
+                Port::<u8>::new(io_base + LEGACY_STATUS).write(status)
+            },
+            Transport::Modern { common, .. } => unsafe { mmio_write(common, COMMON_STATUS, status) },
+        }
+    }
+
+    fn device_features(&self) -> u64 {
+        match *self {
+            Transport::Legacy { io_base } => unsafe {
+                Port::<u32>::new(io_base + LEGACY_DEVICE_FEATURES).read() as u64
+            },
+            Transport::Modern { common, .. } => unsafe {
+                mmio_write::<u32>(common, COMMON_DFSELECT, 0);
+                let low = mmio_read::<u32>(common, COMMON_DF) as u64;
+                mmio_write::<u32>(common, COMMON_DFSELECT, 1);
+                let high = mmio_read::<u32>(common, COMMON_DF) as u64;
+                low | high << 32
+            },
+        }
+    }
+
+    fn set_driver_features(&self, features: u64) {
+        match *self {
+            Transport::Legacy { io_base } => unsafe {
+                Port::<u32>::new(io_base + LEGACY_GUEST_FEATURES).write(features as u32)
+            },
+            Transport::Modern { common, .. } => unsafe {
+                mmio_write::<u32>(common, COMMON_GFSELECT, 0);
+                mmio_write::<u32>(common, COMMON_GF, features as u32);
+                mmio_write::<u32>(common, COMMON_GFSELECT, 1);
+                mmio_write::<u32>(common, COMMON_GF, (features >> 32) as u32);
+            },
+        }
+    }
+
+    /// Lê e reconhece o registrador ISR
+    fn ack_interrupt(&self) -> u8 {
+        match *self {
+            Transport::Legacy { io_base } => unsafe { Port::<u8>::new(io_base + LEGACY_ISR).read() },
+            Transport::Modern { isr, .. } => unsafe { mmio_read(isr, 0) },
+        }
+    }
+
+    /// Capacidade do disco em setores de 512 bytes (offset 0 do device cfg)
+    fn capacity(&self) -> u64 {
+        match *self {
+            Transport::Legacy { io_base } => unsafe {
+                let low = Port::<u32>::new(io_base + LEGACY_CONFIG).read() as u64;
+                let high = Port::<u32>::new(io_base + LEGACY_CONFIG + 4).read() as u64;
+                low | high << 32
+            },
+            Transport::Modern { device, .. } => unsafe {
+                let low = mmio_read::<u32>(device, 0) as u64;
+                let high = mmio_read::<u32>(device, 4) as u64;
+                low | high << 32
+            },
+        }
+    }
+
+    /// Configura a fila 0 e retorna (virtqueue, offset de notificação)
+    fn setup_queue(&self) -> Result<(VirtQueue, usize), &'static str> {
+        match *self {
+            Transport::Legacy { io_base } => unsafe {
+                Port::<u16>::new(io_base + LEGACY_QUEUE_SELECT).write(0);
+                let size = Port::<u16>::new(io_base + LEGACY_QUEUE_SIZE).read();
+                if size == 0 {
+                    return Err("fila virtio indisponível");
+                }
+                // No legado o tamanho é imposto pelo dispositivo
+                let queue = VirtQueue::new(size)?;
+                Port::<u32>::new(io_base + LEGACY_QUEUE_PFN)
+                    .write((queue.phys.as_u64() >> 12) as u32);
+                Ok((queue, 0))
+            },
+            Transport::Modern { common, notify_multiplier, .. } => unsafe {
+                mmio_write::<u16>(common, COMMON_Q_SELECT, 0);
+                let max = mmio_read::<u16>(common, COMMON_Q_SIZE);
+                if max == 0 {
+                    return Err("fila virtio indisponível");
+                }
+                let size = core::cmp::min(max, MAX_QUEUE_SIZE);
+                let queue = VirtQueue::new(size)?;
+
+                mmio_write::<u16>(common, COMMON_Q_SIZE, size);
+                mmio_write::<u64>(common, COMMON_Q_DESCLO, queue.phys.as_u64());
+                mmio_write::<u64>(common, COMMON_Q_AVAILLO, queue.phys.as_u64() + queue.avail_offset as u64);
+                mmio_write::<u64>(common, COMMON_Q_USEDLO, queue.phys.as_u64() + queue.used_offset as u64);
+
+                let notify_off = mmio_read::<u16>(common, COMMON_Q_NOFF) as usize;
+                mmio_write::<u16>(common, COMMON_Q_ENABLE, 1);
+                Ok((queue, notify_off * notify_multiplier as usize))
+            },
+        }
+    }
+
+    fn notify(&self, notify_offset: usize) {
+        match *self {
+            Transport::Legacy { io_base } => unsafe {
+                Port::<u16>::new(io_base + LEGACY_QUEUE_NOTIFY).write(0)
+            },
+            Transport::Modern { notify, .. } => unsafe { mmio_write::<u16>(notify, notify_offset, 0) },
+        }
+    }
+}
+
+/// Split virtqueue: tabela de descritores, anel "avail" e anel "used"
+struct VirtQueue {
+    size: u16,
+    phys: PhysAddr,
+    virt: VirtAddr,
+    avail_offset: usize,
+    used_offset: usize,
+    last_used: u16,
+}
+
+impl VirtQueue {
+    fn new(size: u16) -> Result<Self, &'static str> {
+        let size_usize = size as usize;
+        let desc_bytes = 16 * size_usize;
+        let avail_bytes = 6 + 2 * size_usize;
+        // O anel "used" precisa estar alinhado a 4096 no transporte legado
+        let used_offset = (desc_bytes + avail_bytes).next_multiple_of(4096);
+        let used_bytes = 6 + 8 * size_usize;
+        let pages = (used_offset + used_bytes).div_ceil(4096);
+
+        let (phys, virt) = alloc_dma(pages).ok_or("sem memória para virtqueue")?;
+
+        Ok(Self {
+            size,
+            phys,
+            virt,
+            avail_offset: desc_bytes,
+            used_offset,
+            last_used: 0,
+        })
+    }
+
+    fn base(&self) -> usize {
+        self.virt.as_u64() as usize
+    }
+
+    fn write_descriptor(&mut self, index: u16, desc: Descriptor) {
+        let ptr = (self.base() + index as usize * 16) as *mut Descriptor;
+        unsafe { write_volatile(ptr, desc) }
+    }
+
+    /// Publica a cabeça de uma cadeia no anel "avail"
+    fn push_avail(&mut self, head: u16) {
+        let avail = self.base() + self.avail_offset;
+        unsafe {
+            let idx_ptr = (avail + 2) as *mut u16;
+            let idx = read_volatile(idx_ptr);
+            let slot = (avail + 4 + (idx % self.size) as usize * 2) as *mut u16;
+            write_volatile(slot, head);
+            fence(Ordering::SeqCst);
+            write_volatile(idx_ptr, idx.wrapping_add(1));
+        }
+    }
+
+    fn used_idx(&self) -> u16 {
+        unsafe { read_volatile((self.base() + self.used_offset + 2) as *const u16) }
+    }
+
+    /// Retorna true se o dispositivo concluiu uma nova requisição
+    fn pop_used(&mut self) -> bool {
+        fence(Ordering::SeqCst);
+        if self.used_idx() == self.last_used {
+            return false;
+        }
+        self.last_used = self.last_used.wrapping_add(1);
+        true
+    }
+}
+
+/// Estado mutável protegido pelo lock do dispositivo
+struct Inner {
+    queue: VirtQueue,
+    /// Página com o cabeçalho (offset 0) e o byte de status (offset 16)
+    request_phys: PhysAddr,
+    request_virt: VirtAddr,
+    bounce_phys: PhysAddr,
+    bounce_virt: VirtAddr,
+}
+
+/// Disco virtio-blk registrado como dispositivo de blocos
+pub struct VirtioBlk {
+    name: String,
+    transport: Transport,
+    notify_offset: usize,
+    sectors: u64,
+    read_only: bool,
+    has_flush: bool,
+    irq_fired: AtomicBool,
+    inner: Mutex<Inner>,
+}
+
+impl VirtioBlk {
+    /// Inicializa o dispositivo seguindo a sequência da especificação virtio
+    pub fn init(name: &str, dev: &PciDevice) -> Result<Self, &'static str> {
+        dev.enable_bus_master();
+        let transport = Transport::probe(dev)?;
+
+        transport.set_status(0);
+        transport.set_status(STATUS_ACKNOWLEDGE);
+        transport.set_status(STATUS_ACKNOWLEDGE | STATUS_DRIVER);
+
+        let offered = transport.device_features();
+        let mut accepted = offered & (FEATURE_BLK_RO | FEATURE_BLK_FLUSH);
+        if transport.is_modern() {
+            if offered & FEATURE_VERSION_1 == 0 {
+                transport.set_status(STATUS_FAILED);
+                return Err("dispositivo moderno sem VIRTIO_F_VERSION_1");
+            }
+            accepted |= FEATURE_VERSION_1;
+        }
+        transport.set_driver_features(accepted);
+
+        let mut status = STATUS_ACKNOWLEDGE | STATUS_DRIVER;
+        if transport.is_modern() {
+            status |= STATUS_FEATURES_OK;
+            transport.set_status(status);
+            if transport.status() & STATUS_FEATURES_OK == 0 {
+                transport.set_status(STATUS_FAILED);
+                return Err("features recusadas pelo dispositivo");
+            }
+        }
+
+        let (queue, notify_offset) = transport.setup_queue()?;
+        let (request_phys, request_virt) = alloc_dma(1).ok_or("sem memória para DMA")?;
+        let (bounce_phys, bounce_virt) = alloc_dma(BOUNCE_PAGES).ok_or("sem memória para DMA")?;
+
+        let sectors = transport.capacity();
+        transport.set_status(status | STATUS_DRIVER_OK);
+
+        Ok(Self {
+            name: name.into(),
+            transport,
+            notify_offset,
+            sectors,
+            read_only: accepted & FEATURE_BLK_RO != 0,
+            has_flush: accepted & FEATURE_BLK_FLUSH != 0,
+            irq_fired: AtomicBool::new(false),
+            inner: Mutex::new(Inner {
+                queue,
+                request_phys,
+                request_virt,
+                bounce_phys,
+                bounce_virt,
+            }),
+        })
+    }
+
+    /// Chamado pela IRQ do dispositivo
+    fn handle_irq(&self) {
+        if self.transport.ack_interrupt() & 1 != 0 {
+            self.irq_fired.store(true, Ordering::Release);
+        }
+    }
+
+    /// Monta a cadeia cabeçalho → dados → status e espera a conclusão
+    fn submit(&self, inner: &mut Inner, kind: u32, sector: u64, len: usize) -> Result<(), &'static str> {
+        unsafe {
+            write_volatile(
+                inner.request_virt.as_mut_ptr::<RequestHeader>(),
+                RequestHeader { kind, reserved: 0, sector },
+            );
+            write_volatile((inner.request_virt + 16u64).as_mut_ptr::<u8>(), 0xFF);
+        }
+
+        let header = Descriptor {
+            addr: inner.request_phys.as_u64(),
+            len: 16,
+            flags: DESC_F_NEXT,
+            next: 1,
+        };
+        let status = Descriptor {
+            addr: inner.request_phys.as_u64() + 16,
+            len: 1,
+            flags: DESC_F_WRITE,
+            next: 0,
+        };
+
+        inner.queue.write_descriptor(0, header);
+        if len > 0 {
+            let data_flags = if kind == REQ_IN { DESC_F_WRITE } else { 0 };
+            inner.queue.write_descriptor(1, Descriptor {
+                addr: inner.bounce_phys.as_u64(),
+                len: len as u32,
+                flags: data_flags | DESC_F_NEXT,
+                next: 2,
+            });
+            inner.queue.write_descriptor(2, status);
+        } else {
+            inner.queue.write_descriptor(1, status);
+        }
+
+        self.irq_fired.store(false, Ordering::Release);
+        inner.queue.push_avail(0);
+        fence(Ordering::SeqCst);
+        self.transport.notify(self.notify_offset);
+
+        let mut completed = false;
+        for _ in 0..TIMEOUT {
+            if self.irq_fired.load(Ordering::Acquire) || inner.queue.used_idx() != inner.queue.last_used {
+                if inner.queue.pop_used() {
+                    completed = true;
+                    break;
+                }
+            }
+            core::hint::spin_loop();
+        }
+        if !completed {
+            return Err("timeout na requisição virtio-blk");
+        }
+
+        match unsafe { read_volatile((inner.request_virt + 16u64).as_ptr::<u8>()) } {
+            0 => Ok(()),
+            2 => Err("operação virtio-blk não suportada"),
+            _ => Err("erro de E/S no virtio-blk"),
+        }
+    }
+
+    fn check_range(&self, lba: u64, len: usize) -> Result<(), &'static str> {
+        if len % SECTOR_SIZE != 0 {
+            return Err("buffer não é múltiplo do setor");
+        }
+        if !lba.checked_add((len / SECTOR_SIZE) as u64).is_some_and(|end| end <= self.sectors) {
+            return Err("LBA fora do disco");
+        }
+        Ok(())
+    }
+}
+
+impl BlockDevice for VirtioBlk {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sector_count(&self) -> u64 {
+        self.sectors
+    }
+
+    fn read_sectors(&self, lba: u64, buf: &mut [u8]) -> Result<(), &'static str> {
+        self.check_range(lba, buf.len())?;
+        let mut inner = self.inner.lock();
+        let chunk_size = BOUNCE_PAGES * 4096;
+
+        for (i, chunk) in buf.chunks_mut(chunk_size).enumerate() {
+            let sector = lba + (i * chunk_size / SECTOR_SIZE) as u64;
+            self.submit(&mut inner, REQ_IN, sector, chunk.len())?;
+            unsafe {
+                core::ptr::copy_nonoverlapping(inner.bounce_virt.as_ptr::<u8>(), chunk.as_mut_ptr(), chunk.len());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_sectors(&self, lba: u64, buf: &[u8]) -> Result<(), &'static str> {
+        if self.read_only {
+            return Err("disco virtio somente leitura");
+        }
+        self.check_range(lba, buf.len())?;
+        let mut inner = self.inner.lock();
+        let chunk_size = BOUNCE_PAGES * 4096;
+
+        for (i, chunk) in buf.chunks(chunk_size).enumerate() {
+            let sector = lba + (i * chunk_size / SECTOR_SIZE) as u64;
+            unsafe {
+                core::ptr::copy_nonoverlapping(chunk.as_ptr(), inner.bounce_virt.as_mut_ptr::<u8>(), chunk.len());
+            }
+            self.submit(&mut inner, REQ_OUT, sector, chunk.len())?;
+        }
+
+        if self.has_flush {
+            self.submit(&mut inner, REQ_FLUSH, 0, 0)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Dispositivos inicializados, consultados pelo handler de IRQ
+static DEVICES: IrqSpinLock<Vec<&'static VirtioBlk>> = IrqSpinLock::named("virtio_devices", Vec::new());
+
+fn irq_handler() {
+    for dev in DEVICES.lock().iter() {
+        dev.handle_irq();
+    }
+}
+
+/// Procura discos virtio-blk no barramento PCI e os registra como vda, vdb...
+pub fn init() {
+    let found = pci::find(VIRTIO_VENDOR, &[DEVICE_LEGACY_BLK, DEVICE_MODERN_BLK]);
+
+    for (i, dev) in found.iter().enumerate() {
+        let name = format!("vd{}", (b'a' + i as u8) as char);
+
+        match VirtioBlk::init(&name, dev) {
+            Ok(disk) => {
+                let disk: &'static VirtioBlk = Box::leak(Box::new(disk));
+                vga_println!(
+                    "virtio-blk {}: {} setores ({}, IRQ {})",
+                    name,
+                    disk.sectors,
+                    if disk.transport.is_modern() { "moderno" } else { "legado" },
+                    dev.interrupt_line
+                );
+                DEVICES.lock().push(disk);
+                // Sem IRQ utilizável o driver continua funcionando por polling
+                if !crate::interrupts::register_irq_handler(dev.interrupt_line, irq_handler) {
+                    vga_println!("virtio-blk {}: IRQ {} sem handler, usando polling", name, dev.interrupt_line);
+                }
+                block::register(disk);
+            }
+            Err(e) => vga_println!("virtio-blk {}: {}", name, e),
+        }
+    }
+}